@@ -3,6 +3,7 @@ use std::time::Duration;
 
 use parser::SyntaxConfig;
 use regex::Regex;
+use rustc_hash::FxHashSet;
 
 #[derive(Debug)]
 pub struct Config {
@@ -22,8 +23,46 @@ pub struct BuildConfig {
     pub args: Vec<String>,
     pub on_save: bool,
     pub forward_search_after: bool,
+    /// The directory of the output document, relative to the base directory of the project.
     pub output_dir: String,
+    /// The directory of the auxiliary files like `.aux` and `.log`.
+    /// The output directory is used if it is not configured.
+    pub aux_dir: Option<String>,
     pub output_filename: Option<PathBuf>,
+    pub engine_flags: EngineFlags,
+    pub recipes: Vec<BuildRecipe>,
+    pub default_recipe: Option<String>,
+    pub continuous: bool,
+    pub continuous_args: Vec<String>,
+}
+
+/// A named sequence of programs that are run one after another to build a document.
+#[derive(Debug)]
+pub struct BuildRecipe {
+    pub name: String,
+    pub steps: Vec<BuildStep>,
+}
+
+#[derive(Debug)]
+pub struct BuildStep {
+    pub name: String,
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Engine {
+    Pdflatex,
+    Xelatex,
+    Lualatex,
+}
+
+/// The arguments that select the TeX engine when running the build program.
+#[derive(Debug)]
+pub struct EngineFlags {
+    pub pdflatex: String,
+    pub xelatex: String,
+    pub lualatex: String,
 }
 
 #[derive(Debug)]
@@ -31,6 +70,7 @@ pub struct DiagnosticsConfig {
     pub allowed_patterns: Vec<Regex>,
     pub ignored_patterns: Vec<Regex>,
     pub chktex: ChktexConfig,
+    pub undefined_commands: UndefinedCommandsConfig,
     pub delay: Duration,
 }
 
@@ -41,6 +81,13 @@ pub struct ChktexConfig {
     pub on_edit: bool,
 }
 
+#[derive(Debug, Default)]
+pub struct UndefinedCommandsConfig {
+    pub enabled: bool,
+    /// Names of commands and environments that are provided by packages unknown to the server.
+    pub allowed: FxHashSet<String>,
+}
+
 #[derive(Debug)]
 pub struct SynctexConfig {
     pub program: String,
@@ -113,11 +160,46 @@ impl Default for BuildConfig {
             on_save: false,
             forward_search_after: false,
             output_dir: String::from("."),
+            aux_dir: None,
             output_filename: None,
+            engine_flags: EngineFlags::default(),
+            recipes: Vec::new(),
+            default_recipe: None,
+            continuous: false,
+            continuous_args: ["-pvc", "-view=none"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
         }
     }
 }
 
+impl Default for EngineFlags {
+    fn default() -> Self {
+        Self {
+            pdflatex: String::from("-pdf"),
+            xelatex: String::from("-xelatex"),
+            lualatex: String::from("-lualatex"),
+        }
+    }
+}
+
+impl EngineFlags {
+    pub fn get(&self, engine: Engine) -> &str {
+        match engine {
+            Engine::Pdflatex => &self.pdflatex,
+            Engine::Xelatex => &self.xelatex,
+            Engine::Lualatex => &self.lualatex,
+        }
+    }
+
+    pub fn find(&self, flag: &str) -> Option<Engine> {
+        [Engine::Pdflatex, Engine::Xelatex, Engine::Lualatex]
+            .into_iter()
+            .find(|engine| self.get(*engine) == flag)
+    }
+}
+
 impl Default for DiagnosticsConfig {
     fn default() -> Self {
         Self {
@@ -125,6 +207,7 @@ impl Default for DiagnosticsConfig {
             ignored_patterns: Vec::new(),
             delay: Duration::from_millis(300),
             chktex: ChktexConfig::default(),
+            undefined_commands: UndefinedCommandsConfig::default(),
         }
     }
 }
@@ -34,6 +34,115 @@ impl<'a> BibtexFieldType<'a> {
     }    
 }
 
+/// The bibliography processor that reads the `.bib` files of a project.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum BibtexDialect {
+    Bibtex,
+    Biblatex,
+}
+
+impl BibtexDialect {
+    pub fn data_model(self) -> &'static BibtexDataModel<'static> {
+        match self {
+            Self::Bibtex => &BIBTEX_DATA_MODEL,
+            Self::Biblatex => &BIBLATEX_DATA_MODEL,
+        }
+    }
+}
+
+/// The entry types and fields that are known to a bibliography processor.
+#[derive(Debug, Clone, Copy)]
+pub struct BibtexDataModel<'a> {
+    pub entry_types: &'a [BibtexEntryModel<'a>],
+    pub entry_type_aliases: &'a [BibtexEntryTypeAlias<'a>],
+    pub fields: &'a [&'a str],
+    /// The fields that can be used with every entry type.
+    pub global_fields: &'a [&'a str],
+    /// Pairs of field aliases and the fields that they stand for.
+    pub field_aliases: &'a [(&'a str, &'a str)],
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BibtexEntryModel<'a> {
+    pub name: &'a str,
+    /// Each group lists alternatives of which at least one field is required (like `year/date`).
+    pub required: &'a [&'a [&'a str]],
+    /// The fields that are used in addition to the required and global ones.
+    /// Entry types without any required or optional fields (like `@customa`)
+    /// have no field model and accept every field.
+    pub optional: &'a [&'a str],
+}
+
+/// An entry type which is mapped to another entry type (like `@phdthesis` to `@thesis`).
+#[derive(Debug, Clone, Copy)]
+pub struct BibtexEntryTypeAlias<'a> {
+    pub name: &'a str,
+    pub target: &'a str,
+    /// The fields that are set implicitly by the alias.
+    pub implied: &'a [&'a str],
+}
+
+impl<'a> BibtexDataModel<'a> {
+    /// Looks up an entry type (case-insensitive) and resolves aliases.
+    /// Returns the model of the entry type and the fields that are implied by an alias.
+    pub fn find_entry_type(&self, name: &str) -> Option<(&BibtexEntryModel<'a>, &'a [&'a str])> {
+        let (name, implied) = self
+            .entry_type_aliases
+            .iter()
+            .find(|alias| alias.name.eq_ignore_ascii_case(name))
+            .map_or((name, &[][..]), |alias| (alias.target, alias.implied));
+
+        let model = self
+            .entry_types
+            .iter()
+            .find(|ty| ty.name.eq_ignore_ascii_case(name))?;
+
+        Some((model, implied))
+    }
+
+    /// Checks if the field (with aliases resolved) is used by the given entry type.
+    pub fn is_field_allowed(&self, entry_type: &BibtexEntryModel, field: &str) -> bool {
+        (entry_type.required.is_empty() && entry_type.optional.is_empty())
+            || self.global_fields.contains(&field)
+            || entry_type.optional.contains(&field)
+            || entry_type
+                .required
+                .iter()
+                .any(|group| group.contains(&field))
+    }
+
+    /// Returns the name of the field that is denoted by the given name (case-insensitive),
+    /// resolving aliases like `journal` to `journaltitle`.
+    pub fn find_field(&self, name: &str) -> Option<&'a str> {
+        self.field_aliases
+            .iter()
+            .find(|(alias, _)| alias.eq_ignore_ascii_case(name))
+            .map(|(_, field)| *field)
+            .or_else(|| {
+                self.fields
+                    .iter()
+                    .copied()
+                    .find(|field| field.eq_ignore_ascii_case(name))
+            })
+    }
+}
+
+pub static BIBTEX_DATA_MODEL: BibtexDataModel<'static> = BibtexDataModel {
+    entry_types: BIBTEX_ENTRY_MODELS,
+    entry_type_aliases: BIBTEX_ENTRY_TYPE_ALIASES,
+    fields: BIBTEX_FIELDS,
+    global_fields: BIBTEX_GLOBAL_FIELDS,
+    field_aliases: &[],
+};
+
+pub static BIBLATEX_DATA_MODEL: BibtexDataModel<'static> = BibtexDataModel {
+    entry_types: BIBLATEX_ENTRY_MODELS,
+    entry_type_aliases: BIBLATEX_ENTRY_TYPE_ALIASES,
+    fields: BIBLATEX_FIELDS,
+    global_fields: BIBLATEX_GLOBAL_FIELDS,
+    field_aliases: BIBLATEX_FIELD_ALIASES,
+};
+
 
 pub static BIBTEX_ENTRY_TYPES: &[BibtexEntryType<'static>] = &[
     BibtexEntryType {
@@ -889,3 +998,602 @@ pub static BIBTEX_FIELD_TYPES: &[BibtexFieldType<'static>] = &[
         documentation: "An alias for `institution`, provided for BibTeX compatibility. The `institution` field is used by traditional BibTeX for technical reports whereas the `school` field holds the institution associated with theses. The `biblatex` package employs the generic field name `institution` in both cases.",
     }    
 ];
+
+pub static BIBLATEX_ENTRY_MODELS: &[BibtexEntryModel<'static>] = &[
+    BibtexEntryModel {
+        name: "article",
+        required: &[&["author"], &["title"], &["journaltitle"], &["year", "date"]],
+        optional: &[
+            "translator", "annotator", "commentator", "subtitle", "titleaddon", "editor", "editora",
+            "editorb", "editorc", "journalsubtitle", "journaltitleaddon", "issuetitle",
+            "issuesubtitle", "issuetitleaddon", "language", "origlanguage", "series", "volume",
+            "number", "eid", "issue", "month", "pages", "version", "note", "issn", "addendum",
+            "pubstate", "doi", "eprint", "eprintclass", "eprinttype", "url", "urldate",
+        ],
+    },
+    BibtexEntryModel {
+        name: "book",
+        required: &[&["author"], &["title"], &["year", "date"]],
+        optional: &[
+            "editor", "editora", "editorb", "editorc", "translator", "annotator", "commentator",
+            "introduction", "foreword", "afterword", "subtitle", "titleaddon", "maintitle",
+            "mainsubtitle", "maintitleaddon", "language", "origlanguage", "volume", "part",
+            "edition", "volumes", "series", "number", "note", "publisher", "location", "isbn",
+            "eid", "chapter", "pages", "pagetotal", "addendum", "pubstate", "doi", "eprint",
+            "eprintclass", "eprinttype", "url", "urldate",
+        ],
+    },
+    BibtexEntryModel {
+        name: "mvbook",
+        required: &[&["author"], &["title"], &["year", "date"]],
+        optional: &[
+            "editor", "editora", "editorb", "editorc", "translator", "annotator", "commentator",
+            "introduction", "foreword", "afterword", "subtitle", "titleaddon", "language",
+            "origlanguage", "edition", "volumes", "series", "number", "note", "publisher",
+            "location", "isbn", "pagetotal", "addendum", "pubstate", "doi", "eprint", "eprintclass",
+            "eprinttype", "url", "urldate",
+        ],
+    },
+    BibtexEntryModel {
+        name: "inbook",
+        required: &[&["author"], &["title"], &["booktitle"], &["year", "date"]],
+        optional: &[
+            "bookauthor", "editor", "editora", "editorb", "editorc", "translator", "annotator",
+            "commentator", "introduction", "foreword", "afterword", "subtitle", "titleaddon",
+            "maintitle", "mainsubtitle", "maintitleaddon", "booksubtitle", "booktitleaddon",
+            "language", "origlanguage", "volume", "part", "edition", "volumes", "series", "number",
+            "note", "publisher", "location", "isbn", "eid", "chapter", "pages", "addendum",
+            "pubstate", "doi", "eprint", "eprintclass", "eprinttype", "url", "urldate",
+        ],
+    },
+    BibtexEntryModel {
+        name: "bookinbook",
+        required: &[&["author"], &["title"], &["booktitle"], &["year", "date"]],
+        optional: &[
+            "bookauthor", "editor", "editora", "editorb", "editorc", "translator", "annotator",
+            "commentator", "introduction", "foreword", "afterword", "subtitle", "titleaddon",
+            "maintitle", "mainsubtitle", "maintitleaddon", "booksubtitle", "booktitleaddon",
+            "language", "origlanguage", "volume", "part", "edition", "volumes", "series", "number",
+            "note", "publisher", "location", "isbn", "eid", "chapter", "pages", "addendum",
+            "pubstate", "doi", "eprint", "eprintclass", "eprinttype", "url", "urldate",
+        ],
+    },
+    BibtexEntryModel {
+        name: "suppbook",
+        required: &[&["author"], &["title"], &["booktitle"], &["year", "date"]],
+        optional: &[
+            "bookauthor", "editor", "editora", "editorb", "editorc", "translator", "annotator",
+            "commentator", "introduction", "foreword", "afterword", "subtitle", "titleaddon",
+            "maintitle", "mainsubtitle", "maintitleaddon", "booksubtitle", "booktitleaddon",
+            "language", "origlanguage", "volume", "part", "edition", "volumes", "series", "number",
+            "note", "publisher", "location", "isbn", "eid", "chapter", "pages", "addendum",
+            "pubstate", "doi", "eprint", "eprintclass", "eprinttype", "url", "urldate",
+        ],
+    },
+    BibtexEntryModel {
+        name: "booklet",
+        required: &[&["author", "editor"], &["title"], &["year", "date"]],
+        optional: &[
+            "subtitle", "titleaddon", "language", "howpublished", "type", "note", "location", "eid",
+            "chapter", "pages", "pagetotal", "addendum", "pubstate", "doi", "eprint", "eprintclass",
+            "eprinttype", "url", "urldate",
+        ],
+    },
+    BibtexEntryModel {
+        name: "collection",
+        required: &[&["editor"], &["title"], &["year", "date"]],
+        optional: &[
+            "editora", "editorb", "editorc", "translator", "annotator", "commentator",
+            "introduction", "foreword", "afterword", "subtitle", "titleaddon", "maintitle",
+            "mainsubtitle", "maintitleaddon", "language", "origlanguage", "volume", "part",
+            "edition", "volumes", "series", "number", "note", "publisher", "location", "isbn",
+            "eid", "chapter", "pages", "pagetotal", "addendum", "pubstate", "doi", "eprint",
+            "eprintclass", "eprinttype", "url", "urldate",
+        ],
+    },
+    BibtexEntryModel {
+        name: "mvcollection",
+        required: &[&["editor"], &["title"], &["year", "date"]],
+        optional: &[
+            "editora", "editorb", "editorc", "translator", "annotator", "commentator",
+            "introduction", "foreword", "afterword", "subtitle", "titleaddon", "language",
+            "origlanguage", "edition", "volumes", "series", "number", "note", "publisher",
+            "location", "isbn", "pagetotal", "addendum", "pubstate", "doi", "eprint", "eprintclass",
+            "eprinttype", "url", "urldate",
+        ],
+    },
+    BibtexEntryModel {
+        name: "incollection",
+        required: &[&["author"], &["title"], &["booktitle"], &["year", "date"]],
+        optional: &[
+            "editor", "editora", "editorb", "editorc", "translator", "annotator", "commentator",
+            "introduction", "foreword", "afterword", "subtitle", "titleaddon", "maintitle",
+            "mainsubtitle", "maintitleaddon", "booksubtitle", "booktitleaddon", "language",
+            "origlanguage", "volume", "part", "edition", "volumes", "series", "number", "note",
+            "publisher", "location", "isbn", "eid", "chapter", "pages", "addendum", "pubstate",
+            "doi", "eprint", "eprintclass", "eprinttype", "url", "urldate",
+        ],
+    },
+    BibtexEntryModel {
+        name: "suppcollection",
+        required: &[&["author"], &["title"], &["booktitle"], &["year", "date"]],
+        optional: &[
+            "editor", "editora", "editorb", "editorc", "translator", "annotator", "commentator",
+            "introduction", "foreword", "afterword", "subtitle", "titleaddon", "maintitle",
+            "mainsubtitle", "maintitleaddon", "booksubtitle", "booktitleaddon", "language",
+            "origlanguage", "volume", "part", "edition", "volumes", "series", "number", "note",
+            "publisher", "location", "isbn", "eid", "chapter", "pages", "addendum", "pubstate",
+            "doi", "eprint", "eprintclass", "eprinttype", "url", "urldate",
+        ],
+    },
+    BibtexEntryModel {
+        name: "dataset",
+        required: &[&["author", "editor"], &["title"], &["year", "date"]],
+        optional: &[
+            "subtitle", "titleaddon", "language", "edition", "type", "series", "number", "version",
+            "note", "organization", "publisher", "location", "addendum", "pubstate", "doi",
+            "eprint", "eprintclass", "eprinttype", "url", "urldate",
+        ],
+    },
+    BibtexEntryModel {
+        name: "manual",
+        required: &[&["author", "editor"], &["title"], &["year", "date"]],
+        optional: &[
+            "subtitle", "titleaddon", "language", "edition", "type", "series", "number", "version",
+            "note", "organization", "publisher", "location", "isbn", "eid", "chapter", "pages",
+            "pagetotal", "addendum", "pubstate", "doi", "eprint", "eprintclass", "eprinttype",
+            "url", "urldate",
+        ],
+    },
+    BibtexEntryModel {
+        name: "misc",
+        required: &[&["author", "editor"], &["title"], &["year", "date"]],
+        optional: &[
+            "subtitle", "titleaddon", "language", "howpublished", "type", "version", "note",
+            "organization", "location", "month", "addendum", "pubstate", "doi", "eprint",
+            "eprintclass", "eprinttype", "url", "urldate",
+        ],
+    },
+    BibtexEntryModel {
+        name: "online",
+        required: &[
+            &["author", "editor"],
+            &["title"],
+            &["year", "date"],
+            &["doi", "eprint", "url"],
+        ],
+        optional: &[
+            "subtitle", "titleaddon", "language", "version", "note", "organization", "month",
+            "addendum", "pubstate", "eprintclass", "eprinttype", "urldate",
+        ],
+    },
+    BibtexEntryModel {
+        name: "patent",
+        required: &[&["author"], &["title"], &["number"], &["year", "date"]],
+        optional: &[
+            "holder", "subtitle", "titleaddon", "type", "version", "location", "note", "month",
+            "addendum", "pubstate", "doi", "eprint", "eprintclass", "eprinttype", "url", "urldate",
+        ],
+    },
+    BibtexEntryModel {
+        name: "periodical",
+        required: &[&["editor"], &["title"], &["year", "date"]],
+        optional: &[
+            "editora", "editorb", "editorc", "subtitle", "titleaddon", "issuetitle",
+            "issuesubtitle", "issuetitleaddon", "language", "series", "volume", "number", "issue",
+            "month", "note", "issn", "addendum", "pubstate", "doi", "eprint", "eprintclass",
+            "eprinttype", "url", "urldate",
+        ],
+    },
+    BibtexEntryModel {
+        name: "suppperiodical",
+        required: &[&["author"], &["title"], &["journaltitle"], &["year", "date"]],
+        optional: &[
+            "translator", "annotator", "commentator", "subtitle", "titleaddon", "editor", "editora",
+            "editorb", "editorc", "journalsubtitle", "journaltitleaddon", "issuetitle",
+            "issuesubtitle", "issuetitleaddon", "language", "origlanguage", "series", "volume",
+            "number", "eid", "issue", "month", "pages", "version", "note", "issn", "addendum",
+            "pubstate", "doi", "eprint", "eprintclass", "eprinttype", "url", "urldate",
+        ],
+    },
+    BibtexEntryModel {
+        name: "proceedings",
+        required: &[&["title"], &["year", "date"]],
+        optional: &[
+            "editor", "subtitle", "titleaddon", "maintitle", "mainsubtitle", "maintitleaddon",
+            "eventtitle", "eventtitleaddon", "eventdate", "venue", "language", "volume", "part",
+            "volumes", "series", "number", "note", "organization", "publisher", "location", "month",
+            "isbn", "eid", "chapter", "pages", "pagetotal", "addendum", "pubstate", "doi", "eprint",
+            "eprintclass", "eprinttype", "url", "urldate",
+        ],
+    },
+    BibtexEntryModel {
+        name: "mvproceedings",
+        required: &[&["title"], &["year", "date"]],
+        optional: &[
+            "editor", "subtitle", "titleaddon", "eventtitle", "eventtitleaddon", "eventdate",
+            "venue", "language", "volumes", "series", "number", "note", "organization", "publisher",
+            "location", "month", "isbn", "pagetotal", "addendum", "pubstate", "doi", "eprint",
+            "eprintclass", "eprinttype", "url", "urldate",
+        ],
+    },
+    BibtexEntryModel {
+        name: "inproceedings",
+        required: &[&["author"], &["title"], &["booktitle"], &["year", "date"]],
+        optional: &[
+            "editor", "subtitle", "titleaddon", "maintitle", "mainsubtitle", "maintitleaddon",
+            "booksubtitle", "booktitleaddon", "eventtitle", "eventtitleaddon", "eventdate", "venue",
+            "language", "volume", "part", "volumes", "series", "number", "note", "organization",
+            "publisher", "location", "month", "isbn", "eid", "chapter", "pages", "addendum",
+            "pubstate", "doi", "eprint", "eprintclass", "eprinttype", "url", "urldate",
+        ],
+    },
+    BibtexEntryModel {
+        name: "reference",
+        required: &[&["editor"], &["title"], &["year", "date"]],
+        optional: &[
+            "editora", "editorb", "editorc", "translator", "annotator", "commentator",
+            "introduction", "foreword", "afterword", "subtitle", "titleaddon", "maintitle",
+            "mainsubtitle", "maintitleaddon", "language", "origlanguage", "volume", "part",
+            "edition", "volumes", "series", "number", "note", "publisher", "location", "isbn",
+            "eid", "chapter", "pages", "pagetotal", "addendum", "pubstate", "doi", "eprint",
+            "eprintclass", "eprinttype", "url", "urldate",
+        ],
+    },
+    BibtexEntryModel {
+        name: "mvreference",
+        required: &[&["editor"], &["title"], &["year", "date"]],
+        optional: &[
+            "editora", "editorb", "editorc", "translator", "annotator", "commentator",
+            "introduction", "foreword", "afterword", "subtitle", "titleaddon", "language",
+            "origlanguage", "edition", "volumes", "series", "number", "note", "publisher",
+            "location", "isbn", "pagetotal", "addendum", "pubstate", "doi", "eprint", "eprintclass",
+            "eprinttype", "url", "urldate",
+        ],
+    },
+    BibtexEntryModel {
+        name: "inreference",
+        required: &[&["author"], &["title"], &["booktitle"], &["year", "date"]],
+        optional: &[
+            "editor", "editora", "editorb", "editorc", "translator", "annotator", "commentator",
+            "introduction", "foreword", "afterword", "subtitle", "titleaddon", "maintitle",
+            "mainsubtitle", "maintitleaddon", "booksubtitle", "booktitleaddon", "language",
+            "origlanguage", "volume", "part", "edition", "volumes", "series", "number", "note",
+            "publisher", "location", "isbn", "eid", "chapter", "pages", "addendum", "pubstate",
+            "doi", "eprint", "eprintclass", "eprinttype", "url", "urldate",
+        ],
+    },
+    BibtexEntryModel {
+        name: "report",
+        required: &[&["author"], &["title"], &["type"], &["institution"], &["year", "date"]],
+        optional: &[
+            "subtitle", "titleaddon", "language", "number", "version", "note", "location", "month",
+            "isrn", "eid", "chapter", "pages", "pagetotal", "addendum", "pubstate", "doi", "eprint",
+            "eprintclass", "eprinttype", "url", "urldate",
+        ],
+    },
+    BibtexEntryModel {
+        name: "set",
+        required: &[&["entryset"]],
+        optional: &[],
+    },
+    BibtexEntryModel {
+        name: "software",
+        required: &[&["author", "editor"], &["title"], &["year", "date"]],
+        optional: &[
+            "subtitle", "titleaddon", "language", "howpublished", "type", "version", "note",
+            "organization", "location", "month", "addendum", "pubstate", "doi", "eprint",
+            "eprintclass", "eprinttype", "url", "urldate",
+        ],
+    },
+    BibtexEntryModel {
+        name: "thesis",
+        required: &[&["author"], &["title"], &["type"], &["institution"], &["year", "date"]],
+        optional: &[
+            "subtitle", "titleaddon", "language", "note", "location", "month", "isbn", "eid",
+            "chapter", "pages", "pagetotal", "addendum", "pubstate", "doi", "eprint", "eprintclass",
+            "eprinttype", "url", "urldate",
+        ],
+    },
+    BibtexEntryModel {
+        name: "unpublished",
+        required: &[&["author"], &["title"], &["year", "date"]],
+        optional: &[
+            "subtitle", "titleaddon", "type", "eventtitle", "eventtitleaddon", "eventdate", "venue",
+            "language", "howpublished", "note", "location", "isbn", "month", "addendum", "pubstate",
+            "doi", "eprint", "eprintclass", "eprinttype", "url", "urldate",
+        ],
+    },
+    BibtexEntryModel {
+        name: "xdata",
+        required: &[],
+        optional: &[],
+    },
+    BibtexEntryModel {
+        name: "customa",
+        required: &[],
+        optional: &[],
+    },
+    BibtexEntryModel {
+        name: "customb",
+        required: &[],
+        optional: &[],
+    },
+    BibtexEntryModel {
+        name: "customc",
+        required: &[],
+        optional: &[],
+    },
+    BibtexEntryModel {
+        name: "customd",
+        required: &[],
+        optional: &[],
+    },
+    BibtexEntryModel {
+        name: "custome",
+        required: &[],
+        optional: &[],
+    },
+    BibtexEntryModel {
+        name: "customf",
+        required: &[],
+        optional: &[],
+    },
+    BibtexEntryModel {
+        name: "artwork",
+        required: &[],
+        optional: &[],
+    },
+    BibtexEntryModel {
+        name: "audio",
+        required: &[],
+        optional: &[],
+    },
+    BibtexEntryModel {
+        name: "bibnote",
+        required: &[],
+        optional: &[],
+    },
+    BibtexEntryModel {
+        name: "commentary",
+        required: &[],
+        optional: &[],
+    },
+    BibtexEntryModel {
+        name: "image",
+        required: &[],
+        optional: &[],
+    },
+    BibtexEntryModel {
+        name: "jurisdiction",
+        required: &[],
+        optional: &[],
+    },
+    BibtexEntryModel {
+        name: "legislation",
+        required: &[],
+        optional: &[],
+    },
+    BibtexEntryModel {
+        name: "legal",
+        required: &[],
+        optional: &[],
+    },
+    BibtexEntryModel {
+        name: "letter",
+        required: &[],
+        optional: &[],
+    },
+    BibtexEntryModel {
+        name: "movie",
+        required: &[],
+        optional: &[],
+    },
+    BibtexEntryModel {
+        name: "music",
+        required: &[],
+        optional: &[],
+    },
+    BibtexEntryModel {
+        name: "performance",
+        required: &[],
+        optional: &[],
+    },
+    BibtexEntryModel {
+        name: "review",
+        required: &[],
+        optional: &[],
+    },
+    BibtexEntryModel {
+        name: "standard",
+        required: &[],
+        optional: &[],
+    },
+    BibtexEntryModel {
+        name: "video",
+        required: &[],
+        optional: &[],
+    },
+];
+
+pub static BIBLATEX_ENTRY_TYPE_ALIASES: &[BibtexEntryTypeAlias<'static>] = &[
+    BibtexEntryTypeAlias {
+        name: "conference",
+        target: "inproceedings",
+        implied: &[],
+    },
+    BibtexEntryTypeAlias {
+        name: "electronic",
+        target: "online",
+        implied: &[],
+    },
+    BibtexEntryTypeAlias {
+        name: "mastersthesis",
+        target: "thesis",
+        implied: &["type"],
+    },
+    BibtexEntryTypeAlias {
+        name: "phdthesis",
+        target: "thesis",
+        implied: &["type"],
+    },
+    BibtexEntryTypeAlias {
+        name: "techreport",
+        target: "report",
+        implied: &["type"],
+    },
+    BibtexEntryTypeAlias {
+        name: "www",
+        target: "online",
+        implied: &[],
+    },
+];
+
+pub static BIBLATEX_FIELDS: &[&str] = &[
+    "abstract", "addendum", "afterword", "annotation", "annotator", "author", "authortype",
+    "bookauthor", "bookpagination", "booksubtitle", "booktitle", "booktitleaddon", "chapter",
+    "commentator", "date", "doi", "edition", "editor", "editora", "editorb", "editorc",
+    "editortype", "editoratype", "editorbtype", "editorctype", "eid", "entrysubtype", "eprint",
+    "eprintclass", "eprinttype", "eventdate", "eventtitle", "eventtitleaddon", "file", "foreword",
+    "holder", "howpublished", "indextitle", "institution", "introduction", "isan", "isbn", "ismn",
+    "isrn", "issn", "issue", "issuesubtitle", "issuetitle", "iswc", "journalsubtitle",
+    "journaltitle", "label", "language", "library", "location", "mainsubtitle", "maintitle",
+    "maintitleaddon", "month", "nameaddon", "note", "number", "organization", "origdate",
+    "origlanguage", "origlocation", "origpublisher", "origtitle", "pages", "pagetotal",
+    "pagination", "part", "publisher", "pubstate", "reprinttitle", "series", "shortauthor",
+    "shorteditor", "shorthand", "shorthandintro", "shortjournal", "shortseries", "shorttitle",
+    "subtitle", "title", "titleaddon", "translator", "type", "url", "urldate", "venue", "version",
+    "volume", "volumes", "year", "crossref", "entryset", "execute", "gender", "langid",
+    "langidopts", "ids", "indexsorttitle", "keywords", "options", "presort", "related",
+    "relatedoptions", "relatedtype", "relatedstring", "sortkey", "sortname", "sortshorthand",
+    "sorttitle", "sortyear", "xdata", "xref", "namea", "nameb", "namec", "nameatype", "namebtype",
+    "namectype", "lista", "listb", "listc", "listd", "liste", "listf", "usera", "userb", "userc",
+    "userd", "usere", "userf", "verba", "verbb", "verbc", "issuetitleaddon", "journaltitleaddon",
+];
+
+pub static BIBLATEX_GLOBAL_FIELDS: &[&str] = &[
+    "abstract", "annotation", "authortype", "bookpagination", "crossref", "date", "doi", "entryset",
+    "entrysubtype", "eprint", "eprintclass", "eprinttype", "execute", "file", "gender", "ids",
+    "indexsorttitle", "indextitle", "isan", "ismn", "iswc", "keywords", "label", "langid",
+    "langidopts", "library", "lista", "listb", "listc", "listd", "liste", "listf", "month",
+    "namea", "nameb", "namec", "nameatype", "namebtype", "namectype", "options", "pagination",
+    "presort", "related", "relatedoptions", "relatedstring", "relatedtype", "shortauthor",
+    "shorteditor", "shorthand", "shorthandintro", "shortjournal", "shortseries", "shorttitle",
+    "sortkey", "sortname", "sortshorthand", "sorttitle", "sortyear", "url", "urldate", "usera",
+    "userb", "userc", "userd", "usere", "userf", "verba", "verbb", "verbc", "xdata", "xref", "year",
+];
+
+pub static BIBLATEX_FIELD_ALIASES: &[(&str, &str)] = &[
+    ("address", "location"),
+    ("annote", "annotation"),
+    ("archiveprefix", "eprinttype"),
+    ("journal", "journaltitle"),
+    ("key", "sortkey"),
+    ("pdf", "file"),
+    ("primaryclass", "eprintclass"),
+    ("school", "institution"),
+];
+
+pub static BIBTEX_ENTRY_MODELS: &[BibtexEntryModel<'static>] = &[
+    BibtexEntryModel {
+        name: "article",
+        required: &[&["author"], &["title"], &["journal"], &["year"]],
+        optional: &["volume", "number", "pages", "month", "note"],
+    },
+    BibtexEntryModel {
+        name: "book",
+        required: &[&["author", "editor"], &["title"], &["publisher"], &["year"]],
+        optional: &["volume", "number", "series", "address", "edition", "month", "note"],
+    },
+    BibtexEntryModel {
+        name: "booklet",
+        required: &[&["title"]],
+        optional: &["author", "howpublished", "address", "month", "year", "note"],
+    },
+    BibtexEntryModel {
+        name: "inbook",
+        required: &[
+            &["author", "editor"],
+            &["title"],
+            &["chapter", "pages"],
+            &["publisher"],
+            &["year"],
+        ],
+        optional: &["volume", "number", "series", "type", "address", "edition", "month", "note"],
+    },
+    BibtexEntryModel {
+        name: "incollection",
+        required: &[&["author"], &["title"], &["booktitle"], &["publisher"], &["year"]],
+        optional: &[
+            "editor", "volume", "number", "series", "type", "chapter", "pages", "address",
+            "edition", "month", "note",
+        ],
+    },
+    BibtexEntryModel {
+        name: "inproceedings",
+        required: &[&["author"], &["title"], &["booktitle"], &["year"]],
+        optional: &[
+            "editor", "volume", "number", "series", "pages", "address", "month", "organization",
+            "publisher", "note",
+        ],
+    },
+    BibtexEntryModel {
+        name: "manual",
+        required: &[&["title"]],
+        optional: &["author", "organization", "address", "edition", "month", "year", "note"],
+    },
+    BibtexEntryModel {
+        name: "mastersthesis",
+        required: &[&["author"], &["title"], &["school"], &["year"]],
+        optional: &["type", "address", "month", "note"],
+    },
+    BibtexEntryModel {
+        name: "misc",
+        required: &[],
+        optional: &["author", "title", "howpublished", "month", "year", "note"],
+    },
+    BibtexEntryModel {
+        name: "phdthesis",
+        required: &[&["author"], &["title"], &["school"], &["year"]],
+        optional: &["type", "address", "month", "note"],
+    },
+    BibtexEntryModel {
+        name: "proceedings",
+        required: &[&["title"], &["year"]],
+        optional: &[
+            "editor", "volume", "number", "series", "address", "month", "organization", "publisher",
+            "note",
+        ],
+    },
+    BibtexEntryModel {
+        name: "techreport",
+        required: &[&["author"], &["title"], &["institution"], &["year"]],
+        optional: &["type", "number", "address", "month", "note"],
+    },
+    BibtexEntryModel {
+        name: "unpublished",
+        required: &[&["author"], &["title"], &["note"]],
+        optional: &["month", "year"],
+    },
+];
+
+pub static BIBTEX_ENTRY_TYPE_ALIASES: &[BibtexEntryTypeAlias<'static>] = &[
+    BibtexEntryTypeAlias {
+        name: "conference",
+        target: "inproceedings",
+        implied: &[],
+    },
+];
+
+pub static BIBTEX_FIELDS: &[&str] = &[
+    "address", "annote", "author", "booktitle", "chapter", "crossref", "edition", "editor",
+    "howpublished", "institution", "journal", "key", "month", "note", "number", "organization",
+    "pages", "publisher", "school", "series", "title", "type", "volume", "year", "abstract", "doi",
+    "eid", "eprint", "isbn", "issn", "keywords", "language", "url",
+];
+
+/// Besides `crossref` and `key`, contains the fields of common extensions (like `doi`).
+pub static BIBTEX_GLOBAL_FIELDS: &[&str] = &[
+    "abstract", "annote", "crossref", "doi", "eid", "eprint", "isbn", "issn", "key", "keywords",
+    "language", "url",
+];
@@ -1,7 +1,8 @@
 use std::path::PathBuf;
 
 use distro::Language;
-use syntax::{bibtex, latex, BuildError};
+use rowan::{TextLen, TextRange};
+use syntax::{bibtex, latex, BibLogError, BuildError, FileList};
 use url::Url;
 
 use crate::{
@@ -52,47 +53,94 @@ impl Document {
         };
 
         let line_index = LineIndex::new(&text);
+        let data = Self::parse_data(&text, params.language, params.config);
 
-        let data = match params.language {
+        let document = Self {
+            uri,
+            dir,
+            path,
+            text,
+            line_index,
+            owner: params.owner,
+            cursor: params.cursor,
+            language: params.language,
+            data,
+        };
+
+        document
+    }
+
+    /// Replaces the text in `delete` with `insert` and updates the document
+    /// by reusing the unchanged parts of the previous syntax tree if possible.
+    pub fn edit(&mut self, delete: TextRange, insert: &str, config: &Config) {
+        self.text
+            .replace_range(std::ops::Range::<usize>::from(delete), insert);
+
+        self.line_index.edit(&self.text, delete, insert.text_len());
+
+        if let DocumentData::Tex(data) = &mut self.data {
+            if let Some((green, changed)) =
+                parser::reparse_latex(&data.green, delete, insert, &config.syntax)
+            {
+                let old_root = data.root_node();
+                data.green = green;
+                if !data
+                    .semantics
+                    .process_edit(&old_root, &data.root_node(), changed)
+                {
+                    data.semantics = semantics::tex::Semantics::default();
+                    data.semantics.process_root(&data.root_node());
+                }
+
+                return;
+            }
+        }
+
+        self.data = Self::parse_data(&self.text, self.language, config);
+    }
+
+    fn parse_data(text: &str, language: Language, config: &Config) -> DocumentData {
+        match language {
             Language::Tex => {
-                let green = parser::parse_latex(&text, &params.config.syntax);
+                let green = parser::parse_latex(text, &config.syntax);
                 let mut semantics = semantics::tex::Semantics::default();
                 semantics.process_root(&latex::SyntaxNode::new_root(green.clone()));
                 DocumentData::Tex(TexDocumentData { green, semantics })
             }
             Language::Bib => {
-                let green = parser::parse_bibtex(&text);
+                let green = parser::parse_bibtex(text);
                 let mut semantics = semantics::bib::Semantics::default();
                 semantics.process_root(&bibtex::SyntaxNode::new_root(green.clone()));
                 DocumentData::Bib(BibDocumentData { green, semantics })
             }
             Language::Aux => {
-                let green = parser::parse_latex(&text, &params.config.syntax);
+                let green = parser::parse_latex(text, &config.syntax);
                 let mut semantics = semantics::auxiliary::Semantics::default();
                 semantics.process_root(&latex::SyntaxNode::new_root(green.clone()));
                 DocumentData::Aux(AuxDocumentData { green, semantics })
             }
             Language::Log => {
-                let errors = parser::parse_build_log(&text).errors;
+                let errors = parser::parse_build_log(text).errors;
                 DocumentData::Log(LogDocumentData { errors })
             }
+            Language::Blg => {
+                let errors = parser::parse_bib_log(text).errors;
+                DocumentData::Blg(BlgDocumentData { errors })
+            }
+            Language::Fls => {
+                let file_list = parser::parse_file_list(text);
+                DocumentData::Fls(FlsDocumentData { file_list })
+            }
             Language::Root => DocumentData::Root,
-            Language::Tectonic => DocumentData::Tectonic,
-        };
-
-        let document = Self {
-            uri,
-            dir,
-            path,
-            text,
-            line_index,
-            owner: params.owner,
-            cursor: params.cursor,
-            language: params.language,
-            data,
-        };
-
-        document
+            Language::Tectonic => {
+                let semantics = semantics::tectonic::Semantics::parse(text);
+                DocumentData::Tectonic(TectonicDocumentData { semantics })
+            }
+            Language::Latexmkrc => {
+                let semantics = semantics::latexmkrc::Semantics::parse(text);
+                DocumentData::Latexmkrc(LatexmkrcDocumentData { semantics })
+            }
+        }
     }
 }
 
@@ -134,8 +182,11 @@ pub enum DocumentData {
     Bib(BibDocumentData),
     Aux(AuxDocumentData),
     Log(LogDocumentData),
+    Blg(BlgDocumentData),
+    Fls(FlsDocumentData),
     Root,
-    Tectonic,
+    Tectonic(TectonicDocumentData),
+    Latexmkrc(LatexmkrcDocumentData),
 }
 
 impl DocumentData {
@@ -170,6 +221,38 @@ impl DocumentData {
             None
         }
     }
+
+    pub fn as_tectonic(&self) -> Option<&TectonicDocumentData> {
+        if let DocumentData::Tectonic(data) = self {
+            Some(data)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_latexmkrc(&self) -> Option<&LatexmkrcDocumentData> {
+        if let DocumentData::Latexmkrc(data) = self {
+            Some(data)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_blg(&self) -> Option<&BlgDocumentData> {
+        if let DocumentData::Blg(data) = self {
+            Some(data)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_fls(&self) -> Option<&FlsDocumentData> {
+        if let DocumentData::Fls(data) = self {
+            Some(data)
+        } else {
+            None
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -201,6 +284,26 @@ pub struct LogDocumentData {
     pub errors: Vec<BuildError>,
 }
 
+#[derive(Debug, Clone)]
+pub struct BlgDocumentData {
+    pub errors: Vec<BibLogError>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FlsDocumentData {
+    pub file_list: FileList,
+}
+
+#[derive(Debug, Clone)]
+pub struct TectonicDocumentData {
+    pub semantics: semantics::tectonic::Semantics,
+}
+
+#[derive(Debug, Clone)]
+pub struct LatexmkrcDocumentData {
+    pub semantics: semantics::latexmkrc::Semantics,
+}
+
 #[derive(Debug, Clone)]
 pub struct AuxDocumentData {
     pub green: rowan::GreenNode,
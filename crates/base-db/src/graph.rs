@@ -4,20 +4,36 @@ use distro::Language;
 use itertools::Itertools;
 use once_cell::sync::Lazy;
 use percent_encoding::percent_decode_str;
-use rustc_hash::FxHashSet;
+use rustc_hash::{FxHashMap, FxHashSet};
 use url::Url;
 
 use crate::{semantics, Document, DocumentData, Workspace};
 
+/// The extensions that `graphicx` tries with the common drivers if the path has none.
+const GRAPHICS_EXTENSIONS: &[&str] = &["pdf", "png", "jpg", "jpeg", "mps", "eps", "ps"];
+
 pub static HOME_DIR: Lazy<Option<PathBuf>> = Lazy::new(dirs::home_dir);
 
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
 pub struct Edge<'a> {
     pub source: &'a Document,
     pub target: &'a Document,
+    pub kind: EdgeKind,
     pub weight: Option<EdgeWeight<'a>>,
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum EdgeKind {
+    /// The target is referenced by a command like `\input` in the source.
+    Link,
+    /// The target is a file generated when compiling the source.
+    Artifact,
+    /// The target has been read by a TeX run according to the `.fls` file of the source.
+    Recorder,
+    /// The target is one of the inputs listed in a `Tectonic.toml` file.
+    Manifest,
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
 pub struct EdgeWeight<'a> {
     pub link: &'a semantics::tex::Link,
@@ -25,12 +41,39 @@ pub struct EdgeWeight<'a> {
     pub new_base_dir: Url,
 }
 
+/// A link that does not point to any document of the workspace.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct UnresolvedLink<'a> {
+    pub source: &'a Document,
+    pub link: &'a semantics::tex::Link,
+    /// The locations that have been tried in order.
+    pub candidates: Vec<Url>,
+}
+
+/// An image included with `\includegraphics` that does not exist.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct UnresolvedGraphics<'a> {
+    pub source: &'a Document,
+    pub path: &'a semantics::Span,
+    /// The locations that have been tried in order.
+    pub candidates: Vec<Url>,
+}
+
 #[derive(Debug)]
 pub struct Graph<'a> {
     pub workspace: &'a Workspace,
     pub start: &'a Document,
     pub edges: Vec<Edge<'a>>,
     pub missing: Vec<Url>,
+    pub unresolved: Vec<UnresolvedLink<'a>>,
+    pub unresolved_graphics: Vec<UnresolvedGraphics<'a>>,
+    /// The locations where the included images have been found.
+    pub resolved_graphics: Vec<Url>,
+    /// Files that have been read by a TeX run according to the `.fls` file of the start
+    /// but are not documents (like the code of `\lstinputlisting`).
+    pub assets: Vec<Url>,
+    /// Chains of links that lead back to their first document, in the order they are followed.
+    pub cycles: Vec<Vec<Edge<'a>>>,
 }
 
 impl<'a> Graph<'a> {
@@ -40,26 +83,197 @@ impl<'a> Graph<'a> {
             start,
             edges: Vec::new(),
             missing: Vec::new(),
+            unresolved: Vec::new(),
+            unresolved_graphics: Vec::new(),
+            resolved_graphics: Vec::new(),
+            assets: Vec::new(),
+            cycles: Vec::new(),
         };
 
         let base_dir = workspace.current_dir(&start.dir);
-        let mut stack = vec![(start, base_dir)];
+        let mut stack = vec![(start, base_dir.clone())];
         let mut visited = FxHashSet::default();
+        let mut sources = Vec::new();
+        graph.traverse(&mut stack, &mut visited, &mut sources);
+
+        // Files that are only known from the recorder are added after following the links
+        // so that the files included explicitly keep their edges.
+        graph.recorder_edges(&base_dir, &mut stack, &mut visited);
+        graph.traverse(&mut stack, &mut visited, &mut sources);
+        graph.graphics(&sources);
 
+        let mut cycles = Vec::new();
+        graph.find_cycles(
+            start,
+            &mut Vec::new(),
+            &mut FxHashSet::default(),
+            &mut cycles,
+        );
+        graph.cycles = cycles;
+        graph
+    }
+
+    fn traverse(
+        &mut self,
+        stack: &mut Vec<(&'a Document, Url)>,
+        visited: &mut FxHashSet<&'a Url>,
+        sources: &mut Vec<(&'a Document, Url)>,
+    ) {
         while let Some((source, base_dir)) = stack.pop() {
-            let index = graph.edges.len();
-            graph.explicit_edges(source, &base_dir);
-            for edge in &graph.edges[index..] {
-                let Some(weight) = edge.weight.as_ref() else { continue };
+            sources.push((source, base_dir.clone()));
+            let index = self.edges.len();
+            self.explicit_edges(source, &base_dir);
+            for edge in &self.edges[index..] {
+                let new_base_dir = match (&edge.weight, edge.kind) {
+                    (Some(weight), _) => weight.new_base_dir.clone(),
+                    (None, EdgeKind::Manifest) => {
+                        let Ok(src_dir) = edge.source.dir.join("src/") else { continue };
+                        src_dir
+                    }
+                    (None, _) => continue,
+                };
+
                 if visited.insert(&edge.target.uri) {
-                    stack.push((edge.target, weight.new_base_dir.clone()));
+                    stack.push((edge.target, new_base_dir));
                 }
             }
 
-            graph.implicit_edges(source, &base_dir);
+            self.implicit_edges(source, &base_dir);
         }
+    }
 
-        graph
+    /// Looks up the images of every document relative to its base directory
+    /// and the `\graphicspath` of the whole graph.
+    /// The images are not part of the workspace, so they are checked on the file system
+    /// when the graph is built. The graph needs to be updated if an image is created or deleted.
+    fn graphics(&mut self, sources: &[(&'a Document, Url)]) {
+        let graphics_paths = self
+            .preorder()
+            .filter_map(|document| document.data.as_tex())
+            .flat_map(|data| data.semantics.graphics_paths.iter())
+            .map(|path| path.text.as_str())
+            .sorted()
+            .dedup();
+
+        let prefixes: Vec<_> = std::iter::once("").chain(graphics_paths).collect();
+
+        for (source, base_dir) in sources {
+            let Some(data) = source.data.as_tex() else { continue };
+            for path in &data.semantics.graphics {
+                let stem = &path.text;
+                let file_names = std::iter::once(stem.clone()).chain(
+                    GRAPHICS_EXTENSIONS
+                        .iter()
+                        .map(|ext| format!("{stem}.{ext}")),
+                );
+
+                let candidates: Vec<_> = prefixes
+                    .iter()
+                    .cartesian_product(file_names)
+                    .filter_map(|(prefix, name)| base_dir.join(&format!("{prefix}{name}")).ok())
+                    .unique()
+                    .collect();
+
+                let exists = |uri: &Url| {
+                    self.workspace.lookup(uri).is_some()
+                        || uri.to_file_path().map_or(false, |path| path.is_file())
+                };
+
+                match candidates.iter().find(|uri| exists(uri)) {
+                    Some(uri) => {
+                        self.resolved_graphics.push(uri.clone());
+                    }
+                    None => {
+                        self.unresolved_graphics.push(UnresolvedGraphics {
+                            source,
+                            path,
+                            candidates,
+                        });
+                    }
+                };
+            }
+        }
+    }
+
+    /// Follows the links in depth-first order to find the ones that point back to a document
+    /// on the current path. LaTeX would include these documents until it runs out of memory.
+    fn find_cycles<'g>(
+        &'g self,
+        source: &'a Document,
+        path: &mut Vec<&'g Edge<'a>>,
+        visited: &mut FxHashSet<&'a Url>,
+        cycles: &mut Vec<Vec<Edge<'a>>>,
+    ) {
+        visited.insert(&source.uri);
+        for edge in self
+            .edges
+            .iter()
+            .filter(|edge| edge.kind == EdgeKind::Link && edge.source == source)
+        {
+            path.push(edge);
+            if let Some(index) = path.iter().position(|other| other.source == edge.target) {
+                cycles.push(path[index..].iter().map(|edge| (*edge).clone()).collect());
+            } else if !visited.contains(&edge.target.uri) {
+                self.find_cycles(edge.target, path, visited, cycles);
+            }
+
+            path.pop();
+        }
+    }
+
+    /// Restores a graph from the cache without resolving the links again.
+    pub(crate) fn from_cache(
+        workspace: &'a Workspace,
+        start: &'a Document,
+        data: &CachedGraph,
+    ) -> Self {
+        let edges = data
+            .edges
+            .iter()
+            .filter_map(|edge| edge.restore(workspace))
+            .collect();
+
+        let unresolved = data
+            .unresolved
+            .iter()
+            .filter_map(|link| {
+                let source = workspace.lookup(&link.source)?;
+                Some(UnresolvedLink {
+                    source,
+                    link: source.data.as_tex()?.semantics.links.get(link.index)?,
+                    candidates: link.candidates.clone(),
+                })
+            })
+            .collect();
+
+        let unresolved_graphics = data
+            .unresolved_graphics
+            .iter()
+            .filter_map(|path| {
+                let source = workspace.lookup(&path.source)?;
+                Some(UnresolvedGraphics {
+                    source,
+                    path: source.data.as_tex()?.semantics.graphics.get(path.index)?,
+                    candidates: path.candidates.clone(),
+                })
+            })
+            .collect();
+
+        Self {
+            workspace,
+            start,
+            edges,
+            missing: data.missing.clone(),
+            unresolved,
+            unresolved_graphics,
+            resolved_graphics: data.resolved_graphics.clone(),
+            assets: data.assets.clone(),
+            cycles: data
+                .cycles
+                .iter()
+                .filter_map(|cycle| cycle.iter().map(|edge| edge.restore(workspace)).collect())
+                .collect(),
+        }
     }
 
     pub fn preorder(&self) -> impl DoubleEndedIterator<Item = &'a Document> + '_ {
@@ -69,9 +283,42 @@ impl<'a> Graph<'a> {
     }
 
     fn explicit_edges(&mut self, source: &'a Document, base_dir: &Url) {
-        let DocumentData::Tex(data) = &source.data else { return };
-        for link in &data.semantics.links {
-            self.explicit_edge(source, base_dir, link);
+        match &source.data {
+            DocumentData::Tex(data) => {
+                for link in &data.semantics.links {
+                    self.explicit_edge(source, base_dir, link);
+                }
+            }
+            DocumentData::Tectonic(data) => {
+                self.manifest_edges(source, &data.semantics);
+            }
+            _ => {}
+        };
+    }
+
+    /// Tectonic looks up the inputs of every output in the `src` directory of the project.
+    fn manifest_edges(&mut self, source: &'a Document, semantics: &semantics::tectonic::Semantics) {
+        let Ok(src_dir) = source.dir.join("src/") else { return };
+        for input in semantics
+            .outputs
+            .iter()
+            .flat_map(|output| output.inputs.iter())
+            .unique()
+        {
+            let Ok(target_uri) = src_dir.join(input) else { continue };
+            match self.workspace.lookup(&target_uri) {
+                Some(target) => {
+                    self.edges.push(Edge {
+                        source,
+                        target,
+                        kind: EdgeKind::Manifest,
+                        weight: None,
+                    });
+                }
+                None => {
+                    self.missing.push(target_uri);
+                }
+            };
         }
     }
 
@@ -98,13 +345,17 @@ impl<'a> Graph<'a> {
             .filter(|path| home_dir.map_or(false, |dir| path.starts_with(dir)))
             .flat_map(Url::from_file_path);
 
-        for target_uri in file_names
+        let candidates: Vec<_> = file_names
             .iter()
             .flat_map(|file_name| base_dir.join(file_name))
             .chain(distro_files)
-        {
-            match self.workspace.lookup(&target_uri) {
+            .collect();
+
+        let mut resolved = false;
+        for target_uri in &candidates {
+            match self.workspace.lookup(target_uri) {
                 Some(target) => {
+                    resolved = true;
                     let new_base_dir = link
                         .base_dir
                         .as_deref()
@@ -120,14 +371,23 @@ impl<'a> Graph<'a> {
                     self.edges.push(Edge {
                         source,
                         target,
+                        kind: EdgeKind::Link,
                         weight,
                     });
                 }
                 None => {
-                    self.missing.push(target_uri);
+                    self.missing.push(target_uri.clone());
                 }
             };
         }
+
+        if !resolved {
+            self.unresolved.push(UnresolvedLink {
+                source,
+                link,
+                candidates,
+            });
+        }
     }
 
     fn implicit_edges(&mut self, source: &'a Document, base_dir: &Url) {
@@ -135,6 +395,68 @@ impl<'a> Graph<'a> {
         if source.language == Language::Tex && !uri.ends_with(".aux") {
             self.implicit_edge(source, base_dir, "log");
             self.implicit_edge(source, base_dir, "aux");
+            self.implicit_edge(source, base_dir, "fls");
+            self.implicit_edge(source, base_dir, "blg");
+        }
+    }
+
+    fn recorder_edges(
+        &mut self,
+        base_dir: &Url,
+        stack: &mut Vec<(&'a Document, Url)>,
+        visited: &mut FxHashSet<&'a Url>,
+    ) {
+        let start = self.start;
+        let file_lists: Vec<_> = self
+            .edges
+            .iter()
+            .filter(|edge| edge.source == start && edge.kind == EdgeKind::Artifact)
+            .filter_map(|edge| edge.target.data.as_fls())
+            .collect();
+
+        for data in file_lists {
+            let file_list = &data.file_list;
+            for path in file_list
+                .inputs
+                .iter()
+                .filter(|path| !file_list.outputs.contains(path))
+            {
+                let Ok(target_uri) = Url::from_file_path(path) else { continue };
+                if target_uri == start.uri || !target_uri.as_str().starts_with(base_dir.as_str()) {
+                    continue;
+                }
+
+                match self.workspace.lookup(&target_uri) {
+                    Some(target) => {
+                        if self.preorder().any(|document| document == target) {
+                            continue;
+                        }
+
+                        self.edges.push(Edge {
+                            source: start,
+                            target,
+                            kind: EdgeKind::Recorder,
+                            weight: None,
+                        });
+
+                        if visited.insert(&target.uri) {
+                            stack.push((target, base_dir.clone()));
+                        }
+                    }
+                    None if matches!(
+                        Language::from_path(path),
+                        Some(Language::Tex | Language::Bib)
+                    ) =>
+                    {
+                        self.missing.push(target_uri);
+                    }
+                    None => {
+                        if !self.assets.contains(&target_uri) {
+                            self.assets.push(target_uri);
+                        }
+                    }
+                };
+            }
         }
     }
 
@@ -145,16 +467,25 @@ impl<'a> Graph<'a> {
                 .as_ref(),
         );
 
+        // Only the root document is affected by the job name, included files keep their own name.
+        let is_root = source.data.as_tex().map_or(false, |data| data.semantics.can_be_root);
+        if is_root && source.uri == self.start.uri {
+            if let Some(jobname) = self.workspace.jobname(source) {
+                path.set_file_name(jobname);
+            }
+        }
+
         path.set_extension(extension);
         let Some(target_uri) = path.file_name()
             .and_then(OsStr::to_str)
-            .and_then(|name| self.workspace.output_dir(base_dir).join(name).ok()) else { return };
+            .and_then(|name| self.workspace.aux_dir(base_dir).join(name).ok()) else { return };
 
         match self.workspace.lookup(&target_uri) {
             Some(target) => {
                 self.edges.push(Edge {
                     source,
                     target,
+                    kind: EdgeKind::Artifact,
                     weight: None,
                 });
             }
@@ -164,3 +495,270 @@ impl<'a> Graph<'a> {
         }
     }
 }
+
+#[derive(Debug, Clone)]
+struct CachedEdge {
+    source: Url,
+    target: Url,
+    kind: EdgeKind,
+    weight: Option<CachedEdgeWeight>,
+}
+
+#[derive(Debug, Clone)]
+struct CachedEdgeWeight {
+    link: usize,
+    old_base_dir: Url,
+    new_base_dir: Url,
+}
+
+impl CachedEdge {
+    fn new(edge: &Edge) -> Self {
+        Self {
+            source: edge.source.uri.clone(),
+            target: edge.target.uri.clone(),
+            kind: edge.kind,
+            weight: edge.weight.as_ref().map(|weight| CachedEdgeWeight {
+                link: link_index(edge.source, weight.link),
+                old_base_dir: weight.old_base_dir.clone(),
+                new_base_dir: weight.new_base_dir.clone(),
+            }),
+        }
+    }
+
+    fn restore<'a>(&self, workspace: &'a Workspace) -> Option<Edge<'a>> {
+        let source = workspace.lookup(&self.source)?;
+        let target = workspace.lookup(&self.target)?;
+        let weight = match &self.weight {
+            Some(weight) => Some(EdgeWeight {
+                link: source.data.as_tex()?.semantics.links.get(weight.link)?,
+                old_base_dir: weight.old_base_dir.clone(),
+                new_base_dir: weight.new_base_dir.clone(),
+            }),
+            None => None,
+        };
+
+        Some(Edge {
+            source,
+            target,
+            kind: self.kind,
+            weight,
+        })
+    }
+}
+
+/// Refers to a link or image of the source by its index.
+#[derive(Debug, Clone)]
+struct CachedUnresolvedLink {
+    source: Url,
+    index: usize,
+    candidates: Vec<Url>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct CachedGraph {
+    edges: Vec<CachedEdge>,
+    missing: Vec<Url>,
+    unresolved: Vec<CachedUnresolvedLink>,
+    unresolved_graphics: Vec<CachedUnresolvedLink>,
+    resolved_graphics: Vec<Url>,
+    assets: Vec<Url>,
+    cycles: Vec<Vec<CachedEdge>>,
+    preorder: Vec<Url>,
+}
+
+impl<'a> From<&Graph<'a>> for CachedGraph {
+    fn from(graph: &Graph<'a>) -> Self {
+        let edges = graph.edges.iter().map(CachedEdge::new).collect();
+
+        let unresolved = graph
+            .unresolved
+            .iter()
+            .map(|link| CachedUnresolvedLink {
+                source: link.source.uri.clone(),
+                index: link_index(link.source, link.link),
+                candidates: link.candidates.clone(),
+            })
+            .collect();
+
+        let unresolved_graphics = graph
+            .unresolved_graphics
+            .iter()
+            .map(|path| CachedUnresolvedLink {
+                source: path.source.uri.clone(),
+                index: graphics_index(path.source, path.path),
+                candidates: path.candidates.clone(),
+            })
+            .collect();
+
+        let cycles = graph
+            .cycles
+            .iter()
+            .map(|cycle| cycle.iter().map(CachedEdge::new).collect())
+            .collect();
+
+        let preorder = graph
+            .preorder()
+            .map(|document| document.uri.clone())
+            .collect();
+
+        Self {
+            edges,
+            missing: graph.missing.clone(),
+            unresolved,
+            unresolved_graphics,
+            resolved_graphics: graph.resolved_graphics.clone(),
+            assets: graph.assets.clone(),
+            cycles,
+            preorder,
+        }
+    }
+}
+
+fn graphics_index(source: &Document, path: &semantics::Span) -> usize {
+    source
+        .data
+        .as_tex()
+        .and_then(|data| {
+            data.semantics
+                .graphics
+                .iter()
+                .position(|other| std::ptr::eq(other, path))
+        })
+        .unwrap()
+}
+
+fn link_index(source: &Document, link: &semantics::tex::Link) -> usize {
+    source
+        .data
+        .as_tex()
+        .and_then(|data| {
+            data.semantics
+                .links
+                .iter()
+                .position(|other| std::ptr::eq(other, link))
+        })
+        .unwrap()
+}
+
+/// Keeps the dependency graph of every document in the workspace
+/// along with reverse lookup tables so that the graphs do not need to be rebuilt for every request.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct GraphCache {
+    graphs: FxHashMap<Url, CachedGraph>,
+    members: FxHashMap<Url, FxHashSet<Url>>,
+    missing: FxHashMap<Url, FxHashSet<Url>>,
+    graphics: FxHashMap<Url, FxHashSet<Url>>,
+}
+
+impl GraphCache {
+    pub fn get(&self, start: &Url) -> Option<&CachedGraph> {
+        self.graphs.get(start)
+    }
+
+    pub fn preorder(&self, start: &Url) -> &[Url] {
+        self.graphs
+            .get(start)
+            .map_or(&[], |graph| graph.preorder.as_slice())
+    }
+
+    /// Returns the start nodes of all graphs that contain the given document.
+    pub fn containing(&self, uri: &Url) -> impl Iterator<Item = &Url> + '_ {
+        self.members.get(uri).into_iter().flatten()
+    }
+
+    /// Returns the start nodes of all graphs that reference the given document but cannot find it.
+    pub fn referencing_missing(&self, uri: &Url) -> impl Iterator<Item = &Url> + '_ {
+        self.missing.get(uri).into_iter().flatten()
+    }
+
+    pub fn missing(&self) -> impl Iterator<Item = &Url> + '_ {
+        self.missing.keys()
+    }
+
+    /// Returns the start nodes of all graphs that have looked for an image at the given location.
+    pub fn referencing_graphics(&self, uri: &Url) -> impl Iterator<Item = &Url> + '_ {
+        self.graphics.get(uri).into_iter().flatten()
+    }
+
+    /// Returns the locations of the images that have been found or are still missing.
+    pub fn graphics(&self) -> impl Iterator<Item = &Url> + '_ {
+        self.graphics.keys()
+    }
+
+    /// Returns the files that have been read by a TeX run but are not documents.
+    pub fn assets(&self) -> impl Iterator<Item = &Url> + '_ {
+        self.graphs
+            .values()
+            .flat_map(|graph| graph.assets.iter())
+            .unique()
+    }
+
+    pub fn insert(&mut self, start: Url, graph: CachedGraph) {
+        self.remove(&start);
+
+        // The assets belong to the project although they are not part of the workspace.
+        for uri in graph.preorder.iter().chain(&graph.assets) {
+            self.members
+                .entry(uri.clone())
+                .or_default()
+                .insert(start.clone());
+        }
+
+        for uri in &graph.missing {
+            self.missing
+                .entry(uri.clone())
+                .or_default()
+                .insert(start.clone());
+        }
+
+        for uri in graphics_locations(&graph) {
+            self.graphics
+                .entry(uri.clone())
+                .or_default()
+                .insert(start.clone());
+        }
+
+        self.graphs.insert(start, graph);
+    }
+
+    pub fn remove(&mut self, start: &Url) {
+        let Some(graph) = self.graphs.remove(start) else { return };
+
+        for uri in graph.preorder.iter().chain(&graph.assets) {
+            remove_entry(&mut self.members, uri, start);
+        }
+
+        for uri in &graph.missing {
+            remove_entry(&mut self.missing, uri, start);
+        }
+
+        for uri in graphics_locations(&graph) {
+            remove_entry(&mut self.graphics, uri, start);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.graphs.clear();
+        self.members.clear();
+        self.missing.clear();
+        self.graphics.clear();
+    }
+}
+
+/// The images that have been found and all locations of the images that are missing.
+fn graphics_locations(graph: &CachedGraph) -> impl Iterator<Item = &Url> + '_ {
+    graph.resolved_graphics.iter().chain(
+        graph
+            .unresolved_graphics
+            .iter()
+            .flat_map(|path| path.candidates.iter()),
+    )
+}
+
+fn remove_entry(map: &mut FxHashMap<Url, FxHashSet<Url>>, key: &Url, start: &Url) {
+    let Some(starts) = map.get_mut(key) else { return };
+    starts.remove(start);
+    if starts.is_empty() {
+        map.remove(key);
+    }
+}
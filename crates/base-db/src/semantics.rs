@@ -1,5 +1,7 @@
 pub mod auxiliary;
 pub mod bib;
+pub mod latexmkrc;
+pub mod tectonic;
 pub mod tex;
 
 #[derive(PartialEq, Eq, Clone, Hash)]
@@ -7,13 +7,16 @@ use super::Span;
 #[derive(Debug, Clone, Default)]
 pub struct Semantics {
     pub entries: Vec<Entry>,
+    pub strings: Vec<StringDef>,
 }
 
 impl Semantics {
     pub fn process_root(&mut self, root: &bibtex::SyntaxNode) {
         for node in root.children() {
-            if let Some(entry) = bibtex::Entry::cast(node) {
+            if let Some(entry) = bibtex::Entry::cast(node.clone()) {
                 self.process_entry(entry);
+            } else if let Some(string) = bibtex::StringDef::cast(node) {
+                self.process_string(string);
             }
         }
     }
@@ -29,6 +32,18 @@ impl Semantics {
             });
         }
     }
+
+    fn process_string(&mut self, string: bibtex::StringDef) {
+        if let Some(name) = string.name_token() {
+            self.strings.push(StringDef {
+                name: Span {
+                    range: name.text_range(),
+                    text: name.text().into(),
+                },
+                full_range: string.syntax().text_range(),
+            });
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -36,3 +51,9 @@ pub struct Entry {
     pub name: Span,
     pub full_range: TextRange,
 }
+
+#[derive(Debug, Clone)]
+pub struct StringDef {
+    pub name: Span,
+    pub full_range: TextRange,
+}
@@ -0,0 +1,148 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::Engine;
+
+static SCALAR_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?m)^\s*\$(?P<name>out_dir|aux_dir|jobname|pdf_mode)\s*=\s*(?:'(?P<single>[^']*)'|"(?P<double>[^"]*)"|(?P<number>\d+))\s*;"#).unwrap()
+});
+
+static DEFAULT_FILES_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?m)^\s*@default_files\s*=\s*(?P<qw>qw)?\s*\((?P<files>[^)]*)\)\s*;"#).unwrap()
+});
+
+static STRING_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"'(?P<single>[^']*)'|"(?P<double>[^"]*)""#).unwrap());
+
+/// The settings of a `latexmkrc` file that are relevant for finding the output of a build.
+/// The file is a Perl script so only the common assignment forms are recognized.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Semantics {
+    pub out_dir: Option<String>,
+    pub aux_dir: Option<String>,
+    pub jobname: Option<String>,
+    pub pdf_mode: Option<u32>,
+    pub default_files: Vec<String>,
+}
+
+impl Semantics {
+    pub fn parse(text: &str) -> Self {
+        let mut semantics = Self::default();
+        for captures in SCALAR_REGEX.captures_iter(text) {
+            let value = captures
+                .name("single")
+                .or_else(|| captures.name("double"))
+                .or_else(|| captures.name("number"))
+                .map_or("", |value| value.as_str());
+
+            match &captures["name"] {
+                "out_dir" => semantics.out_dir = Some(String::from(value)),
+                "aux_dir" => semantics.aux_dir = Some(String::from(value)),
+                "jobname" => semantics.jobname = Some(String::from(value)),
+                "pdf_mode" => semantics.pdf_mode = value.parse().ok(),
+                _ => {}
+            };
+        }
+
+        if let Some(captures) = DEFAULT_FILES_REGEX.captures_iter(text).last() {
+            let files = &captures["files"];
+            semantics.default_files = if captures.name("qw").is_some() {
+                files.split_whitespace().map(String::from).collect()
+            } else {
+                STRING_REGEX
+                    .captures_iter(files)
+                    .filter_map(|file| file.name("single").or_else(|| file.name("double")))
+                    .map(|file| String::from(file.as_str()))
+                    .collect()
+            };
+        }
+
+        semantics
+    }
+
+    /// Returns the engine selected by `$pdf_mode`.
+    pub fn engine(&self) -> Option<Engine> {
+        match self.pdf_mode? {
+            1 => Some(Engine::Pdflatex),
+            4 => Some(Engine::Lualatex),
+            5 => Some(Engine::Xelatex),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Engine;
+
+    use super::Semantics;
+
+    #[test]
+    fn test_quotes() {
+        let semantics = Semantics::parse(
+            r#"
+$out_dir = 'build';
+  $aux_dir = "aux";
+$jobname="thesis" ;
+"#,
+        );
+
+        assert_eq!(semantics.out_dir.as_deref(), Some("build"));
+        assert_eq!(semantics.aux_dir.as_deref(), Some("aux"));
+        assert_eq!(semantics.jobname.as_deref(), Some("thesis"));
+    }
+
+    #[test]
+    fn test_pdf_mode() {
+        let semantics = Semantics::parse("$pdf_mode = 5;");
+        assert_eq!(semantics.pdf_mode, Some(5));
+        assert_eq!(semantics.engine(), Some(Engine::Xelatex));
+
+        let semantics = Semantics::parse("$pdf_mode = 2;");
+        assert_eq!(semantics.pdf_mode, Some(2));
+        assert_eq!(semantics.engine(), None);
+    }
+
+    #[test]
+    fn test_default_files() {
+        let semantics = Semantics::parse("@default_files = ('main.tex', \"appendix.tex\");");
+        assert_eq!(semantics.default_files, vec!["main.tex", "appendix.tex"]);
+
+        let semantics = Semantics::parse("@default_files = qw(main.tex appendix.tex);");
+        assert_eq!(semantics.default_files, vec!["main.tex", "appendix.tex"]);
+    }
+
+    #[test]
+    fn test_repeated_assignments() {
+        let semantics = Semantics::parse(
+            r#"
+$out_dir = 'foo';
+@default_files = ('foo.tex');
+$out_dir = 'bar';
+@default_files = ('bar.tex');
+"#,
+        );
+
+        assert_eq!(semantics.out_dir.as_deref(), Some("bar"));
+        assert_eq!(semantics.default_files, vec!["bar.tex"]);
+    }
+
+    #[test]
+    fn test_comments() {
+        let semantics = Semantics::parse(
+            r#"
+# $out_dir = 'x';
+# @default_files = ('x.tex');
+$jobname = 'thesis'; # $aux_dir = 'x';
+"#,
+        );
+
+        assert_eq!(
+            semantics,
+            Semantics {
+                jobname: Some(String::from("thesis")),
+                ..Semantics::default()
+            }
+        );
+    }
+}
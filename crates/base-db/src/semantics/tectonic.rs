@@ -0,0 +1,201 @@
+use serde::Deserialize;
+
+/// The project description of a document built with the V2 interface of Tectonic.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Semantics {
+    pub name: Option<String>,
+    pub outputs: Vec<Output>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Output {
+    pub name: String,
+    pub kind: OutputKind,
+    /// The files that are concatenated to build the output, relative to the `src` directory.
+    pub inputs: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum OutputKind {
+    Pdf,
+    Html,
+}
+
+impl Semantics {
+    /// Returns an empty project description if the file is not valid TOML.
+    pub fn parse(text: &str) -> Self {
+        let Ok(manifest) = toml::from_str::<Manifest>(text) else { return Self::default() };
+
+        let outputs = manifest
+            .output
+            .into_iter()
+            .map(|output| {
+                let kind = match output.kind.as_deref() {
+                    Some("html") => OutputKind::Html,
+                    _ => OutputKind::Pdf,
+                };
+
+                let inputs = match output.inputs {
+                    // Inline inputs are tables instead of file names.
+                    Some(inputs) => inputs
+                        .iter()
+                        .filter_map(toml::Value::as_str)
+                        .map(String::from)
+                        .collect(),
+                    None => vec![
+                        output
+                            .preamble
+                            .unwrap_or_else(|| String::from("_preamble.tex")),
+                        output.index.unwrap_or_else(|| String::from("index.tex")),
+                        output
+                            .postamble
+                            .unwrap_or_else(|| String::from("_postamble.tex")),
+                    ],
+                };
+
+                Output {
+                    name: output.name,
+                    kind,
+                    inputs,
+                }
+            })
+            .collect();
+
+        Self {
+            name: manifest.doc.and_then(|doc| doc.name),
+            outputs,
+        }
+    }
+}
+
+impl Output {
+    /// Returns the directory that Tectonic writes the output files to, relative to the project.
+    pub fn build_dir(&self) -> String {
+        format!("build/{}/", self.name)
+    }
+
+    /// Returns the path of the generated PDF file, relative to the project.
+    pub fn pdf_path(&self) -> Option<String> {
+        let name = &self.name;
+        (self.kind == OutputKind::Pdf).then(|| format!("{}{name}.pdf", self.build_dir()))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    doc: Option<ManifestDoc>,
+    #[serde(default)]
+    output: Vec<ManifestOutput>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ManifestDoc {
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ManifestOutput {
+    name: String,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    preamble: Option<String>,
+    index: Option<String>,
+    postamble: Option<String>,
+    inputs: Option<Vec<toml::Value>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Output, OutputKind, Semantics};
+
+    #[test]
+    fn test_default_inputs() {
+        let semantics = Semantics::parse(
+            r#"
+[doc]
+name = "thesis"
+
+[[output]]
+name = "default"
+type = "pdf"
+"#,
+        );
+
+        assert_eq!(semantics.name.as_deref(), Some("thesis"));
+        assert_eq!(
+            semantics.outputs,
+            vec![Output {
+                name: String::from("default"),
+                kind: OutputKind::Pdf,
+                inputs: vec![
+                    String::from("_preamble.tex"),
+                    String::from("index.tex"),
+                    String::from("_postamble.tex"),
+                ],
+            }]
+        );
+
+        assert_eq!(semantics.outputs[0].build_dir(), "build/default/");
+        assert_eq!(
+            semantics.outputs[0].pdf_path().as_deref(),
+            Some("build/default/default.pdf")
+        );
+    }
+
+    #[test]
+    fn test_custom_inputs() {
+        let semantics = Semantics::parse(
+            r#"
+[[output]]
+name = "default"
+preamble = "header.tex"
+index = "main.tex"
+"#,
+        );
+
+        assert_eq!(semantics.name, None);
+        assert_eq!(
+            semantics.outputs[0].inputs,
+            vec!["header.tex", "main.tex", "_postamble.tex"]
+        );
+    }
+
+    #[test]
+    fn test_inline_inputs() {
+        let semantics = Semantics::parse(
+            r#"
+[[output]]
+name = "default"
+inputs = ["preamble.tex", { inline = "\\newcommand{\\foo}{bar}" }, "index.tex"]
+"#,
+        );
+
+        assert_eq!(
+            semantics.outputs[0].inputs,
+            vec!["preamble.tex", "index.tex"]
+        );
+    }
+
+    #[test]
+    fn test_html() {
+        let semantics = Semantics::parse(
+            r#"
+[[output]]
+name = "web"
+type = "html"
+"#,
+        );
+
+        assert_eq!(semantics.outputs[0].kind, OutputKind::Html);
+        assert_eq!(semantics.outputs[0].pdf_path(), None);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(Semantics::parse("[[output]\nname = "), Semantics::default());
+        assert_eq!(
+            Semantics::parse("[[output]]\ntype = \"pdf\""),
+            Semantics::default()
+        );
+    }
+}
@@ -1,7 +1,8 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
 use rowan::{ast::AstNode, TextLen};
-use rustc_hash::FxHashSet;
 use syntax::latex::{self, HasBrack, HasCurly};
-use text_size::TextRange;
+use text_size::{TextRange, TextSize};
 
 use super::Span;
 
@@ -12,14 +13,190 @@ pub struct Semantics {
     pub citations: Vec<Citation>,
     pub commands: Vec<Span>,
     pub environments: Vec<Span>,
+    pub command_definitions: Vec<CommandDefinition>,
+    pub environment_definitions: Vec<Span>,
     pub theorem_definitions: Vec<TheoremDefinition>,
-    pub graphics_paths: FxHashSet<String>,
+    pub magic_comments: Vec<MagicComment>,
+    pub graphics: Vec<Span>,
+    pub graphics_paths: Vec<Span>,
     pub can_be_root: bool,
     pub can_be_compiled: bool,
 }
 
 impl Semantics {
     pub fn process_root(&mut self, root: &latex::SyntaxNode) {
+        self.process_descendants(root);
+        self.update_flags();
+    }
+
+    /// Updates the semantics after the range `changed` of `new_root` has been rebuilt incrementally.
+    ///
+    /// Returns `false` if the edit cannot be applied locally and `process_root` needs to be called instead.
+    pub fn process_edit(
+        &mut self,
+        old_root: &latex::SyntaxNode,
+        new_root: &latex::SyntaxNode,
+        changed: TextRange,
+    ) -> bool {
+        let Some(dirty) = find_dirty_node(new_root, changed) else { return false };
+
+        let old_len = old_root.text_range().end();
+        let new_len = new_root.text_range().end();
+        let old_dirty = TextRange::new(
+            dirty.text_range().start(),
+            old_len - (new_len - dirty.text_range().end()),
+        );
+
+        let shift = |range: TextRange| {
+            if range.start() >= old_dirty.end() {
+                TextRange::new(
+                    new_len - (old_len - range.start()),
+                    new_len - (old_len - range.end()),
+                )
+            } else {
+                range
+            }
+        };
+
+        // The ranges of the sections and environments surrounding the edit have changed
+        // so we need to update the targets of the labels outside of the dirty node.
+        let mut targets = Vec::new();
+        for new_node in dirty.ancestors().skip(1) {
+            let Some(new_range) = target_range(&new_node) else { continue };
+
+            let range = new_node.text_range();
+            let old_range = TextRange::new(range.start(), old_len - (new_len - range.end()));
+            let Some(old_range) = old_root
+                .covering_element(old_range)
+                .ancestors()
+                .find(|node| node.kind() == new_node.kind() && node.text_range() == old_range)
+                .and_then(|node| target_range(&node)) else { return false };
+
+            targets.push((old_range, new_range));
+        }
+
+        let mut patch = Self::default();
+        patch.process_descendants(&dirty);
+
+        for label in &mut self.labels {
+            // The labels inside of the dirty node are replaced by the patch.
+            if old_dirty.contains(label.name.range.start()) {
+                continue;
+            }
+
+            for target in &mut label.targets {
+                if let Some((_, new_range)) = targets.iter().find(|(old, _)| *old == target.range) {
+                    target.range = *new_range;
+                } else if target.range.start() >= old_dirty.end() {
+                    target.range = shift(target.range);
+                } else if target.range.end() > old_dirty.start() {
+                    return false;
+                }
+            }
+        }
+
+        splice(
+            &mut self.links,
+            patch.links,
+            old_dirty,
+            |link| link.path.range.start(),
+            |link| link.path.range = shift(link.path.range),
+        );
+
+        splice(
+            &mut self.graphics,
+            patch.graphics,
+            old_dirty,
+            |path| path.range.start(),
+            |path| path.range = shift(path.range),
+        );
+
+        splice(
+            &mut self.graphics_paths,
+            patch.graphics_paths,
+            old_dirty,
+            |path| path.range.start(),
+            |path| path.range = shift(path.range),
+        );
+
+        splice(
+            &mut self.labels,
+            patch.labels,
+            old_dirty,
+            |label| label.name.range.start(),
+            |label| {
+                label.name.range = shift(label.name.range);
+                label.full_range = shift(label.full_range);
+            },
+        );
+
+        splice(
+            &mut self.citations,
+            patch.citations,
+            old_dirty,
+            |citation| citation.name.range.start(),
+            |citation| {
+                citation.name.range = shift(citation.name.range);
+                citation.full_range = shift(citation.full_range);
+            },
+        );
+
+        splice(
+            &mut self.commands,
+            patch.commands,
+            old_dirty,
+            |command| command.range.start() - "\\".text_len(),
+            |command| command.range = shift(command.range),
+        );
+
+        splice(
+            &mut self.environments,
+            patch.environments,
+            old_dirty,
+            |environment| environment.range.start(),
+            |environment| environment.range = shift(environment.range),
+        );
+
+        splice(
+            &mut self.command_definitions,
+            patch.command_definitions,
+            old_dirty,
+            |command_def| command_def.full_range.start(),
+            |command_def| {
+                command_def.name.range = shift(command_def.name.range);
+                command_def.full_range = shift(command_def.full_range);
+            },
+        );
+
+        splice(
+            &mut self.environment_definitions,
+            patch.environment_definitions,
+            old_dirty,
+            |environment| environment.range.start(),
+            |environment| environment.range = shift(environment.range),
+        );
+
+        splice(
+            &mut self.theorem_definitions,
+            patch.theorem_definitions,
+            old_dirty,
+            |theorem_def| theorem_def.name.range.start(),
+            |theorem_def| theorem_def.name.range = shift(theorem_def.name.range),
+        );
+
+        splice(
+            &mut self.magic_comments,
+            patch.magic_comments,
+            old_dirty,
+            |comment| comment.value.range.start(),
+            |comment| comment.value.range = shift(comment.value.range),
+        );
+
+        self.update_flags();
+        true
+    }
+
+    fn process_descendants(&mut self, root: &latex::SyntaxNode) {
         for node in root.descendants_with_tokens() {
             match node {
                 latex::SyntaxElement::Node(node) => {
@@ -31,10 +208,64 @@ impl Semantics {
                         let range = TextRange::new(range.start() + "\\".text_len(), range.end());
                         let text = String::from(&token.text()[1..]);
                         self.commands.push(Span { range, text });
+                    } else if token.kind() == latex::COMMENT {
+                        self.process_comment(&token);
                     }
                 }
             };
         }
+    }
+
+    pub fn magic_comment(&self, kind: MagicCommentKind) -> Option<&Span> {
+        self.magic_comments
+            .iter()
+            .find(|comment| comment.kind == kind)
+            .map(|comment| &comment.value)
+    }
+
+    fn process_comment(&mut self, token: &latex::SyntaxToken) {
+        static REGEX: Lazy<Regex> = Lazy::new(|| {
+            Regex::new(r"(?i)^%\s*!\s*(TEX\s+(?:root|(?:TS-)?program|recipe)|BIB\s+program)\s*=\s*(.*?)\s*$")
+                .unwrap()
+        });
+
+        let Some(captures) = REGEX.captures(token.text()) else { return };
+        let key = captures[1].to_lowercase();
+        let kind = if key.ends_with("root") {
+            MagicCommentKind::Root
+        } else if key.ends_with("recipe") {
+            MagicCommentKind::Recipe
+        } else if key.starts_with("bib") {
+            MagicCommentKind::BibProgram
+        } else {
+            MagicCommentKind::Program
+        };
+
+        let value = captures.get(2).unwrap();
+        if value.as_str().is_empty() {
+            return;
+        }
+
+        let start = token.text_range().start();
+        let range = TextRange::new(
+            start + TextSize::from(value.start() as u32),
+            start + TextSize::from(value.end() as u32),
+        );
+
+        self.magic_comments.push(MagicComment {
+            kind,
+            value: Span {
+                text: value.as_str().into(),
+                range,
+            },
+        });
+    }
+
+    fn update_flags(&mut self) {
+        self.can_be_compiled = self
+            .environments
+            .iter()
+            .any(|environment| environment.text == "document");
 
         self.can_be_root = self.can_be_compiled
             && !self
@@ -60,12 +291,29 @@ impl Semantics {
             self.process_environment(environment);
         } else if let Some(theorem_def) = latex::TheoremDefinition::cast(node.clone()) {
             self.process_theorem_definition(theorem_def);
+        } else if let Some(command_def) = latex::CommandDefinition::cast(node.clone()) {
+            // Includes `\DeclareMathOperator` which has its own `MATH_OPERATOR` node.
+            self.process_command_definition(command_def.syntax());
+        } else if let Some(command) = latex::GenericCommand::cast(node.clone()) {
+            if is_command_definer(&command) {
+                self.process_command_definition(command.syntax());
+            }
+        } else if let Some(environment_def) = latex::EnvironmentDefinition::cast(node.clone()) {
+            self.process_environment_definition(environment_def);
+        } else if let Some(graphics_path) = latex::GraphicsPath::cast(node.clone()) {
+            self.process_graphics_path(graphics_path);
         }
     }
 
     fn process_include(&mut self, include: latex::Include) {
         let Some(list) = include.path_list() else { return };
 
+        if include.syntax().kind() == latex::GRAPHICS_INCLUDE {
+            self.graphics
+                .extend(list.keys().map(|path| Span::from(&path)));
+            return;
+        }
+
         for path in list.keys() {
             let kind = match include.syntax().kind() {
                 latex::PACKAGE_INCLUDE => LinkKind::Sty,
@@ -84,6 +332,12 @@ impl Semantics {
         }
     }
 
+    fn process_graphics_path(&mut self, graphics_path: latex::GraphicsPath) {
+        for path in graphics_path.path_list().filter_map(|group| group.key()) {
+            self.graphics_paths.push(Span::from(&path));
+        }
+    }
+
     fn process_import(&mut self, import: latex::Import) {
         let Some(mut base_dir) = import
             .directory()
@@ -230,9 +484,86 @@ impl Semantics {
             .and_then(|begin| begin.name())
             .and_then(|group| group.key()) else { return };
 
-        let name = Span::from(&name);
-        self.can_be_compiled = self.can_be_compiled || name.text == "document";
-        self.environments.push(name);
+        self.environments.push(Span::from(&name));
+    }
+
+    /// Processes a command like `\newcommand{\foo}` or `\def\foo`.
+    /// The name is either the first group of the definition or the command that follows it.
+    fn process_command_definition(&mut self, definer: &latex::SyntaxNode) {
+        let Some(definer_name) = definer.first_token() else { return };
+        let group = definer.children().find(|child| {
+            matches!(
+                child.kind(),
+                latex::CURLY_GROUP_COMMAND | latex::CURLY_GROUP
+            )
+        });
+
+        // `\newcounter{foo}` defines `\thefoo` which prints the value of the counter.
+        if definer_name.text() == "\\newcounter" {
+            let word = group
+                .iter()
+                .flat_map(|group| group.descendants_with_tokens())
+                .filter_map(|element| element.into_token())
+                .find(|token| token.kind() == latex::WORD);
+
+            let Some(word) = word else { return };
+            let range = word.text_range();
+            self.command_definitions.push(CommandDefinition {
+                name: Span {
+                    range,
+                    text: format!("the{}", word.text()),
+                },
+                full_range: TextRange::new(definer.text_range().start(), range.end()),
+            });
+
+            return;
+        }
+
+        let name = match group {
+            Some(group) => group
+                .descendants_with_tokens()
+                .filter_map(|element| element.into_token())
+                .find(|token| token.kind() == latex::COMMAND_NAME),
+            None => definer
+                .next_sibling()
+                .and_then(|next| next.first_token())
+                .filter(|token| token.kind() == latex::COMMAND_NAME),
+        };
+
+        let Some(name) = name else { return };
+
+        let range = name.text_range();
+        let range = TextRange::new(range.start() + "\\".text_len(), range.end());
+        let full_range = TextRange::new(definer.text_range().start(), range.end());
+        let text = &name.text()[1..];
+
+        // `\newif\iffoo` also defines `\footrue` and `\foofalse`.
+        if definer_name.text() == "\\newif" {
+            if let Some(flag) = text.strip_prefix("if") {
+                for suffix in ["true", "false"] {
+                    self.command_definitions.push(CommandDefinition {
+                        name: Span {
+                            range,
+                            text: format!("{flag}{suffix}"),
+                        },
+                        full_range,
+                    });
+                }
+            }
+        }
+
+        self.command_definitions.push(CommandDefinition {
+            name: Span {
+                range,
+                text: String::from(text),
+            },
+            full_range,
+        });
+    }
+
+    fn process_environment_definition(&mut self, environment_def: latex::EnvironmentDefinition) {
+        let Some(name) = environment_def.name().and_then(|name| name.key()) else { return };
+        self.environment_definitions.push(Span::from(&name));
     }
 
     fn process_theorem_definition(&mut self, theorem_def: latex::TheoremDefinition) {
@@ -247,6 +578,129 @@ impl Semantics {
     }
 }
 
+/// Returns `true` if the command defines the command that follows it
+/// or a command that is derived from its argument (like `\newcounter`).
+/// Definitions like `\newcommand` have their own syntax nodes and are not included.
+fn is_command_definer(command: &latex::GenericCommand) -> bool {
+    let Some(name) = command.name() else { return false };
+    matches!(
+        name.text()[1..].trim_end_matches('*'),
+        "providecommand"
+            | "DeclareMathSymbol"
+            | "DeclarePairedDelimiter"
+            | "NewDocumentCommand"
+            | "RenewDocumentCommand"
+            | "ProvideDocumentCommand"
+            | "DeclareDocumentCommand"
+            | "NewExpandableDocumentCommand"
+            | "newrobustcmd"
+            | "def"
+            | "gdef"
+            | "edef"
+            | "xdef"
+            | "let"
+            | "newif"
+            | "newlength"
+            | "newsavebox"
+            | "newcount"
+            | "newdimen"
+            | "newskip"
+            | "newtoks"
+            | "newbox"
+            | "chardef"
+            | "mathchardef"
+            | "newcounter"
+    )
+}
+
+fn is_definition(node: &latex::SyntaxNode) -> bool {
+    latex::CommandDefinition::can_cast(node.kind())
+        || latex::EnvironmentDefinition::can_cast(node.kind())
+        || latex::GenericCommand::cast(node.clone())
+            .map_or(false, |command| is_command_definer(&command))
+}
+
+/// Finds the smallest node that contains the change and all of the nodes whose semantics depend on it.
+fn find_dirty_node(root: &latex::SyntaxNode, changed: TextRange) -> Option<latex::SyntaxNode> {
+    let mut dirty = match root.covering_element(changed) {
+        latex::SyntaxElement::Node(node) => node,
+        latex::SyntaxElement::Token(token) => token.parent()?,
+    };
+
+    for node in dirty.ancestors().collect::<Vec<_>>() {
+        let intersects = |range: Option<TextRange>| {
+            range.map_or(false, |range| range.intersect(changed).is_some())
+        };
+
+        let is_dirty = if let Some(section) = latex::Section::cast(node.clone()) {
+            intersects(section.name().map(|name| name.syntax().text_range()))
+        } else if let Some(environment) = latex::Environment::cast(node.clone()) {
+            intersects(environment.begin().map(|begin| begin.syntax().text_range()))
+                || node
+                    .children()
+                    .filter(|child| child.kind() == latex::CAPTION)
+                    .any(|caption| intersects(Some(caption.text_range())))
+        } else {
+            is_definition(&node)
+                || latex::Include::can_cast(node.kind())
+                || latex::Import::can_cast(node.kind())
+                || latex::GraphicsPath::can_cast(node.kind())
+                || latex::LabelDefinition::can_cast(node.kind())
+                || latex::LabelReference::can_cast(node.kind())
+                || latex::LabelReferenceRange::can_cast(node.kind())
+                || latex::Citation::can_cast(node.kind())
+                || latex::TheoremDefinition::can_cast(node.kind())
+        };
+
+        if is_dirty {
+            dirty = node.clone();
+        }
+
+        // The name in `\def\foo` is a sibling of the command that defines it.
+        let is_defined_name = node
+            .prev_sibling()
+            .map_or(false, |prev| is_definition(&prev));
+        if is_defined_name {
+            if let Some(parent) = node.parent() {
+                dirty = parent;
+            }
+        }
+    }
+
+    Some(dirty)
+}
+
+fn target_range(node: &latex::SyntaxNode) -> Option<TextRange> {
+    latex::Section::cast(node.clone())
+        .map(|section| latex::small_range(&section))
+        .or_else(|| latex::Environment::cast(node.clone()).map(|env| latex::small_range(&env)))
+        .or_else(|| latex::EnumItem::cast(node.clone()).map(|item| latex::small_range(&item)))
+}
+
+/// Replaces the items anchored inside of the old dirty range with `patch`
+/// and moves the items behind it.
+fn splice<T>(
+    items: &mut Vec<T>,
+    patch: Vec<T>,
+    old_dirty: TextRange,
+    anchor: impl Fn(&T) -> TextSize,
+    mut shift: impl FnMut(&mut T),
+) {
+    let index = items
+        .iter()
+        .position(|item| anchor(item) >= old_dirty.start())
+        .unwrap_or(items.len());
+
+    items.retain(|item| !old_dirty.contains(anchor(item)));
+    for item in &mut items[index..] {
+        if anchor(item) >= old_dirty.end() {
+            shift(item);
+        }
+    }
+
+    items.splice(index..index, patch);
+}
+
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
 pub enum LinkKind {
     Sty,
@@ -308,6 +762,13 @@ pub enum LabelObject {
     },
 }
 
+#[derive(Debug, Clone)]
+pub struct CommandDefinition {
+    pub name: Span,
+    /// Starts at the command that defines the name.
+    pub full_range: TextRange,
+}
+
 #[derive(Debug, Clone)]
 pub struct TheoremDefinition {
     pub name: Span,
@@ -319,3 +780,227 @@ pub struct Citation {
     pub name: Span,
     pub full_range: TextRange,
 }
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub enum MagicCommentKind {
+    Root,
+    Program,
+    Recipe,
+    BibProgram,
+}
+
+/// A comment like `% !TEX root = main.tex` that configures how the document is compiled.
+#[derive(Debug, Clone)]
+pub struct MagicComment {
+    pub kind: MagicCommentKind,
+    pub value: Span,
+}
+
+#[cfg(test)]
+mod tests {
+    use parser::SyntaxConfig;
+    use syntax::latex;
+    use text_size::{TextRange, TextSize};
+
+    use super::Semantics;
+
+    /// Replaces the first occurrence of `old` with `new` and updates the semantics incrementally.
+    fn edit(text: &str, old: &str, new: &str) -> Semantics {
+        let config = SyntaxConfig::default();
+        let old_green = parser::parse_latex(text, &config);
+        let old_root = latex::SyntaxNode::new_root(old_green.clone());
+        let mut semantics = Semantics::default();
+        semantics.process_root(&old_root);
+
+        let start = TextSize::try_from(text.find(old).unwrap()).unwrap();
+        let delete = TextRange::at(start, TextSize::try_from(old.len()).unwrap());
+        let (new_green, changed) = parser::reparse_latex(&old_green, delete, new, &config).unwrap();
+        let new_root = latex::SyntaxNode::new_root(new_green);
+        assert!(semantics.process_edit(&old_root, &new_root, changed));
+        semantics
+    }
+
+    /// Checks that the incremental update yields the same semantics as processing the new text.
+    fn check_incremental(text: &str, old: &str, new: &str) {
+        let new_text = text.replacen(old, new, 1);
+        let green = parser::parse_latex(&new_text, &SyntaxConfig::default());
+        let mut expected = Semantics::default();
+        expected.process_root(&latex::SyntaxNode::new_root(green));
+
+        let actual = edit(text, old, new);
+        assert_eq!(format!("{actual:#?}"), format!("{expected:#?}"));
+    }
+
+    fn check_edit(text: &str, old: &str, new: &str, expected: &[&str]) {
+        let semantics = edit(text, old, new);
+        let actual: Vec<_> = semantics
+            .command_definitions
+            .iter()
+            .map(|command_def| command_def.name.text.as_str())
+            .collect();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_edit_command_definition_name() {
+        check_edit(
+            "\\newcommand{\\foo}{x}\n\\newif\\ifbaz\n",
+            "\\foo",
+            "\\bar",
+            &["bar", "baztrue", "bazfalse", "ifbaz"],
+        );
+    }
+
+    #[test]
+    fn test_edit_def_name() {
+        check_edit(
+            "\\begin{document}\n\\def\\foo{x}\n\\foo\n\\end{document}",
+            "\\foo",
+            "\\bar",
+            &["bar"],
+        );
+    }
+
+    #[test]
+    fn test_edit_def_definer() {
+        check_edit(
+            "\\begin{document}\n\\def\\foo{x}\n\\let\\baz\\relax\n\\end{document}",
+            "\\def",
+            "\\relax",
+            &["baz"],
+        );
+    }
+
+    #[test]
+    fn test_edit_counter_name() {
+        check_edit(
+            "\\newcounter{foo}\n\\DeclareMathOperator*{\\argmax}{arg\\,max}\n",
+            "foo",
+            "bar",
+            &["thebar", "argmax"],
+        );
+    }
+
+    #[test]
+    fn test_edit_math_operator_name() {
+        check_edit(
+            "\\DeclareMathOperator{\\argmax}{arg\\,max}\n\\newcounter{foo}\n",
+            "\\argmax",
+            "\\argmin",
+            &["argmin", "thefoo"],
+        );
+    }
+
+    #[test]
+    fn test_edit_graphics_path() {
+        let semantics = edit(
+            "\\graphicspath{{figures/}}\n\\includegraphics{plot}",
+            "figures",
+            "images",
+        );
+
+        let actual: Vec<_> = semantics
+            .graphics_paths
+            .iter()
+            .map(|path| path.text.as_str())
+            .collect();
+
+        assert_eq!(actual, ["images/"]);
+    }
+
+    #[test]
+    fn test_incremental_label_target() {
+        check_incremental(
+            r#"\section{Foo}
+\label{sec:foo}
+See \ref{sec:foo}.
+\section{Bar}
+\label{sec:bar}"#,
+            "Foo",
+            "Foo and Baz",
+        );
+    }
+
+    #[test]
+    fn test_incremental_move_links() {
+        check_incremental(
+            r#"\begin{document}
+\input{a}
+\include{b}
+\bibliography{c}
+\includegraphics{d}
+\end{document}"#,
+            r#"\input{a}"#,
+            "\\input{aa}\n\\input{x}\n",
+        );
+    }
+
+    #[test]
+    fn test_incremental_move_citations() {
+        check_incremental(
+            r#"\begin{document}
+Text \cite{foo} and \cite{bar}
+\nocite{baz}
+\end{document}"#,
+            "Text",
+            "Some \\textbf{more}\n\ntext \\cite{qux}",
+        );
+    }
+
+    #[test]
+    fn test_incremental_remove_citation() {
+        check_incremental(
+            r#"\begin{document}
+Text \cite{foo} and \cite{bar}
+\nocite{baz}
+\end{document}"#,
+            r#"\cite{foo} and "#,
+            "",
+        );
+    }
+
+    #[test]
+    fn test_incremental_merge_sections() {
+        check_incremental(
+            r#"\begin{document}
+\section{A}
+\label{a}
+\section{B}
+\label{b}
+\ref{a}
+\end{document}"#,
+            "}\n\\label{a}\n\\section{",
+            "",
+        );
+    }
+
+    #[test]
+    fn test_incremental_split_environment() {
+        check_incremental(
+            r#"\begin{document}
+\begin{figure}
+\caption{X}
+\label{fig}
+\end{figure}
+\ref{fig}
+\end{document}"#,
+            "X}\n\\label",
+            "X}\n\\end{figure}\n\\begin{table}\n\\label",
+        );
+    }
+
+    #[test]
+    fn test_incremental_cross_commands() {
+        check_incremental(
+            r#"\begin{document}
+\newcommand{\foo}{x}
+\label{a}
+\cite{b}
+\input{c}
+\end{document}"#,
+            "x}\n\\label{a}\n\\cite{",
+            "y}\n\\cite{",
+        );
+    }
+}
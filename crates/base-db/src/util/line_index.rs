@@ -103,6 +103,49 @@ impl LineIndex {
         }
     }
 
+    /// Updates the index after `delete` has been replaced with a text of length `insert_len`.
+    /// Only the lines touched by the edit are scanned again.
+    pub fn edit(&mut self, text: &str, delete: TextRange, insert_len: TextSize) {
+        let shift = |offset: TextSize| offset - delete.len() + insert_len;
+
+        let start_line = self.line_col(delete.start()).line as usize;
+        let end_line = self.line_col(delete.end()).line as usize;
+        let start = self.newlines[start_line];
+        let end = self
+            .newlines
+            .get(end_line + 1)
+            .map_or_else(|| TextSize::of(text), |&offset| shift(offset));
+
+        let part = LineIndex::new(&text[TextRange::new(start, end)]);
+        let old_count = end_line - start_line + 1;
+        let mut new_count = part.newlines.len();
+
+        let mut newlines = self.newlines[..start_line].to_vec();
+        newlines.extend(part.newlines.iter().map(|&offset| offset + start));
+        if end_line + 1 < self.newlines.len() {
+            newlines.pop();
+            newlines.extend(self.newlines[end_line + 1..].iter().copied().map(shift));
+            new_count -= 1;
+        }
+
+        let mut utf16_lines = FxHashMap::default();
+        for (line, chars) in self.utf16_lines.drain() {
+            let line = line as usize;
+            if line < start_line {
+                utf16_lines.insert(line as u32, chars);
+            } else if line > end_line {
+                utf16_lines.insert((line + new_count - old_count) as u32, chars);
+            }
+        }
+
+        for (line, chars) in part.utf16_lines {
+            utf16_lines.insert(start_line as u32 + line, chars);
+        }
+
+        self.newlines = newlines;
+        self.utf16_lines = utf16_lines;
+    }
+
     pub fn line_col(&self, offset: TextSize) -> LineCol {
         let line = partition_point(&self.newlines, |&it| it <= offset) - 1;
         let line_start_offset = self.newlines[line];
@@ -215,3 +258,45 @@ where
 
     left
 }
+
+#[cfg(test)]
+mod tests {
+    use rowan::{TextRange, TextSize};
+
+    use super::LineIndex;
+
+    /// Replaces the first occurrence of `old` with `new` and compares the updated index
+    /// with the index of the new text.
+    fn check_edit(text: &str, old: &str, new: &str) {
+        let start = TextSize::try_from(text.find(old).unwrap()).unwrap();
+        let delete = TextRange::at(start, TextSize::of(old));
+        let new_text = text.replacen(old, new, 1);
+
+        let mut actual = LineIndex::new(text);
+        actual.edit(&new_text, delete, TextSize::of(new));
+        assert_eq!(actual, LineIndex::new(&new_text));
+    }
+
+    #[test]
+    fn test_edit_insert_lines() {
+        check_edit("foo\nbar\nbaz", "bar", "a\nb\nc");
+    }
+
+    #[test]
+    fn test_edit_remove_lines() {
+        check_edit("foo\nbar\nbaz\nqux\n", "o\nbar\nbaz\nq", "");
+    }
+
+    #[test]
+    fn test_edit_end() {
+        check_edit("foo\nbar", "bar", "bar\n");
+        check_edit("foo\nbar\n", "\n", "");
+    }
+
+    #[test]
+    fn test_edit_utf16() {
+        check_edit("ä\nfoo\nö\nü", "foo", "x\nß\ny");
+        check_edit("ä\nfoo\nö\nü", "ä\nfoo\nö", "");
+        check_edit("ä\nfoo\nö\nü", "o\nö", "€");
+    }
+}
@@ -1,16 +1,25 @@
 use std::{
     borrow::{Borrow, Cow},
+    ffi::OsStr,
     path::{Path, PathBuf},
 };
 
 use distro::{Distro, Language};
-use itertools::Itertools;
 use rowan::TextRange;
 use rustc_hash::FxHashSet;
+use syntax::FileList;
 use text_size::TextLen;
 use url::Url;
 
-use crate::{graph, util::LineCol, Config, Document, DocumentData, DocumentParams, Owner};
+use crate::{
+    graph::{self, CachedGraph, GraphCache},
+    semantics::{
+        latexmkrc, tectonic,
+        tex::{LinkKind, MagicCommentKind},
+    },
+    util::LineCol,
+    Config, Document, DocumentData, DocumentParams, LatexmkrcDocumentData, Owner,
+};
 
 #[derive(Debug, Default)]
 pub struct Workspace {
@@ -18,6 +27,7 @@ pub struct Workspace {
     config: Config,
     distro: Distro,
     folders: Vec<PathBuf>,
+    graphs: GraphCache,
 }
 
 impl Workspace {
@@ -55,15 +65,16 @@ impl Workspace {
         cursor: LineCol,
     ) {
         log::debug!("Opening document {uri}...");
-        self.documents.remove(&uri);
-        self.documents.insert(Document::parse(DocumentParams {
+        let document = Document::parse(DocumentParams {
             uri,
             text,
             language,
             owner,
             cursor,
             config: &self.config,
-        }));
+        });
+
+        self.insert(document);
     }
 
     pub fn load(&mut self, path: &Path, language: Language, owner: Owner) -> std::io::Result<()> {
@@ -80,23 +91,21 @@ impl Workspace {
     }
 
     pub fn edit(&mut self, uri: &Url, delete: TextRange, insert: &str) -> Option<()> {
-        let document = self.lookup(uri)?;
-        let mut text = document.text.clone();
-        let cursor = if delete.len() == text.text_len() {
-            let line = document.cursor.line.min(text.lines().count() as u32);
+        // The document is edited in place, so only the parts that affect the graphs are kept.
+        let mut document = self.documents.take(uri)?;
+        let cursor = if delete.len() == document.text.text_len() {
+            let line = document.cursor.line.min(document.text.lines().count() as u32);
             LineCol { line, col: 0 }
         } else {
             document.line_index.line_col(delete.start())
         };
 
-        text.replace_range(std::ops::Range::<usize>::from(delete), insert);
-        self.open(
-            document.uri.clone(),
-            text,
-            document.language,
-            Owner::Client,
-            cursor,
-        );
+        let old = GraphInputs::new(&document);
+        document.edit(delete, insert, &self.config);
+        document.owner = Owner::Client;
+        document.cursor = cursor;
+        self.documents.insert(document);
+        self.update_graphs(uri, Some(old));
 
         Some(())
     }
@@ -106,13 +115,25 @@ impl Workspace {
         watcher: &mut dyn notify::Watcher,
         watched_dirs: &mut FxHashSet<PathBuf>,
     ) {
+        // The recorded assets and the images are not documents but still affect the graphs.
+        let file_dirs = self
+            .graphs
+            .assets()
+            .chain(self.graphs.graphics())
+            .filter(|uri| uri.scheme() == "file")
+            .filter_map(|uri| uri.join(".").ok())
+            .map(|dir| dir.to_file_path());
+
         self.iter()
             .filter(|document| document.uri.scheme() == "file")
             .flat_map(|document| {
-                let dir1 = self.output_dir(&self.current_dir(&document.dir));
-                let dir2 = &document.dir;
-                [dir1.to_file_path(), dir2.to_file_path()]
+                let base_dir = self.current_dir(&document.dir);
+                let dir1 = self.output_dir(&base_dir);
+                let dir2 = self.aux_dir(&base_dir);
+                let dir3 = document.dir.clone();
+                [dir1, dir2, dir3].map(|dir| dir.to_file_path())
             })
+            .chain(file_dirs)
             .flatten()
             .for_each(|path| {
                 if !watched_dirs.contains(&path) {
@@ -129,19 +150,72 @@ impl Workspace {
         }
 
         self.iter()
-            .filter(|document| matches!(document.data, DocumentData::Root | DocumentData::Tectonic))
+            .filter(|document| {
+                matches!(
+                    document.data,
+                    DocumentData::Root | DocumentData::Tectonic(_)
+                )
+            })
             .flat_map(|document| document.uri.join("."))
             .find(|root_dir| base_dir.as_str().starts_with(root_dir.as_str()))
             .unwrap_or_else(|| base_dir.clone())
     }
 
+    /// Returns the directory that contains the output document.
+    ///
+    /// The `$out_dir` of a `.latexmkrc` file is used unless the output directory is configured.
     pub fn output_dir(&self, base_dir: &Url) -> Url {
-        let mut path = self.config.build.output_dir.clone();
-        if !path.ends_with('/') {
-            path.push('/');
+        let config_dir = self.config.build.output_dir.as_str();
+        let rc_dir = self
+            .latexmkrc_data(base_dir)
+            .and_then(|data| data.semantics.out_dir.as_deref())
+            .filter(|_| config_dir == ".");
+
+        join_dir(base_dir, rc_dir.unwrap_or(config_dir))
+    }
+
+    /// Returns the directory that contains the auxiliary files like `.aux` and `.log`.
+    ///
+    /// Falls back to the output directory if neither the configuration
+    /// nor the `.latexmkrc` file specify the auxiliary directory.
+    pub fn aux_dir(&self, base_dir: &Url) -> Url {
+        let rc_dir = self
+            .latexmkrc_data(base_dir)
+            .and_then(|data| data.semantics.aux_dir.as_deref());
+
+        match self.config.build.aux_dir.as_deref().or(rc_dir) {
+            Some(dir) => join_dir(base_dir, dir),
+            None => self.output_dir(base_dir),
         }
+    }
 
-        base_dir.join(&path).unwrap_or_else(|_| base_dir.clone())
+    /// Returns the `.latexmkrc` file that `latexmk` reads when it runs in `base_dir`.
+    pub fn latexmkrc(&self, base_dir: &Url) -> Option<&Document> {
+        self.iter()
+            .filter(|document| &document.dir == base_dir)
+            .find(|document| document.data.as_latexmkrc().is_some())
+    }
+
+    fn latexmkrc_data(&self, base_dir: &Url) -> Option<&LatexmkrcDocumentData> {
+        self.latexmkrc(base_dir)?.data.as_latexmkrc()
+    }
+
+    /// Returns the name of the output files of the given root document without the extension.
+    ///
+    /// The configured output file name takes precedence over the `$jobname` of a `.latexmkrc`.
+    pub fn jobname(&self, root: &Document) -> Option<String> {
+        let stem = root.path.as_deref()?.file_stem()?.to_str()?;
+        if let Some(path) = self.config.build.output_filename.as_deref() {
+            return path.file_stem().and_then(OsStr::to_str).map(String::from);
+        }
+
+        let base_dir = self.current_dir(&root.dir);
+        let jobname = self
+            .latexmkrc_data(&base_dir)
+            .and_then(|data| data.semantics.jobname.as_deref());
+
+        // `latexmk` replaces `%A` with the base name of the root file.
+        Some(jobname.map_or_else(|| String::from(stem), |name| name.replace("%A", stem)))
     }
 
     pub fn contains(&self, path: &Path) -> bool {
@@ -152,47 +226,163 @@ impl Workspace {
         self.folders.iter().any(|dir| path.starts_with(dir))
     }
 
-    pub fn project(&self, child: &Document) -> Project {
-        let mut documents = FxHashSet::default();
-        for graph in self
-            .iter()
-            .map(|start| graph::Graph::new(self, start))
-            .filter(|graph| graph.preorder().contains(&child))
-        {
-            documents.extend(graph.preorder());
+    pub fn graph<'a>(&'a self, start: &'a Document) -> graph::Graph<'a> {
+        match self.graphs.get(&start.uri) {
+            Some(data) => graph::Graph::from_cache(self, start, data),
+            None => graph::Graph::new(self, start),
         }
+    }
+
+    pub fn project(&self, child: &Document) -> Project {
+        let documents = self
+            .graphs
+            .containing(&child.uri)
+            .flat_map(|start| self.graphs.preorder(start))
+            .filter_map(|uri| self.lookup(uri))
+            .collect();
 
         Project { documents }
     }
 
-    pub fn parents(&self, child: &Document) -> FxHashSet<&Document> {
-        self.iter()
+    /// Returns the root documents that include the given document.
+    ///
+    /// A `% !TEX root` magic comment takes precedence over the dependency graph.
+    /// Otherwise, the roots are sorted by their URI so that the first one is always the same.
+    pub fn parents(&self, child: &Document) -> Vec<&Document> {
+        if let Some(root) = self
+            .magic_root(child)
+            .and_then(|uri| self.lookup(&uri))
+        {
+            return vec![root];
+        }
+
+        let mut parents: Vec<_> = self
+            .graphs
+            .containing(&child.uri)
+            .filter_map(|uri| self.lookup(uri))
             .filter(|document| {
                 let DocumentData::Tex(data) = &document.data else { return false };
                 data.semantics.can_be_root
             })
-            .filter(|parent| {
-                let graph = graph::Graph::new(self, parent);
-                let mut nodes = graph.preorder();
-                nodes.contains(&child)
+            .collect();
+
+        parents.sort_by(|a, b| a.uri.cmp(&b.uri));
+
+        // `latexmk` builds the files listed in `@default_files` if it is called without arguments.
+        let is_default_file = |document: &Document| {
+            let base_dir = self.current_dir(&document.dir);
+            self.latexmkrc_data(&base_dir).map_or(false, |data| {
+                data.semantics
+                    .default_files
+                    .iter()
+                    .any(|file| base_dir.join(file).map_or(false, |uri| uri == document.uri))
             })
-            .collect()
+        };
+
+        parents.sort_by_key(|document| !is_default_file(document));
+        parents
+    }
+
+    /// Returns the `Tectonic.toml` file of the project that contains the given document.
+    pub fn tectonic_root(&self, child: &Document) -> Option<&Document> {
+        self.graphs
+            .containing(&child.uri)
+            .filter_map(|uri| self.lookup(uri))
+            .filter(|document| matches!(document.data, DocumentData::Tectonic(_)))
+            .min_by(|a, b| a.uri.cmp(&b.uri))
+    }
+
+    fn magic_root(&self, child: &Document) -> Option<Url> {
+        let data = child.data.as_tex()?;
+        let path = data.semantics.magic_comment(MagicCommentKind::Root)?;
+        child.dir.join(&path.text).ok()
     }
 
     pub fn set_config(&mut self, config: Config) {
         self.config = config;
         self.reload();
+        self.rebuild_graphs();
     }
 
     pub fn set_distro(&mut self, distro: Distro) {
         self.distro = distro;
         self.reload();
+        self.rebuild_graphs();
     }
 
     pub fn set_folders(&mut self, folders: Vec<PathBuf>) {
         self.folders = folders;
     }
 
+    fn insert(&mut self, document: Document) {
+        let uri = document.uri.clone();
+        let old = self.documents.replace(document);
+        self.update_graphs(&uri, old.as_ref().map(GraphInputs::new));
+    }
+
+    /// Updates the cached dependency graphs after the document `uri` has been changed from `old`.
+    fn update_graphs(&mut self, uri: &Url, old: Option<GraphInputs>) {
+        let new = self.lookup(uri).map(GraphInputs::new);
+
+        // Project markers change the base directory of every document below them.
+        let is_marker = |inputs: &Option<GraphInputs>| {
+            inputs.as_ref().map_or(false, |inputs| {
+                matches!(inputs.language, Language::Root | Language::Tectonic)
+            })
+        };
+
+        if is_marker(&old) != is_marker(&new) {
+            self.rebuild_graphs();
+            return;
+        }
+
+        if old.is_some() && old == new {
+            return;
+        }
+
+        // The `.latexmkrc` file moves the auxiliary files of every document next to it.
+        if [&old, &new]
+            .into_iter()
+            .flatten()
+            .any(|inputs| inputs.language == Language::Latexmkrc)
+        {
+            self.rebuild_graphs();
+            return;
+        }
+
+        let mut starts: FxHashSet<Url> = self
+            .graphs
+            .containing(uri)
+            .chain(self.graphs.referencing_missing(uri))
+            .cloned()
+            .collect();
+
+        starts.insert(uri.clone());
+        for start in starts {
+            self.update_graph(&start);
+        }
+    }
+
+    fn update_graph(&mut self, start: &Url) {
+        match self.lookup(start) {
+            Some(document) => {
+                let graph = CachedGraph::from(&graph::Graph::new(self, document));
+                self.graphs.insert(start.clone(), graph);
+            }
+            None => {
+                self.graphs.remove(start);
+            }
+        };
+    }
+
+    fn rebuild_graphs(&mut self) {
+        self.graphs.clear();
+        let uris: Vec<_> = self.iter().map(|document| document.uri.clone()).collect();
+        for uri in uris {
+            self.update_graph(&uri);
+        }
+    }
+
     pub fn set_cursor(&mut self, uri: &Url, cursor: LineCol) -> Option<()> {
         let mut document = self.lookup(uri)?.clone();
         document.cursor = cursor;
@@ -222,7 +412,20 @@ impl Workspace {
     }
 
     pub fn remove(&mut self, uri: &Url) {
-        self.documents.remove(uri);
+        let old = self.documents.take(uri);
+        self.update_graphs(uri, old.as_ref().map(GraphInputs::new));
+    }
+
+    /// Updates the graphs that look for an image at the given path after it has been created
+    /// or deleted. Returns `true` if any graph has been updated.
+    pub fn update_graphics(&mut self, path: &Path) -> bool {
+        let Ok(uri) = Url::from_file_path(path) else { return false };
+        let starts: Vec<_> = self.graphs.referencing_graphics(&uri).cloned().collect();
+        for start in &starts {
+            self.update_graph(start);
+        }
+
+        !starts.is_empty()
     }
 
     pub fn close(&mut self, uri: &Url) -> Option<()> {
@@ -272,7 +475,10 @@ impl Workspace {
                 .map(|entry| entry.path())
             {
                 let Some(lang) = Language::from_path(&file) else { continue };
-                if !matches!(lang, Language::Tex | Language::Root | Language::Tectonic) {
+                if !matches!(
+                    lang,
+                    Language::Tex | Language::Root | Language::Tectonic | Language::Latexmkrc
+                ) {
                     continue;
                 }
 
@@ -287,10 +493,15 @@ impl Workspace {
     }
 
     fn discover_children(&mut self, checked_paths: &mut FxHashSet<PathBuf>) -> bool {
-        let files = self
+        let magic_roots: Vec<_> = self
             .iter()
-            .map(|start| graph::Graph::new(self, start))
-            .flat_map(|graph| graph.missing)
+            .filter_map(|document| self.magic_root(document))
+            .collect();
+
+        let files = self
+            .graphs
+            .missing()
+            .chain(magic_roots.iter())
             .filter(|uri| uri.scheme() == "file")
             .flat_map(|uri| uri.to_file_path())
             .collect::<FxHashSet<_>>();
@@ -308,7 +519,128 @@ impl Workspace {
     }
 }
 
+/// The parts of a document that the dependency graphs are built from.
+#[derive(PartialEq)]
+struct GraphInputs {
+    language: Language,
+    /// The job name only renames the implicit output files of a root document.
+    can_be_root: bool,
+    links: Vec<(LinkKind, String, Option<String>)>,
+    /// The images are resolved against the `\graphicspath` of the whole graph.
+    graphics: Vec<String>,
+    manifest: Option<tectonic::Semantics>,
+    latexmkrc: Option<latexmkrc::Semantics>,
+    /// A new build changes the files that are recorded in the `.fls` file.
+    file_list: Option<FileList>,
+}
+
+impl GraphInputs {
+    fn new(document: &Document) -> Self {
+        let links = document.data.as_tex().map_or(Vec::new(), |data| {
+            data.semantics
+                .links
+                .iter()
+                .map(|link| (link.kind, link.path.text.clone(), link.base_dir.clone()))
+                .collect()
+        });
+
+        let graphics = document.data.as_tex().map_or(Vec::new(), |data| {
+            let semantics = &data.semantics;
+            semantics
+                .graphics
+                .iter()
+                .chain(&semantics.graphics_paths)
+                .map(|path| path.text.clone())
+                .collect()
+        });
+
+        Self {
+            language: document.language,
+            can_be_root: document
+                .data
+                .as_tex()
+                .map_or(false, |data| data.semantics.can_be_root),
+            links,
+            graphics,
+            manifest: document
+                .data
+                .as_tectonic()
+                .map(|data| data.semantics.clone()),
+            latexmkrc: document
+                .data
+                .as_latexmkrc()
+                .map(|data| data.semantics.clone()),
+            file_list: document.data.as_fls().map(|data| data.file_list.clone()),
+        }
+    }
+}
+
+fn join_dir(base_dir: &Url, dir: &str) -> Url {
+    let mut path = String::from(dir);
+    if !path.ends_with('/') {
+        path.push('/');
+    }
+
+    base_dir.join(&path).unwrap_or_else(|_| base_dir.clone())
+}
+
 #[derive(Debug)]
 pub struct Project<'a> {
     pub documents: FxHashSet<&'a Document>,
 }
+
+#[cfg(test)]
+mod tests {
+    use distro::Language;
+    use rowan::TextRange;
+    use text_size::TextLen;
+    use url::Url;
+
+    use crate::{util::LineCol, Owner};
+
+    use super::Workspace;
+
+    fn open(workspace: &mut Workspace, name: &str, text: &str, language: Language) -> Url {
+        let uri = Url::parse(&format!("file:///texlab/{name}")).unwrap();
+        let cursor = LineCol { line: 0, col: 0 };
+        workspace.open(
+            uri.clone(),
+            String::from(text),
+            language,
+            Owner::Client,
+            cursor,
+        );
+        uri
+    }
+
+    #[test]
+    fn test_edit_can_be_root() {
+        let mut workspace = Workspace::default();
+        open(
+            &mut workspace,
+            ".latexmkrc",
+            "$jobname = 'thesis';",
+            Language::Latexmkrc,
+        );
+        let aux = open(&mut workspace, "thesis.aux", "", Language::Aux);
+        let main = open(
+            &mut workspace,
+            "main.tex",
+            r#"\section{Foo}"#,
+            Language::Tex,
+        );
+
+        let contains_aux = |workspace: &Workspace| {
+            let document = workspace.lookup(&main).unwrap();
+            let project = workspace.project(document);
+            project.documents.iter().any(|document| document.uri == aux)
+        };
+
+        assert!(!contains_aux(&workspace));
+
+        let text = r#"\begin{document}\section{Foo}\end{document}"#;
+        let len = workspace.lookup(&main).unwrap().text.text_len();
+        workspace.edit(&main, TextRange::up_to(len), text);
+        assert!(contains_aux(&workspace));
+    }
+}
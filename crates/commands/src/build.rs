@@ -6,13 +6,18 @@ use std::{
 };
 
 use anyhow::Result;
-use base_db::Workspace;
+use base_db::{
+    semantics::tex::{LinkKind, MagicCommentKind},
+    BuildConfig, Document, Engine, Workspace,
+};
 use bstr::io::BufReadExt;
 use crossbeam_channel::Sender;
 use thiserror::Error;
 use url::Url;
 
-use crate::placeholders::replace_placeholders;
+use crate::placeholders::{
+    replace_placeholders, validate_placeholders, PlaceholderError, BUILD_PLACEHOLDERS,
+};
 
 #[derive(Debug, Error)]
 pub enum BuildError {
@@ -22,49 +27,236 @@ pub enum BuildError {
     #[error("Document \"{0}\" does not exist on the local file system")]
     NotLocal(Url),
 
+    #[error("Build recipe \"{0}\" is not configured")]
+    UnknownRecipe(String),
+
+    #[error("Invalid build arguments: {0}")]
+    Placeholder(#[from] PlaceholderError),
+
     #[error("Unable to run compiler: {0}")]
     Compile(#[from] std::io::Error),
 }
 
 #[derive(Debug)]
 pub struct BuildCommand {
-    program: String,
-    args: Vec<String>,
-    working_dir: PathBuf,
+    root: Url,
+    steps: Vec<BuildStep>,
+    engine: Option<Engine>,
 }
 
 impl BuildCommand {
     pub fn new(workspace: &Workspace, uri: &Url) -> Result<Self, BuildError> {
-        let Some(document) = workspace.lookup(uri) else {
+        Self::with_recipe(workspace, uri, None)
+    }
+
+    /// Creates the command that builds the given document with the steps of a recipe.
+    /// If no recipe is given, the recipe is taken from a `% !TEX recipe` magic comment
+    /// or from the configuration. Without any recipe, the build program is run once.
+    pub fn with_recipe(
+        workspace: &Workspace,
+        uri: &Url,
+        recipe: Option<&str>,
+    ) -> Result<Self, BuildError> {
+        Self::new_internal(workspace, uri, recipe, false)
+    }
+
+    /// Creates a command that keeps running and compiles the document again whenever one of
+    /// its files changes. Recipes are ignored because the build program watches the files itself.
+    pub fn continuous(workspace: &Workspace, uri: &Url) -> Result<Self, BuildError> {
+        Self::new_internal(workspace, uri, None, true)
+    }
+
+    /// Checks that the arguments of the build program and the recipes only use known placeholders.
+    pub fn validate(config: &BuildConfig) -> Result<(), BuildError> {
+        let steps = config.recipes.iter().flat_map(|recipe| recipe.steps.iter());
+        for args in [&config.args, &config.continuous_args]
+            .into_iter()
+            .chain(steps.map(|step| &step.args))
+        {
+            validate_placeholders(args, BUILD_PLACEHOLDERS)?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks if a line of the output of a continuous build marks the end of a compilation.
+    pub fn is_cycle_end(line: &str) -> bool {
+        line.starts_with("=== Watching for updated files")
+    }
+
+    fn new_internal(
+        workspace: &Workspace,
+        uri: &Url,
+        recipe: Option<&str>,
+        continuous: bool,
+    ) -> Result<Self, BuildError> {
+        let Some(child) = workspace.lookup(uri) else {
             return Err(BuildError::NotFound(uri.clone()));
         };
 
-        let document = workspace
-            .parents(document)
-            .into_iter()
-            .next()
-            .unwrap_or(document);
+        let document = workspace.parents(child).into_iter().next().unwrap_or(child);
 
         let Some(path) = document.path.as_deref().and_then(Path::to_str) else {
             return Err(BuildError::NotLocal(document.uri.clone()));
         };
 
-        let config = &workspace.config().build;
-        let program = config.program.clone();
-        let args = replace_placeholders(&config.args, &[('f', path)]);
-
         let Ok(working_dir) = workspace.current_dir(&document.dir).to_file_path() else {
             return Err(BuildError::NotLocal(document.uri.clone()));
         };
 
+        let dirs = ProjectDirs::new(workspace, document);
+        let jobname = workspace.jobname(document);
+        let placeholders = [
+            ('f', Some(path)),
+            ('r', Some(path)),
+            ('j', jobname.as_deref()),
+            ('d', dirs.root_dir.as_deref()),
+            ('o', dirs.output_dir.as_deref()),
+            ('a', dirs.aux_dir.as_deref()),
+        ];
+
+        let config = &workspace.config().build;
+        let recipe = recipe
+            .map(String::from)
+            .or_else(|| magic_comment(document, child, MagicCommentKind::Recipe))
+            .or_else(|| config.default_recipe.clone())
+            .filter(|_| !continuous);
+
+        // `latexmk` cannot build Tectonic projects, so they use Tectonic unless configured otherwise.
+        if recipe.is_none() && !continuous && config.program == "latexmk" {
+            if let Some(project) = workspace.tectonic_root(child) {
+                return Self::tectonic(project);
+            }
+        }
+
+        let mut steps = match recipe {
+            Some(name) => {
+                let Some(recipe) = config.recipes.iter().find(|recipe| recipe.name == name) else {
+                    return Err(BuildError::UnknownRecipe(name));
+                };
+
+                let bib_program = magic_comment(document, child, MagicCommentKind::BibProgram)
+                    .filter(|program| is_bib_program(program));
+
+                recipe
+                    .steps
+                    .iter()
+                    .map(|step| {
+                        let program = bib_program
+                            .as_ref()
+                            .filter(|_| is_bib_program(&step.program))
+                            .unwrap_or(&step.program);
+
+                        Ok(BuildStep {
+                            name: step.name.clone(),
+                            program: program.clone(),
+                            args: replace_placeholders(&step.args, &placeholders)?,
+                            working_dir: working_dir.clone(),
+                        })
+                    })
+                    .collect::<Result<_, BuildError>>()?
+            }
+            None => {
+                let mut args = Vec::new();
+                if continuous {
+                    args.extend(config.continuous_args.iter().cloned());
+                }
+
+                args.extend(replace_placeholders(&config.args, &placeholders)?);
+                vec![BuildStep {
+                    name: config.program.clone(),
+                    program: config.program.clone(),
+                    args,
+                    working_dir,
+                }]
+            }
+        };
+
+        // Only switch the engine if the arguments contain one of the engine flags.
+        let mut engine = None;
+        for arg in steps.iter_mut().flat_map(|step| step.args.iter_mut()) {
+            if config.engine_flags.find(arg).is_some() {
+                let engine =
+                    *engine.get_or_insert_with(|| detect_engine(workspace, document, child));
+                *arg = String::from(config.engine_flags.get(engine));
+            }
+        }
+
         Ok(Self {
-            program,
-            args,
+            root: document.uri.clone(),
+            steps,
+            engine,
+        })
+    }
+
+    fn tectonic(project: &Document) -> Result<Self, BuildError> {
+        let Ok(working_dir) = project.dir.to_file_path() else {
+            return Err(BuildError::NotLocal(project.uri.clone()));
+        };
+
+        let step = BuildStep {
+            name: String::from("tectonic"),
+            program: String::from("tectonic"),
+            args: vec![String::from("-X"), String::from("build")],
             working_dir,
+        };
+
+        Ok(Self {
+            root: project.uri.clone(),
+            steps: vec![step],
+            engine: None,
         })
     }
 
-    pub fn spawn(self, sender: Sender<String>) -> Result<Child, BuildError> {
+    /// The root document that is compiled by the command.
+    pub fn root(&self) -> &Url {
+        &self.root
+    }
+
+    pub fn engine(&self) -> Option<Engine> {
+        self.engine
+    }
+
+    pub fn steps(&self) -> &[BuildStep] {
+        &self.steps
+    }
+
+    #[cfg(windows)]
+    pub fn cancel(pid: u32) -> std::io::Result<bool> {
+        Ok(std::process::Command::new("taskkill")
+            .arg("/PID")
+            .arg(pid.to_string())
+            .arg("/F")
+            .arg("/T")
+            .status()?
+            .success())
+    }
+
+    #[cfg(not(windows))]
+    pub fn cancel(pid: u32) -> Result<bool> {
+        unsafe {
+            libc::killpg(pid as libc::pid_t, libc::SIGTERM);
+        }
+
+        Ok(true)
+    }
+}
+
+/// A single program that is run as part of a build.
+#[derive(Debug)]
+pub struct BuildStep {
+    name: String,
+    program: String,
+    args: Vec<String>,
+    working_dir: PathBuf,
+}
+
+impl BuildStep {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn spawn(&self, sender: Sender<String>) -> Result<Child, BuildError> {
         log::debug!(
             "Spawning compiler {} {:#?} in directory {}",
             self.program,
@@ -103,25 +295,95 @@ impl BuildCommand {
             .spawn()
             .map_err(Into::into)
     }
+}
 
-    #[cfg(windows)]
-    pub fn cancel(pid: u32) -> std::io::Result<bool> {
-        Ok(std::process::Command::new("taskkill")
-            .arg("/PID")
-            .arg(pid.to_string())
-            .arg("/F")
-            .arg("/T")
-            .status()?
-            .success())
+fn magic_comment(root: &Document, child: &Document, kind: MagicCommentKind) -> Option<String> {
+    [root, child]
+        .into_iter()
+        .filter_map(|document| document.data.as_tex())
+        .find_map(|data| data.semantics.magic_comment(kind))
+        .map(|value| value.text.clone())
+}
+
+/// Recipe steps that run one of these programs use the program
+/// of a `% !BIB program` magic comment instead.
+/// Other programs are not allowed because the documents might not be trusted.
+fn is_bib_program(program: &str) -> bool {
+    matches!(
+        program,
+        "bibtex" | "bibtex8" | "bibtexu" | "pbibtex" | "upbibtex" | "biber"
+    )
+}
+
+/// The directory of the build and the directories of the output files.
+/// The directories are only missing if they are not on the local file system.
+#[derive(Debug, Default)]
+pub(crate) struct ProjectDirs {
+    pub root_dir: Option<String>,
+    pub output_dir: Option<String>,
+    pub aux_dir: Option<String>,
+}
+
+impl ProjectDirs {
+    pub fn new(workspace: &Workspace, root: &Document) -> Self {
+        let base_dir = workspace.current_dir(&root.dir);
+        let output_dir = workspace.output_dir(&base_dir);
+        let aux_dir = workspace.aux_dir(&base_dir);
+        Self {
+            root_dir: local_dir(&base_dir),
+            output_dir: local_dir(&output_dir),
+            aux_dir: local_dir(&aux_dir),
+        }
     }
+}
 
-    #[cfg(not(windows))]
-    pub fn cancel(pid: u32) -> Result<bool> {
-        unsafe {
-            libc::killpg(pid as libc::pid_t, libc::SIGTERM);
+/// Converts a directory URL into a path without the trailing separator.
+fn local_dir(uri: &Url) -> Option<String> {
+    let path = uri.to_file_path().ok()?;
+    let path: PathBuf = path.components().collect();
+    path.to_str().map(String::from)
+}
+
+/// Selects the engine from a `% !TEX program` magic comment in the root document
+/// or in the built document, followed by the `$pdf_mode` of a `.latexmkrc` file.
+/// Falls back to XeLaTeX if the project loads a package that requires a Unicode engine.
+fn detect_engine(workspace: &Workspace, root: &Document, child: &Document) -> Engine {
+    let magic_engine = magic_comment(root, child, MagicCommentKind::Program).and_then(|program| {
+        match program.to_lowercase().as_str() {
+            "pdflatex" => Some(Engine::Pdflatex),
+            "xelatex" => Some(Engine::Xelatex),
+            "lualatex" => Some(Engine::Lualatex),
+            _ => None,
         }
+    });
 
-        Ok(true)
+    if let Some(engine) = magic_engine {
+        return engine;
+    }
+
+    let base_dir = workspace.current_dir(&root.dir);
+    let rc_engine = workspace
+        .latexmkrc(&base_dir)
+        .and_then(|document| document.data.as_latexmkrc())
+        .and_then(|data| data.semantics.engine());
+
+    if let Some(engine) = rc_engine {
+        return engine;
+    }
+
+    let needs_unicode = workspace
+        .project(root)
+        .documents
+        .iter()
+        .filter_map(|document| document.data.as_tex())
+        .flat_map(|data| data.semantics.links.iter())
+        .filter(|link| link.kind == LinkKind::Sty)
+        .any(|link| matches!(link.path.text.as_str(), "fontspec" | "polyglossia"));
+
+    if needs_unicode {
+        Engine::Xelatex
+    } else {
+        Engine::Pdflatex
     }
 }
 
@@ -138,3 +400,391 @@ fn track_output(
         })
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use base_db::{BuildRecipe, BuildStep, Config, Engine};
+    use test_utils::fixture::Fixture;
+
+    use super::{BuildCommand, BuildError};
+
+    #[test]
+    fn test_root_from_graph() {
+        let fixture = Fixture::parse(
+            r#"
+%! b.tex
+\documentclass{article}
+\begin{document}\include{chapter}\end{document}
+
+%! a.tex
+\documentclass{article}
+\begin{document}\include{chapter}\end{document}
+
+%! chapter.tex
+\section{Foo}"#,
+        );
+
+        let uri = &fixture.documents[2].uri;
+        let command = BuildCommand::new(&fixture.workspace, uri).unwrap();
+        assert_eq!(command.steps[0].args.last().unwrap(), "/texlab/a.tex");
+    }
+
+    #[test]
+    fn test_root_from_magic_comment() {
+        let fixture = Fixture::parse(
+            r#"
+%! b.tex
+\documentclass{article}
+\begin{document}\include{chapter}\end{document}
+
+%! a.tex
+\documentclass{article}
+\begin{document}\include{chapter}\end{document}
+
+%! chapter.tex
+% !TEX root = b.tex
+\section{Foo}"#,
+        );
+
+        let uri = &fixture.documents[2].uri;
+        let command = BuildCommand::new(&fixture.workspace, uri).unwrap();
+        assert_eq!(command.steps[0].args.last().unwrap(), "/texlab/b.tex");
+    }
+
+    #[test]
+    fn test_engine_default() {
+        let fixture = Fixture::parse(
+            r#"
+%! main.tex
+\documentclass{article}
+\begin{document}\end{document}"#,
+        );
+
+        let uri = &fixture.documents[0].uri;
+        let command = BuildCommand::new(&fixture.workspace, uri).unwrap();
+        assert_eq!(command.engine(), Some(Engine::Pdflatex));
+        assert_eq!(command.steps[0].args[0], "-pdf");
+    }
+
+    #[test]
+    fn test_engine_from_magic_comment() {
+        let fixture = Fixture::parse(
+            r#"
+%! main.tex
+% !TeX program = LuaLaTeX
+\documentclass{article}
+\usepackage{fontspec}
+\begin{document}\end{document}"#,
+        );
+
+        let uri = &fixture.documents[0].uri;
+        let command = BuildCommand::new(&fixture.workspace, uri).unwrap();
+        assert_eq!(command.engine(), Some(Engine::Lualatex));
+        assert_eq!(command.steps[0].args[0], "-lualatex");
+    }
+
+    #[test]
+    fn test_engine_from_packages() {
+        let fixture = Fixture::parse(
+            r#"
+%! main.tex
+\documentclass{article}
+\input{preamble}
+\begin{document}\end{document}
+
+%! preamble.tex
+\usepackage{polyglossia}"#,
+        );
+
+        let uri = &fixture.documents[0].uri;
+        let command = BuildCommand::new(&fixture.workspace, uri).unwrap();
+        assert_eq!(command.engine(), Some(Engine::Xelatex));
+        assert_eq!(command.steps[0].args[0], "-xelatex");
+    }
+
+    #[test]
+    fn test_engine_from_latexmkrc() {
+        let fixture = Fixture::parse(
+            r#"
+%! main.tex
+\documentclass{article}
+\usepackage{fontspec}
+
+%! .latexmkrc
+$pdf_mode = 4;"#,
+        );
+
+        let uri = &fixture.documents[0].uri;
+        let command = BuildCommand::new(&fixture.workspace, uri).unwrap();
+        assert_eq!(command.engine(), Some(Engine::Lualatex));
+        assert_eq!(command.steps[0].args[0], "-lualatex");
+    }
+
+    fn recipe_config() -> Config {
+        let mut config = Config::default();
+        config.build.recipes.push(BuildRecipe {
+            name: String::from("biber"),
+            steps: vec![
+                BuildStep {
+                    name: String::from("pdflatex"),
+                    program: String::from("pdflatex"),
+                    args: vec![String::from("%f")],
+                },
+                BuildStep {
+                    name: String::from("biber"),
+                    program: String::from("biber"),
+                    args: vec![String::from("main")],
+                },
+            ],
+        });
+
+        config
+    }
+
+    #[test]
+    fn test_recipe_from_magic_comment() {
+        let fixture = Fixture::parse(
+            r#"
+%! main.tex
+% !TEX recipe = biber
+\documentclass{article}
+\begin{document}\end{document}"#,
+        );
+
+        let mut workspace = fixture.workspace;
+        workspace.set_config(recipe_config());
+
+        let uri = &fixture.documents[0].uri;
+        let command = BuildCommand::new(&workspace, uri).unwrap();
+        let steps: Vec<_> = command.steps().iter().map(|step| step.name()).collect();
+        assert_eq!(steps, vec!["pdflatex", "biber"]);
+        assert_eq!(command.steps[0].args, vec!["/texlab/main.tex"]);
+        assert_eq!(command.engine(), None);
+    }
+
+    #[test]
+    fn test_bib_program_from_magic_comment() {
+        let fixture = Fixture::parse(
+            r#"
+%! main.tex
+% !TEX recipe = biber
+% !BIB program = bibtex
+\documentclass{article}
+\begin{document}\end{document}"#,
+        );
+
+        let mut workspace = fixture.workspace;
+        workspace.set_config(recipe_config());
+
+        let uri = &fixture.documents[0].uri;
+        let command = BuildCommand::new(&workspace, uri).unwrap();
+        let programs: Vec<_> = command.steps().iter().map(|step| &step.program).collect();
+        assert_eq!(programs, vec!["pdflatex", "bibtex"]);
+    }
+
+    #[test]
+    fn test_bib_program_unknown() {
+        let fixture = Fixture::parse(
+            r#"
+%! main.tex
+% !TEX recipe = biber
+% !BIB program = rm
+\documentclass{article}
+\begin{document}\end{document}"#,
+        );
+
+        let mut workspace = fixture.workspace;
+        workspace.set_config(recipe_config());
+
+        let uri = &fixture.documents[0].uri;
+        let command = BuildCommand::new(&workspace, uri).unwrap();
+        let programs: Vec<_> = command.steps().iter().map(|step| &step.program).collect();
+        assert_eq!(programs, vec!["pdflatex", "biber"]);
+    }
+
+    #[test]
+    fn test_continuous() {
+        let fixture = Fixture::parse(
+            r#"
+%! main.tex
+% !TEX recipe = biber
+\documentclass{article}
+\begin{document}\end{document}"#,
+        );
+
+        let mut workspace = fixture.workspace;
+        workspace.set_config(recipe_config());
+
+        let uri = &fixture.documents[0].uri;
+        let command = BuildCommand::continuous(&workspace, uri).unwrap();
+        assert_eq!(command.steps.len(), 1);
+        assert_eq!(
+            command.steps[0].args,
+            vec![
+                "-pvc",
+                "-view=none",
+                "-pdf",
+                "-interaction=nonstopmode",
+                "-synctex=1",
+                "/texlab/main.tex"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_directories() {
+        let fixture = Fixture::parse(
+            r#"
+%! main.tex
+\documentclass{article}"#,
+        );
+
+        let mut workspace = fixture.workspace;
+        let mut config = Config::default();
+        config.build.args = vec!["-outdir=%o".into(), "-auxdir=%a".into(), "%f".into()];
+        config.build.output_dir = String::from("build");
+        config.build.aux_dir = Some(String::from("build/aux"));
+        workspace.set_config(config);
+
+        let uri = &fixture.documents[0].uri;
+        let command = BuildCommand::new(&workspace, uri).unwrap();
+        assert_eq!(
+            command.steps[0].args,
+            vec![
+                "-outdir=/texlab/build",
+                "-auxdir=/texlab/build/aux",
+                "/texlab/main.tex"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_directories_default() {
+        let fixture = Fixture::parse(
+            r#"
+%! main.tex
+\documentclass{article}"#,
+        );
+
+        let mut workspace = fixture.workspace;
+        let mut config = Config::default();
+        config.build.args = vec!["-outdir=%o".into(), "-auxdir=%a".into(), "%f".into()];
+        workspace.set_config(config);
+
+        let uri = &fixture.documents[0].uri;
+        let command = BuildCommand::new(&workspace, uri).unwrap();
+        assert_eq!(
+            command.steps[0].args,
+            vec!["-outdir=/texlab", "-auxdir=/texlab", "/texlab/main.tex"]
+        );
+    }
+
+    #[test]
+    fn test_placeholders() {
+        let fixture = Fixture::parse(
+            r#"
+%! main.tex
+\documentclass{article}"#,
+        );
+
+        let mut workspace = fixture.workspace;
+        let mut config = Config::default();
+        config.build.args = ["%{-outdir=%o}", "-jobname=%j-%%", "-cd=%d", "%r"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+
+        workspace.set_config(config);
+
+        let uri = &fixture.documents[0].uri;
+        let command = BuildCommand::new(&workspace, uri).unwrap();
+        assert_eq!(
+            command.steps[0].args,
+            vec![
+                "-outdir=/texlab",
+                "-jobname=main-%",
+                "-cd=/texlab",
+                "/texlab/main.tex"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_placeholder_unknown() {
+        let mut config = Config::default();
+        config.build.recipes.push(BuildRecipe {
+            name: String::from("foo"),
+            steps: vec![BuildStep {
+                name: String::from("foo"),
+                program: String::from("foo"),
+                args: vec![String::from("%l")],
+            }],
+        });
+
+        assert!(matches!(
+            BuildCommand::validate(&config.build),
+            Err(BuildError::Placeholder(_))
+        ));
+    }
+
+    #[test]
+    fn test_tectonic() {
+        let fixture = Fixture::parse(
+            r#"
+%! Tectonic.toml
+[doc]
+name = "thesis"
+
+[[output]]
+name = "default"
+type = "pdf"
+
+%! src/_preamble.tex
+\documentclass{article}
+\begin{document}
+
+%! src/index.tex
+\input{chapter}
+
+%! src/chapter.tex
+\section{Foo}
+
+%! src/_postamble.tex
+\end{document}"#,
+        );
+
+        let uri = &fixture.documents[3].uri;
+        let command = BuildCommand::new(&fixture.workspace, uri).unwrap();
+        assert_eq!(command.root(), &fixture.documents[0].uri);
+        assert_eq!(command.steps[0].program, "tectonic");
+        assert_eq!(command.steps[0].args, vec!["-X", "build"]);
+        assert_eq!(command.engine(), None);
+    }
+
+    #[test]
+    fn test_cycle_end() {
+        assert!(BuildCommand::is_cycle_end(
+            "=== Watching for updated files. Use ctrl/C to stop ..."
+        ));
+        assert!(!BuildCommand::is_cycle_end(
+            "Latexmk: All targets are up-to-date"
+        ));
+    }
+
+    #[test]
+    fn test_recipe_unknown() {
+        let fixture = Fixture::parse(
+            r#"
+%! main.tex
+\documentclass{article}
+\begin{document}\end{document}"#,
+        );
+
+        let mut workspace = fixture.workspace;
+        workspace.set_config(recipe_config());
+
+        let uri = &fixture.documents[0].uri;
+        let result = BuildCommand::with_recipe(&workspace, uri, Some("foo"));
+        assert!(matches!(result, Err(BuildError::UnknownRecipe(name)) if name == "foo"));
+    }
+}
@@ -0,0 +1,357 @@
+use std::{
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use base_db::Workspace;
+use rustc_hash::FxHashSet;
+use url::Url;
+
+use crate::BuildError;
+
+/// Checksum that `latexmk` records for files that did not exist when the build ran.
+const MISSING_CHECKSUM: &str = "00000000000000000000000000000000";
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum BuildState {
+    /// There is no `latexmk` database for the document, for example because it has not been built.
+    Unknown,
+    /// The output does not reflect the listed files anymore.
+    Stale(Vec<PathBuf>),
+    UpToDate,
+}
+
+/// Compares the sources recorded in the `.fdb_latexmk` file of the root document
+/// with the files in the workspace to find out if the output needs to be built again.
+pub fn build_state(workspace: &Workspace, uri: &Url) -> Result<BuildState, BuildError> {
+    let Some(child) = workspace.lookup(uri) else {
+        return Err(BuildError::NotFound(uri.clone()));
+    };
+
+    let root = workspace.parents(child).into_iter().next().unwrap_or(child);
+    if root.path.is_none() {
+        return Err(BuildError::NotLocal(root.uri.clone()));
+    }
+
+    let dir = workspace.current_dir(&root.dir);
+    let aux_dir = workspace.aux_dir(&dir);
+    let (Ok(working_dir), Ok(aux_dir)) = (dir.to_file_path(), aux_dir.to_file_path()) else {
+        return Err(BuildError::NotLocal(root.uri.clone()));
+    };
+
+    let Some(jobname) = workspace.jobname(root) else {
+        return Ok(BuildState::Unknown);
+    };
+
+    let database_path = aux_dir.join(format!("{jobname}.fdb_latexmk"));
+    let Ok(text) = std::fs::read_to_string(database_path) else {
+        return Ok(BuildState::Unknown);
+    };
+
+    let Some(database) = Database::parse(&text) else {
+        return Ok(BuildState::Unknown);
+    };
+
+    let mut changed = database.missing_outputs(&working_dir);
+    changed.extend(database.changed_sources(workspace, &working_dir));
+    if changed.is_empty() {
+        Ok(BuildState::UpToDate)
+    } else {
+        Ok(BuildState::Stale(changed))
+    }
+}
+
+#[derive(Debug, Default)]
+struct Database {
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug, Default)]
+struct Rule {
+    destination: String,
+    sources: Vec<Source>,
+    generated: Vec<String>,
+}
+
+#[derive(Debug)]
+struct Source {
+    path: String,
+    time: f64,
+    size: i64,
+    checksum: String,
+    from_rule: String,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Section {
+    Sources,
+    Generated,
+    Other,
+}
+
+impl Database {
+    /// Parses the file format of `latexmk` (`# Fdb version 3` and `4`).
+    fn parse(text: &str) -> Option<Self> {
+        let mut lines = text.lines();
+        if !lines.next()?.starts_with("# Fdb version") {
+            return None;
+        }
+
+        let mut database = Self::default();
+        let mut section = Section::Other;
+        for line in lines {
+            let tokens = tokenize(line);
+            let Some(first) = tokens.first() else { continue };
+            if line.starts_with('[') {
+                let destination = tokens.get(3)?;
+                database.rules.push(Rule {
+                    destination: String::from(*destination),
+                    ..Rule::default()
+                });
+
+                section = Section::Sources;
+            } else if first.starts_with('(') {
+                section = if *first == "(generated)" {
+                    Section::Generated
+                } else {
+                    Section::Other
+                };
+            } else {
+                let rule = database.rules.last_mut()?;
+                match section {
+                    Section::Sources => {
+                        let &[path, time, size, checksum, from_rule] = tokens.as_slice() else {
+                            return None;
+                        };
+
+                        rule.sources.push(Source {
+                            path: String::from(path),
+                            time: time.parse().ok()?,
+                            size: size.parse().ok()?,
+                            checksum: String::from(checksum),
+                            from_rule: String::from(from_rule),
+                        });
+                    }
+                    Section::Generated => rule.generated.push(String::from(*first)),
+                    Section::Other => {}
+                };
+            }
+        }
+
+        Some(database)
+    }
+
+    fn missing_outputs(&self, working_dir: &Path) -> Vec<PathBuf> {
+        self.rules
+            .iter()
+            .filter(|rule| !rule.destination.is_empty())
+            .map(|rule| working_dir.join(&rule.destination))
+            .filter(|path| !path.exists())
+            .collect()
+    }
+
+    fn changed_sources(&self, workspace: &Workspace, working_dir: &Path) -> Vec<PathBuf> {
+        // Files written by one of the rules change during every build.
+        let generated: FxHashSet<_> = self
+            .rules
+            .iter()
+            .flat_map(|rule| rule.generated.iter())
+            .map(|path| working_dir.join(path))
+            .collect();
+
+        let mut changed = Vec::new();
+        let mut visited = FxHashSet::default();
+        for source in self.rules.iter().flat_map(|rule| rule.sources.iter()) {
+            let path = working_dir.join(&source.path);
+            if !source.from_rule.is_empty()
+                || generated.contains(&path)
+                || !visited.insert(path.clone())
+            {
+                continue;
+            }
+
+            if has_changed(workspace, &path, source) {
+                changed.push(path);
+            }
+        }
+
+        changed
+    }
+}
+
+fn has_changed(workspace: &Workspace, path: &Path, source: &Source) -> bool {
+    if let Some(document) = workspace.lookup_path(path) {
+        return checksum(document.text.as_bytes()) != source.checksum;
+    }
+
+    let Ok(metadata) = path.metadata() else {
+        return source.checksum != MISSING_CHECKSUM;
+    };
+
+    // Reading the files of the distribution again is expensive so we trust the timestamps.
+    let time = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|time| time.as_secs());
+
+    let size = i64::try_from(metadata.len()).ok();
+    if size == Some(source.size) && time == Some(source.time as u64) {
+        return false;
+    }
+
+    std::fs::read(path).map_or(true, |data| checksum(&data) != source.checksum)
+}
+
+fn checksum(data: &[u8]) -> String {
+    format!("{:x}", md5::compute(data))
+}
+
+/// Splits a line into whitespace-separated tokens.
+/// Quoted strings and rule names in brackets are single tokens without the delimiters.
+fn tokenize(line: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut rest = line.trim_start();
+    while !rest.is_empty() {
+        let (token, next) = if let Some(quoted) = rest.strip_prefix('"') {
+            quoted.split_once('"').unwrap_or((quoted, ""))
+        } else if let Some(name) = rest.strip_prefix("[\"") {
+            name.split_once("\"]").unwrap_or((name, ""))
+        } else {
+            rest.split_once(char::is_whitespace).unwrap_or((rest, ""))
+        };
+
+        tokens.push(token);
+        rest = next.trim_start();
+    }
+
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use base_db::{util::LineCol, Owner, Workspace};
+    use distro::Language;
+    use rowan::{TextRange, TextSize};
+    use test_utils::fixture::Fixture;
+    use url::Url;
+
+    use super::{build_state, checksum, tokenize, BuildState, Database};
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize(r#"  "my file.tex" 1686040000 123 abc """#),
+            vec!["my file.tex", "1686040000", "123", "abc", ""]
+        );
+
+        assert_eq!(
+            tokenize(r#"["bibtex main"] 1686040000 "main.aux" "main.bbl" "main" 1686040001"#),
+            vec!["bibtex main", "1686040000", "main.aux", "main.bbl", "main", "1686040001"]
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_changed_sources() {
+        let fixture = Fixture::parse(
+            r#"
+%! main.tex
+\documentclass{article}
+\begin{document}\input{chapter}\end{document}
+
+%! chapter.tex
+\section{Foo}"#,
+        );
+
+        let main = &fixture.documents[0].text;
+        let database = format!(
+            r#"# Fdb version 3
+["pdflatex"] 1686040000 "main.tex" "main.pdf" "main" 1686040001
+  "/texlab/missing.sty" 0 -1 00000000000000000000000000000000 ""
+  "chapter.tex" 1686039990 13 0123456789abcdef0123456789abcdef ""
+  "main.aux" 1686040001 8 a94a2480d3289e625eea47cd1b285758 "pdflatex"
+  "main.tex" 1686039990 {} {} ""
+  (generated)
+  "main.aux"
+  "main.log"
+  "main.pdf""#,
+            main.len(),
+            checksum(main.as_bytes())
+        );
+
+        let database = Database::parse(&database).unwrap();
+        assert_eq!(database.rules.len(), 1);
+        assert_eq!(database.rules[0].destination, "main.pdf");
+        assert_eq!(database.rules[0].generated.len(), 3);
+
+        let changed = database.changed_sources(&fixture.workspace, Path::new("/texlab"));
+        assert_eq!(changed, vec![PathBuf::from("/texlab/chapter.tex")]);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(Database::parse("foo").is_none());
+    }
+
+    #[test]
+    fn test_missing_outputs() -> std::io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        std::fs::write(dir.path().join("main.pdf"), [])?;
+
+        let database = Database::parse(
+            r#"# Fdb version 4
+["bibtex main"] 1686040000 "main.aux" "main.bbl" "main" 1686040001 0
+["pdflatex"] 1686040000 "main.tex" "main.pdf" "main" 1686040001 0
+["makeindex main.idx"] 1686040000 "main.idx" "main.ind" "main" 1686040001 0
+["cusdep"] 1686040000 "" "" "main" 1686040001 0"#,
+        )
+        .unwrap();
+
+        let outputs = database.missing_outputs(dir.path());
+        let expected = vec![dir.path().join("main.bbl"), dir.path().join("main.ind")];
+        assert_eq!(outputs, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_state() -> std::io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let text = String::from("\\documentclass{article}\n");
+        let tex_path = dir.path().join("main.tex");
+        let pdf_path = dir.path().join("main.pdf");
+        std::fs::write(&tex_path, &text)?;
+        std::fs::write(&pdf_path, [])?;
+
+        let database = format!(
+            r#"# Fdb version 4
+["pdflatex"] 1686040000 "main.tex" "main.pdf" "main" 1686040001 0
+  "main.tex" 1686039990 {} {} ""
+  (generated)
+  "main.pdf""#,
+            text.len(),
+            checksum(text.as_bytes())
+        );
+
+        std::fs::write(dir.path().join("main.fdb_latexmk"), database)?;
+
+        let uri = Url::from_file_path(&tex_path).unwrap();
+        let mut workspace = Workspace::default();
+        let cursor = LineCol { line: 0, col: 0 };
+        workspace.open(uri.clone(), text, Language::Tex, Owner::Client, cursor);
+        assert_eq!(build_state(&workspace, &uri).unwrap(), BuildState::UpToDate);
+
+        std::fs::remove_file(&pdf_path)?;
+        let expected = BuildState::Stale(vec![pdf_path.clone()]);
+        assert_eq!(build_state(&workspace, &uri).unwrap(), expected);
+
+        std::fs::write(&pdf_path, [])?;
+        let range = TextRange::empty(TextSize::from(0));
+        workspace.edit(&uri, range, "%");
+        let expected = BuildState::Stale(vec![tex_path]);
+        assert_eq!(build_state(&workspace, &uri).unwrap(), expected);
+        Ok(())
+    }
+}
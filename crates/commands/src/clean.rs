@@ -1,4 +1,4 @@
-use std::process::Stdio;
+use std::path::PathBuf;
 
 use anyhow::Result;
 use base_db::{Document, Workspace};
@@ -9,49 +9,207 @@ pub enum CleanTarget {
     Artifacts,
 }
 
+/// The files produced by LaTeX, BibTeX and friends besides the output document.
+const AUXILIARY_EXTENSIONS: &[&str] = &[
+    "aux",
+    "log",
+    "toc",
+    "lof",
+    "lot",
+    "bbl",
+    "bcf",
+    "blg",
+    "run.xml",
+    "fls",
+    "fdb_latexmk",
+    "synctex.gz",
+    "nav",
+    "snm",
+    "out",
+    "glo",
+    "gls",
+    "glg",
+    "idx",
+    "ind",
+    "ilg",
+];
+
+const ARTIFACT_EXTENSIONS: &[&str] = &["pdf", "dvi"];
+
 #[derive(Debug)]
 pub struct CleanCommand {
-    executable: String,
-    args: Vec<String>,
+    files: Vec<PathBuf>,
 }
 
 impl CleanCommand {
     pub fn new(workspace: &Workspace, document: &Document, target: CleanTarget) -> Result<Self> {
-        let Some(path) = document.path.as_deref() else {
-            anyhow::bail!("document '{}' is not a local file", document.uri)
-        };
+        let parents = workspace.parents(document);
+        let root = parents.into_iter().next().unwrap_or(document);
+        if root.path.is_none() {
+            anyhow::bail!("document '{}' is not a local file", root.uri)
+        }
 
-        let dir = workspace.current_dir(&document.dir);
-        let dir = workspace.output_dir(&dir).to_file_path().unwrap();
+        let Some(jobname) = workspace.jobname(root) else {
+            anyhow::bail!("document '{}' has an invalid file name", root.uri)
+        };
 
-        let flag = match target {
-            CleanTarget::Auxiliary => "-c",
-            CleanTarget::Artifacts => "-C",
+        let base_dir = workspace.current_dir(&root.dir);
+        let (Ok(output_dir), Ok(aux_dir)) = (
+            workspace.output_dir(&base_dir).to_file_path(),
+            workspace.aux_dir(&base_dir).to_file_path(),
+        ) else {
+            anyhow::bail!(
+                "output directory of '{}' is not a local directory",
+                root.uri
+            )
         };
 
-        let executable = String::from("latexmk");
-        let args = vec![
-            format!("-outdir={}", dir.display()),
-            String::from(flag),
-            path.display().to_string(),
-        ];
+        let mut files: Vec<_> = AUXILIARY_EXTENSIONS
+            .iter()
+            .map(|ext| aux_dir.join(format!("{jobname}.{ext}")))
+            .collect();
 
-        Ok(Self { executable, args })
+        // The SyncTeX file may have been moved next to the output document.
+        if output_dir != aux_dir {
+            files.push(output_dir.join(format!("{jobname}.synctex.gz")));
+        }
+
+        if target == CleanTarget::Artifacts {
+            files.extend(
+                ARTIFACT_EXTENSIONS
+                    .iter()
+                    .map(|ext| output_dir.join(format!("{jobname}.{ext}"))),
+            );
+        }
+
+        Ok(Self { files })
     }
 
-    pub fn run(self) -> Result<()> {
-        log::debug!("Cleaning output files: {} {:?}", self.executable, self.args);
-        let result = std::process::Command::new(self.executable)
-            .args(self.args)
-            .stdin(Stdio::null())
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .status();
-
-        if let Err(why) = result {
-            anyhow::bail!("failed to spawn process: {why}")
+    /// Returns the files that would be deleted without touching them.
+    pub fn dry_run(&self) -> Vec<PathBuf> {
+        self.files
+            .iter()
+            .filter(|file| file.is_file())
+            .cloned()
+            .collect()
+    }
+
+    /// Deletes the output files and returns the files that have been removed.
+    pub fn run(self) -> Result<Vec<PathBuf>> {
+        log::debug!("Cleaning output files: {:?}", self.files);
+
+        let mut deleted = Vec::new();
+        for file in self.files.iter().filter(|file| file.is_file()) {
+            if let Err(why) = std::fs::remove_file(file) {
+                anyhow::bail!("failed to delete '{}': {why}", file.display())
+            }
+
+            deleted.push(file.clone());
         }
 
-        Ok(())
+        Ok(deleted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use base_db::Config;
+    use test_utils::fixture::Fixture;
+
+    use super::{CleanCommand, CleanTarget};
+
+    #[test]
+    fn test_auxiliary() {
+        let fixture = Fixture::parse(
+            r#"
+%! main.tex
+\documentclass{article}
+\begin{document}\include{chapter}\end{document}
+
+%! chapter.tex
+\section{Foo}"#,
+        );
+
+        let document = fixture.workspace.lookup(&fixture.documents[1].uri).unwrap();
+        let command =
+            CleanCommand::new(&fixture.workspace, document, CleanTarget::Auxiliary).unwrap();
+
+        let files = &command.files;
+        assert!(files.contains(&PathBuf::from("/texlab/main.aux")));
+        assert!(files.contains(&PathBuf::from("/texlab/main.run.xml")));
+        assert!(files.contains(&PathBuf::from("/texlab/main.synctex.gz")));
+        assert!(!files.contains(&PathBuf::from("/texlab/main.pdf")));
+    }
+
+    #[test]
+    fn test_artifacts() {
+        let mut fixture = Fixture::parse(
+            r#"
+%! main.tex
+\documentclass{article}"#,
+        );
+
+        let mut config = Config::default();
+        config.build.output_dir = String::from("build");
+        config.build.output_filename = Some(PathBuf::from("thesis.pdf"));
+        fixture.workspace.set_config(config);
+
+        let document = fixture.workspace.lookup(&fixture.documents[0].uri).unwrap();
+        let command =
+            CleanCommand::new(&fixture.workspace, document, CleanTarget::Artifacts).unwrap();
+
+        let files = &command.files;
+        assert!(files.contains(&PathBuf::from("/texlab/build/thesis.log")));
+        assert!(files.contains(&PathBuf::from("/texlab/build/thesis.pdf")));
+        assert!(files.contains(&PathBuf::from("/texlab/build/thesis.dvi")));
+    }
+
+    #[test]
+    fn test_aux_dir() {
+        let mut fixture = Fixture::parse(
+            r#"
+%! main.tex
+\documentclass{article}"#,
+        );
+
+        let mut config = Config::default();
+        config.build.output_dir = String::from("build");
+        config.build.aux_dir = Some(String::from("build/aux"));
+        fixture.workspace.set_config(config);
+
+        let document = fixture.workspace.lookup(&fixture.documents[0].uri).unwrap();
+        let command =
+            CleanCommand::new(&fixture.workspace, document, CleanTarget::Artifacts).unwrap();
+
+        let files = &command.files;
+        assert!(files.contains(&PathBuf::from("/texlab/build/aux/main.log")));
+        assert!(files.contains(&PathBuf::from("/texlab/build/main.pdf")));
+        assert!(!files.contains(&PathBuf::from("/texlab/build/main.log")));
+    }
+
+    #[test]
+    fn test_latexmkrc() {
+        let fixture = Fixture::parse(
+            r#"
+%! main.tex
+\documentclass{article}
+
+%! .latexmkrc
+$out_dir = 'out';
+$aux_dir = "aux";
+$jobname = '%A-draft';"#,
+        );
+
+        let document = fixture.workspace.lookup(&fixture.documents[0].uri).unwrap();
+        let command =
+            CleanCommand::new(&fixture.workspace, document, CleanTarget::Artifacts).unwrap();
+
+        let files = &command.files;
+        assert!(files.contains(&PathBuf::from("/texlab/aux/main-draft.aux")));
+        assert!(files.contains(&PathBuf::from("/texlab/out/main-draft.synctex.gz")));
+        assert!(files.contains(&PathBuf::from("/texlab/out/main-draft.pdf")));
+        assert!(!files.contains(&PathBuf::from("/texlab/aux/main-draft.pdf")));
     }
 }
@@ -1,9 +1,9 @@
 use std::io::Write;
 
 use anyhow::Result;
-use base_db::{graph, Document, Workspace};
+use base_db::{graph::EdgeKind, Document, Workspace};
 use itertools::Itertools;
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 
 pub fn show_dependency_graph(workspace: &Workspace) -> Result<String> {
     let documents = workspace
@@ -37,21 +37,229 @@ pub fn show_dependency_graph(workspace: &Workspace) -> Result<String> {
         writeln!(&mut writer, "\t{node} [label=\"{label}\", shape={shape}];")?;
     }
 
-    for edge in workspace
+    let graphs: Vec<_> = workspace
         .iter()
-        .flat_map(|start| graph::Graph::new(workspace, start).edges)
-        .unique()
-    {
+        .map(|start| workspace.graph(start))
+        .collect();
+    let cycles: FxHashSet<_> = graphs
+        .iter()
+        .flat_map(|graph| graph.cycles.iter().flatten())
+        .collect();
+
+    for edge in graphs.iter().flat_map(|graph| graph.edges.iter()).unique() {
         let source = &documents[edge.source];
         let target = &documents[edge.target];
-        let label = edge
-            .weight
-            .as_ref()
-            .map_or("<artifact>", |weight| &weight.link.path.text);
+        let (label, style) = match (edge.kind, &edge.weight) {
+            (EdgeKind::Link, Some(weight)) => (weight.link.path.text.as_str(), "solid"),
+            (EdgeKind::Recorder, _) => ("<recorder>", "dashed"),
+            (EdgeKind::Manifest, _) => ("<manifest>", "solid"),
+            _ => ("<artifact>", "solid"),
+        };
 
-        writeln!(&mut writer, "\t{source} -> {target} [label=\"{label}\"];")?;
+        // Edges that are part of an include cycle are highlighted.
+        let color = if cycles.contains(edge) {
+            ", color=red"
+        } else {
+            ""
+        };
+        writeln!(
+            &mut writer,
+            "\t{source} -> {target} [label=\"{label}\", style={style}{color}];"
+        )?;
+    }
+
+    // The files that are not documents are only known from the recorder.
+    let assets = graphs
+        .iter()
+        .flat_map(|graph| graph.assets.iter().map(|uri| (graph.start, uri)))
+        .unique()
+        .collect::<Vec<_>>();
+
+    let asset_nodes = assets
+        .iter()
+        .map(|(_, uri)| *uri)
+        .unique()
+        .enumerate()
+        .map(|(i, uri)| (uri, format!("a{i:0>5}")))
+        .collect::<FxHashMap<_, _>>();
+
+    for (uri, node) in &asset_nodes {
+        writeln!(&mut writer, "\t{node} [label=\"{uri}\", shape=note];")?;
+    }
+
+    for (start, uri) in assets {
+        let source = &documents[start];
+        let target = &asset_nodes[uri];
+        writeln!(
+            &mut writer,
+            "\t{source} -> {target} [label=\"<recorder>\", style=dashed];"
+        )?;
     }
 
     writeln!(&mut writer, "}}")?;
     Ok(String::from_utf8(writer)?)
 }
+
+#[cfg(test)]
+mod tests {
+    use base_db::{util::LineCol, Config, Owner, Workspace};
+    use distro::Language;
+    use test_utils::fixture::Fixture;
+
+    use super::show_dependency_graph;
+
+    #[test]
+    #[cfg(unix)]
+    fn test_recorder() {
+        let fixture = Fixture::parse(
+            r#"
+%! main.tex
+\documentclass{article}
+\newcommand{\chapter}[1]{\input{#1}}
+\begin{document}\chapter{intro}\input{conclusion}\end{document}
+
+%! intro.tex
+\section{Introduction}
+
+%! conclusion.tex
+\section{Conclusion}
+
+%! main.fls
+PWD /texlab
+INPUT /usr/share/texmf/tex/latex/base/article.cls
+INPUT ./main.tex
+INPUT ./intro.tex
+INPUT ./conclusion.tex
+INPUT ./main.aux
+OUTPUT ./main.aux"#,
+        );
+
+        let graph = show_dependency_graph(&fixture.workspace).unwrap();
+        let edges: Vec<_> = graph.lines().filter(|line| line.contains("->")).collect();
+        assert_eq!(edges.len(), 3);
+        assert_eq!(
+            edges
+                .iter()
+                .filter(|line| line.ends_with("[label=\"<recorder>\", style=dashed];"))
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_recorder_assets() {
+        let fixture = Fixture::parse(
+            r#"
+%! main.tex
+\documentclass{article}
+\begin{document}\lstinputlisting{code.py}\end{document}
+
+%! main.fls
+PWD /texlab
+INPUT ./main.tex
+INPUT ./code.py"#,
+        );
+
+        let graph = show_dependency_graph(&fixture.workspace).unwrap();
+        assert!(graph.contains("[label=\"file:///texlab/code.py\", shape=note];"));
+        assert_eq!(
+            graph
+                .lines()
+                .filter(|line| line.ends_with("[label=\"<recorder>\", style=dashed];"))
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_recorder_reload() {
+        let mut fixture = Fixture::parse(
+            r#"
+%! main.tex
+\documentclass{article}
+\newcommand{\chapter}[1]{\input{#1}}
+\begin{document}\chapter{intro}\end{document}
+
+%! intro.tex
+\section{Introduction}
+
+%! main.fls
+PWD /texlab
+INPUT ./main.tex"#,
+        );
+
+        let count_recorder_edges = |workspace: &Workspace| {
+            let graph = show_dependency_graph(workspace).unwrap();
+            graph
+                .lines()
+                .filter(|line| line.ends_with("[label=\"<recorder>\", style=dashed];"))
+                .count()
+        };
+
+        assert_eq!(count_recorder_edges(&fixture.workspace), 0);
+
+        // The `.fls` file is reloaded after another build.
+        fixture.workspace.open(
+            fixture.documents[2].uri.clone(),
+            String::from("PWD /texlab\nINPUT ./main.tex\nINPUT ./intro.tex"),
+            Language::Fls,
+            Owner::Server,
+            LineCol { line: 0, col: 0 },
+        );
+
+        assert_eq!(count_recorder_edges(&fixture.workspace), 1);
+    }
+
+    #[test]
+    fn test_aux_dir() {
+        let mut fixture = Fixture::parse(
+            r#"
+%! main.tex
+\documentclass{article}
+
+%! build/aux/main.aux
+\relax
+
+%! main.aux
+\relax"#,
+        );
+
+        let mut config = Config::default();
+        config.build.output_dir = String::from("build");
+        config.build.aux_dir = Some(String::from("build/aux"));
+        fixture.workspace.set_config(config);
+
+        let graph = show_dependency_graph(&fixture.workspace).unwrap();
+        let edges: Vec<_> = graph.lines().filter(|line| line.contains("->")).collect();
+        assert_eq!(edges.len(), 1);
+    }
+
+    #[test]
+    fn test_include_cycle() {
+        let fixture = Fixture::parse(
+            r#"
+%! main.tex
+\documentclass{article}
+\begin{document}\input{a}\end{document}
+
+%! a.tex
+\input{b}
+
+%! b.tex
+\input{a}"#,
+        );
+
+        let graph = show_dependency_graph(&fixture.workspace).unwrap();
+        let edges: Vec<_> = graph.lines().filter(|line| line.contains("->")).collect();
+        assert_eq!(edges.len(), 3);
+        assert_eq!(
+            edges
+                .iter()
+                .filter(|line| line.ends_with("color=red];"))
+                .count(),
+            2
+        );
+    }
+}
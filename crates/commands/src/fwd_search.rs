@@ -1,15 +1,21 @@
 use std::{
-    ffi::OsStr,
+    io::Read,
     path::{Path, PathBuf},
-    process::Stdio,
+    process::{ExitStatus, Stdio},
+    str::FromStr,
 };
 
 use anyhow::Result;
-use base_db::Workspace;
+use base_db::{semantics::tectonic::Output, util::LineCol, Document, SynctexConfig, Workspace};
 use thiserror::Error;
 use url::Url;
 
-use crate::placeholders::replace_placeholders;
+use crate::{
+    build::ProjectDirs,
+    placeholders::{
+        replace_placeholders, validate_placeholders, PlaceholderError, FORWARD_SEARCH_PLACEHOLDERS,
+    },
+};
 
 #[derive(Debug, Error)]
 pub enum ForwardSearchError {
@@ -28,8 +34,86 @@ pub enum ForwardSearchError {
     #[error("PDF document \"{0}\" not found")]
     PdfNotFound(PathBuf),
 
+    #[error("Unknown PDF viewer \"{0}\"")]
+    UnknownViewer(String),
+
+    #[error("PDF viewer \"{0}\" not found")]
+    ViewerNotFound(String),
+
     #[error("Unable to launch PDF viewer: {0}")]
     LaunchViewer(#[from] std::io::Error),
+
+    #[error("PDF viewer exited with {status}: {stderr}")]
+    ViewerFailed { status: ExitStatus, stderr: String },
+
+    #[error("Invalid forward search arguments: {0}")]
+    Placeholder(#[from] PlaceholderError),
+}
+
+/// PDF viewers whose command line for forward search is known in advance.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ForwardSearchViewer {
+    Zathura,
+    Okular,
+    Evince,
+    Sioyek,
+    Qpdfview,
+    Sumatrapdf,
+}
+
+impl ForwardSearchViewer {
+    pub fn program(self) -> &'static str {
+        match self {
+            Self::Zathura => "zathura",
+            Self::Okular => "okular",
+            Self::Evince => "evince-synctex",
+            Self::Sioyek => "sioyek",
+            Self::Qpdfview => "qpdfview",
+            Self::Sumatrapdf => "SumatraPDF",
+        }
+    }
+
+    pub fn args(self) -> &'static [&'static str] {
+        match self {
+            Self::Zathura => &["--synctex-forward", "%l:1:%f", "%p"],
+            Self::Okular => &["--unique", "file:%p#src:%l%f"],
+            Self::Evince => &["-f", "%l", "%p"],
+            Self::Sioyek => &[
+                "--reuse-window",
+                "--forward-search-file",
+                "%f",
+                "--forward-search-line",
+                "%l",
+                "%p",
+            ],
+            Self::Qpdfview => &["--unique", "%p#src:%f:%l:1"],
+            Self::Sumatrapdf => &["-reuse-instance", "%p", "-forward-search", "%f", "%l"],
+        }
+    }
+
+    /// Returns the configuration of the viewer. The executable and the arguments can be overridden.
+    pub fn config(self, program: Option<String>, args: Option<Vec<String>>) -> SynctexConfig {
+        SynctexConfig {
+            program: program.unwrap_or_else(|| String::from(self.program())),
+            args: args.unwrap_or_else(|| self.args().iter().map(|arg| String::from(*arg)).collect()),
+        }
+    }
+}
+
+impl FromStr for ForwardSearchViewer {
+    type Err = ForwardSearchError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_lowercase().as_str() {
+            "zathura" => Ok(Self::Zathura),
+            "okular" => Ok(Self::Okular),
+            "evince" | "synctex-dbus" | "evince-synctex" => Ok(Self::Evince),
+            "sioyek" => Ok(Self::Sioyek),
+            "qpdfview" => Ok(Self::Qpdfview),
+            "sumatrapdf" => Ok(Self::Sumatrapdf),
+            _ => Err(ForwardSearchError::UnknownViewer(String::from(name))),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -42,7 +126,7 @@ impl ForwardSearch {
     pub fn new(
         workspace: &Workspace,
         uri: &Url,
-        line: Option<u32>,
+        position: Option<LineCol>,
     ) -> Result<Self, ForwardSearchError> {
         let Some(config) = &workspace.config().synctex else {
             return Err(ForwardSearchError::Unconfigured);
@@ -65,46 +149,251 @@ impl ForwardSearch {
             return Err(ForwardSearchError::InvalidPath(child.uri.clone()));
         };
 
-        let override_path = workspace.config().build.output_filename.as_deref();
-
-        let Some(pdf_path) = override_path.or(parent.path.as_deref())
-            .and_then(Path::file_stem)
-            .and_then(OsStr::to_str)
-            .map(|stem| dir.join(format!("{stem}.pdf"))) else 
-        {
+        let Some(pdf_path) = tectonic_pdf_path(workspace, child).or_else(|| {
+            workspace
+                .jobname(parent)
+                .map(|stem| dir.join(format!("{stem}.pdf")))
+        }) else {
             return Err(ForwardSearchError::InvalidPath(parent.uri.clone()));
         };
 
-        if !pdf_path.exists() {
+        let Ok(pdf_modified) = pdf_path.metadata().and_then(|meta| meta.modified()) else {
             return Err(ForwardSearchError::PdfNotFound(pdf_path));
+        };
+
+        let tex_modified = tex_path.metadata().and_then(|meta| meta.modified());
+        if tex_modified.map_or(false, |tex_modified| tex_modified > pdf_modified) {
+            // The viewer can still show the old output, for example while a build is running.
+            log::warn!(
+                "PDF document \"{}\" is older than the TeX document",
+                pdf_path.display()
+            );
         }
 
         let tex_path = tex_path.to_string_lossy().into_owned();
         let pdf_path = pdf_path.to_string_lossy().into_owned();
-        let line = line.unwrap_or(child.cursor.line);
-        let line = (line + 1).to_string();
+        let position = position.unwrap_or(child.cursor);
+        let line = (position.line + 1).to_string();
+        let column = (position.col + 1).to_string();
+
+        let root_path = parent.path.as_deref().and_then(Path::to_str);
+        let jobname = workspace.jobname(parent);
+        let dirs = ProjectDirs::new(workspace, parent);
 
         let program = config.program.clone();
         let args = replace_placeholders(
             &config.args,
-            &[('f', &tex_path), ('p', &pdf_path), ('l', &line)],
-        );
+            &[
+                ('f', Some(&tex_path)),
+                ('p', Some(&pdf_path)),
+                ('l', Some(&line)),
+                ('c', Some(&column)),
+                ('r', root_path),
+                ('j', jobname.as_deref()),
+                ('d', dirs.root_dir.as_deref()),
+                ('o', dirs.output_dir.as_deref()),
+                ('a', dirs.aux_dir.as_deref()),
+            ],
+        )?;
 
         Ok(Self { program, args })
     }
 }
 
 impl ForwardSearch {
+    /// Checks that the configured viewer can be found and that its arguments are valid
+    /// before the user attempts a forward search.
+    pub fn validate(config: &SynctexConfig) -> Result<(), ForwardSearchError> {
+        validate_placeholders(&config.args, FORWARD_SEARCH_PLACEHOLDERS)?;
+        if find_executable(&config.program).is_none() {
+            return Err(ForwardSearchError::ViewerNotFound(config.program.clone()));
+        }
+
+        Ok(())
+    }
+
     pub fn run(self) -> Result<(), ForwardSearchError> {
         log::debug!("Executing forward search: {} {:?}", self.program, self.args);
 
-        std::process::Command::new(self.program)
+        if find_executable(&self.program).is_none() {
+            return Err(ForwardSearchError::ViewerNotFound(self.program));
+        }
+
+        let mut process = std::process::Command::new(self.program)
             .args(self.args)
             .stdin(Stdio::null())
             .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .status()?;
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        // Some viewers keep running in the foreground so the output needs to be drained while waiting.
+        let mut stderr = process.stderr.take().unwrap();
+        let reader = std::thread::spawn(move || {
+            let mut buffer = Vec::new();
+            let _ = stderr.read_to_end(&mut buffer);
+            buffer
+        });
+
+        let status = process.wait()?;
+        if !status.success() {
+            let stderr = reader.join().unwrap_or_default();
+            let stderr = String::from_utf8_lossy(&stderr).trim().to_string();
+            return Err(ForwardSearchError::ViewerFailed { status, stderr });
+        }
 
         Ok(())
     }
 }
+
+/// Tectonic writes the PDF of a project to `build/<output>/<output>.pdf`.
+fn tectonic_pdf_path(workspace: &Workspace, child: &Document) -> Option<PathBuf> {
+    let project = workspace.tectonic_root(child)?;
+    let data = project.data.as_tectonic()?;
+    let path = data.semantics.outputs.iter().find_map(Output::pdf_path)?;
+    project.dir.join(&path).ok()?.to_file_path().ok()
+}
+
+/// Looks up the program in the same way as the operating system when launching it.
+fn find_executable(program: &str) -> Option<PathBuf> {
+    let path = Path::new(program);
+    if path.components().count() > 1 {
+        return path.is_file().then(|| path.to_path_buf());
+    }
+
+    let extensions: Vec<String> = if cfg!(windows) {
+        std::env::var("PATHEXT")
+            .unwrap_or_else(|_| String::from(".EXE;.CMD;.BAT;.COM"))
+            .split(';')
+            .map(String::from)
+            .chain(std::iter::once(String::new()))
+            .collect()
+    } else {
+        vec![String::new()]
+    };
+
+    let paths = std::env::var_os("PATH")?;
+    std::env::split_paths(&paths)
+        .flat_map(|dir| {
+            extensions
+                .iter()
+                .map(move |ext| dir.join(format!("{program}{ext}")))
+        })
+        .find(|path| path.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use base_db::SynctexConfig;
+    use test_utils::fixture::Fixture;
+
+    use crate::placeholders::PlaceholderError;
+
+    use super::{tectonic_pdf_path, ForwardSearch, ForwardSearchError, ForwardSearchViewer};
+
+    #[test]
+    fn test_viewer_from_name() {
+        assert_eq!("Zathura".parse().ok(), Some(ForwardSearchViewer::Zathura));
+        assert_eq!("synctex-dbus".parse().ok(), Some(ForwardSearchViewer::Evince));
+        assert_eq!("SumatraPDF".parse().ok(), Some(ForwardSearchViewer::Sumatrapdf));
+        assert!(matches!(
+            "foo".parse::<ForwardSearchViewer>(),
+            Err(ForwardSearchError::UnknownViewer(name)) if name == "foo"
+        ));
+    }
+
+    #[test]
+    fn test_viewer_config() {
+        let config = ForwardSearchViewer::Okular.config(None, None);
+        assert_eq!(config.program, "okular");
+        assert_eq!(config.args, vec!["--unique", "file:%p#src:%l%f"]);
+
+        let args = vec![String::from("%p")];
+        let config = ForwardSearchViewer::Okular.config(Some(String::from("foo")), Some(args));
+        assert_eq!(config.program, "foo");
+        assert_eq!(config.args, vec!["%p"]);
+    }
+
+    #[test]
+    fn test_validate_missing_viewer() {
+        let config = SynctexConfig {
+            program: String::from("texlab-missing-viewer"),
+            args: Vec::new(),
+        };
+
+        assert!(matches!(
+            ForwardSearch::validate(&config),
+            Err(ForwardSearchError::ViewerNotFound(program)) if program == "texlab-missing-viewer"
+        ));
+    }
+
+    #[test]
+    fn test_validate_unknown_placeholder() {
+        let config = SynctexConfig {
+            program: String::from("sh"),
+            args: vec![String::from("%p#%x")],
+        };
+
+        assert!(matches!(
+            ForwardSearch::validate(&config),
+            Err(ForwardSearchError::Placeholder(PlaceholderError::Unknown(
+                'x',
+                _
+            )))
+        ));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_viewer_failed() {
+        let command = ForwardSearch {
+            program: String::from("sh"),
+            args: vec![String::from("-c"), String::from("echo foo >&2; exit 3")],
+        };
+
+        assert!(matches!(
+            command.run(),
+            Err(ForwardSearchError::ViewerFailed { status, stderr })
+                if status.code() == Some(3) && stderr == "foo"
+        ));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_tectonic_pdf_path() {
+        let fixture = Fixture::parse(
+            r#"
+%! Tectonic.toml
+[doc]
+name = "thesis"
+
+[[output]]
+name = "web"
+type = "html"
+
+[[output]]
+name = "print"
+type = "pdf"
+
+%! src/index.tex
+\input{chapter}
+
+%! src/chapter.tex
+\section{Foo}
+
+%! other.tex
+\section{Bar}"#,
+        );
+
+        let workspace = &fixture.workspace;
+        let chapter = workspace.lookup(&fixture.documents[2].uri).unwrap();
+        assert_eq!(
+            tectonic_pdf_path(workspace, chapter),
+            Some(PathBuf::from("/texlab/build/print/print.pdf"))
+        );
+
+        let other = workspace.lookup(&fixture.documents[3].uri).unwrap();
+        assert_eq!(tectonic_pdf_path(workspace, other), None);
+    }
+}
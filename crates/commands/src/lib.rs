@@ -1,16 +1,20 @@
 mod build;
+mod build_state;
 mod change_env;
 mod clean;
 mod dep_graph;
 mod find_envs;
 mod fwd_search;
 mod placeholders;
+mod synctex;
 
 pub use self::{
-    build::{BuildCommand, BuildError},
+    build::{BuildCommand, BuildError, BuildStep},
+    build_state::{build_state, BuildState},
     change_env::{change_environment, ChangeEnvironmentResult},
     clean::{CleanCommand, CleanTarget},
     dep_graph::show_dependency_graph,
     find_envs::find_environments,
-    fwd_search::{ForwardSearch, ForwardSearchError},
+    fwd_search::{ForwardSearch, ForwardSearchError, ForwardSearchViewer},
+    synctex::{SyncTex, SyncTexBox, SyncTexError, SyncTexSource},
 };
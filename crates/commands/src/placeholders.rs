@@ -1,50 +1,161 @@
 use rustc_hash::FxHashMap;
+use thiserror::Error;
 
-pub fn replace_placeholders(args: &[String], pairs: &[(char, &str)]) -> Vec<String> {
+/// The placeholders that can be used in the arguments of the build program.
+pub(crate) const BUILD_PLACEHOLDERS: &[char] = &['f', 'r', 'j', 'o', 'a', 'd'];
+
+/// The placeholders that can be used in the arguments of the PDF viewer.
+pub(crate) const FORWARD_SEARCH_PLACEHOLDERS: &[char] =
+    &['f', 'p', 'l', 'c', 'r', 'j', 'o', 'a', 'd'];
+
+#[derive(Debug, PartialEq, Eq, Clone, Error)]
+pub enum PlaceholderError {
+    #[error("Unknown placeholder \"%{0}\" in argument \"{1}\"")]
+    Unknown(char, String),
+
+    #[error("Unterminated conditional segment in argument \"{0}\"")]
+    Unterminated(String),
+}
+
+/// Checks that the arguments only refer to the given placeholders.
+pub fn validate_placeholders(args: &[String], keys: &[char]) -> Result<(), PlaceholderError> {
+    let pairs: Vec<_> = keys.iter().map(|key| (*key, Some(""))).collect();
+    replace_placeholders(args, &pairs).map(drop)
+}
+
+/// Replaces the placeholders (`%f`) in the arguments with their values.
+///
+/// A conditional segment (`%{-outdir=%o}`) is only kept if all of its placeholders have a value.
+/// Arguments that become empty because a segment has been removed are dropped.
+/// `%%` produces a literal percent sign and arguments in double quotes are passed as is.
+pub fn replace_placeholders(
+    args: &[String],
+    pairs: &[(char, Option<&str>)],
+) -> Result<Vec<String>, PlaceholderError> {
     let map = FxHashMap::from_iter(pairs.iter().copied());
-    args.iter()
-        .map(|input| {
-            let quoted = input
-                .strip_prefix('"')
-                .and_then(|input| input.strip_suffix('"'));
-
-            match quoted {
-                Some(output) => String::from(output),
-                None => {
-                    let mut output = String::new();
-                    let mut chars = input.chars();
-                    while let Some(ch) = chars.next() {
-                        if ch == '%' {
-                            match chars.next() {
-                                Some(key) => match map.get(&key) {
-                                    Some(value) => output.push_str(value),
-                                    None => output.push(key),
-                                },
-                                None => output.push('%'),
-                            };
-                        } else {
-                            output.push(ch);
-                        }
-                    }
-
-                    output
+    let mut output = Vec::new();
+    for arg in args {
+        let quoted = arg.strip_prefix('"').and_then(|arg| arg.strip_suffix('"'));
+
+        if let Some(quoted) = quoted {
+            output.push(String::from(quoted));
+            continue;
+        }
+
+        let expansion = expand(arg, arg, &map, true)?;
+        if !expansion.skipped || !expansion.text.is_empty() {
+            output.push(expansion.text);
+        }
+    }
+
+    Ok(output)
+}
+
+#[derive(Debug, Default)]
+struct Expansion {
+    text: String,
+    /// At least one placeholder does not have a value.
+    missing: bool,
+    /// At least one conditional segment has been removed.
+    skipped: bool,
+}
+
+fn expand(
+    input: &str,
+    arg: &str,
+    map: &FxHashMap<char, Option<&str>>,
+    allow_segments: bool,
+) -> Result<Expansion, PlaceholderError> {
+    let mut expansion = Expansion::default();
+    let mut chars = input.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '%' {
+            expansion.text.push(ch);
+            continue;
+        }
+
+        match chars.next() {
+            None | Some('%') => expansion.text.push('%'),
+            Some('{') if allow_segments => {
+                let Some((segment, rest)) = chars.as_str().split_once('}') else {
+                    return Err(PlaceholderError::Unterminated(String::from(arg)));
+                };
+
+                let inner = expand(segment, arg, map, false)?;
+                if inner.missing {
+                    expansion.skipped = true;
+                } else {
+                    expansion.text.push_str(&inner.text);
                 }
+
+                chars = rest.chars();
             }
-        })
-        .collect()
+            Some(key) => match map.get(&key) {
+                Some(Some(value)) => expansion.text.push_str(value),
+                Some(None) => expansion.missing = true,
+                None => return Err(PlaceholderError::Unknown(key, String::from(arg))),
+            },
+        };
+    }
+
+    Ok(expansion)
 }
 
 #[cfg(test)]
 mod tests {
-    use super::replace_placeholders;
+    use super::{replace_placeholders, validate_placeholders, PlaceholderError};
 
     #[test]
     fn test_quoted() {
         let output = replace_placeholders(
             &["foo".into(), "\"%f\"".into(), "%%f".into(), "%fbar".into()],
-            &[('f', "foo")],
+            &[('f', Some("foo"))],
+        );
+
+        assert_eq!(
+            output,
+            Ok(vec![
+                "foo".into(),
+                "%f".into(),
+                "%f".into(),
+                "foobar".into()
+            ])
+        );
+    }
+
+    #[test]
+    fn test_conditional() {
+        let args = [
+            "%{-outdir=%o}".into(),
+            "%{-auxdir=%a}".into(),
+            "-jobname=%j".into(),
+        ];
+        let output = replace_placeholders(
+            &args,
+            &[('o', Some("build")), ('a', None), ('j', Some("main"))],
         );
 
-        assert_eq!(output, vec!["foo".into(), "%f", "%f".into(), "foobar"]);
+        assert_eq!(
+            output,
+            Ok(vec!["-outdir=build".into(), "-jobname=main".into()])
+        );
+    }
+
+    #[test]
+    fn test_unknown() {
+        let args = ["-x".into(), "%q".into()];
+        assert_eq!(
+            validate_placeholders(&args, &['f']),
+            Err(PlaceholderError::Unknown('q', "%q".into()))
+        );
+    }
+
+    #[test]
+    fn test_unterminated() {
+        let args = ["%{-outdir=%o".into()];
+        assert_eq!(
+            validate_placeholders(&args, &['o']),
+            Err(PlaceholderError::Unterminated("%{-outdir=%o".into()))
+        );
     }
 }
@@ -0,0 +1,450 @@
+use std::{
+    ffi::OsStr,
+    io::Read,
+    path::{Component, Path, PathBuf},
+};
+
+use base_db::{Document, Workspace};
+use flate2::read::GzDecoder;
+use rustc_hash::FxHashMap;
+use thiserror::Error;
+use url::Url;
+
+/// The number of scaled points in a PostScript point.
+const SP_PER_BP: f32 = 65781.76;
+
+#[derive(Debug, Error)]
+pub enum SyncTexError {
+    #[error("TeX document \"{0}\" not found")]
+    TexNotFound(Url),
+
+    #[error("Document \"{0}\" does not exist on the local file system")]
+    NotLocal(Url),
+
+    #[error("SyncTeX file of document \"{0}\" not found")]
+    SyncTexNotFound(Url),
+
+    #[error("Unable to read SyncTeX file: {0}")]
+    Read(#[from] std::io::Error),
+
+    #[error("SyncTeX file \"{0}\" is invalid")]
+    Invalid(PathBuf),
+}
+
+/// A rectangle on a page of the PDF in PostScript points.
+/// The origin is the top left corner of the page.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct SyncTexBox {
+    pub page: u32,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SyncTexSource {
+    pub path: PathBuf,
+    /// Zero-based
+    pub line: u32,
+    /// Zero-based
+    pub column: Option<u32>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum RecordKind {
+    VBox,
+    HBox,
+    VoidVBox,
+    VoidHBox,
+    Kern,
+    Glue,
+    Math,
+    Current,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Record {
+    kind: RecordKind,
+    tag: u32,
+    line: u32,
+    column: Option<u32>,
+    h: f32,
+    v: f32,
+    width: f32,
+    height: f32,
+    depth: f32,
+}
+
+impl Record {
+    fn is_box(&self) -> bool {
+        matches!(
+            self.kind,
+            RecordKind::VBox | RecordKind::HBox | RecordKind::VoidVBox | RecordKind::VoidHBox
+        )
+    }
+
+    fn contains(&self, x: f32, y: f32) -> bool {
+        self.is_box()
+            && x >= self.h
+            && x <= self.h + self.width
+            && y >= self.v - self.height
+            && y <= self.v + self.depth
+    }
+
+    fn area(&self) -> f32 {
+        self.width * (self.height + self.depth)
+    }
+
+    fn distance(&self, x: f32, y: f32) -> f32 {
+        let dx = (self.h - x).max(0.0).max(x - self.h - self.width);
+        let dy = (self.v - self.height - y)
+            .max(0.0)
+            .max(y - self.v - self.depth);
+        dx * dx + dy * dy
+    }
+}
+
+#[derive(Debug)]
+struct Page {
+    number: u32,
+    records: Vec<Record>,
+}
+
+/// The contents of a `.synctex` file that links the PDF to the TeX sources.
+#[derive(Debug)]
+pub struct SyncTex {
+    inputs: FxHashMap<u32, PathBuf>,
+    pages: Vec<Page>,
+}
+
+impl SyncTex {
+    /// Finds and reads the SyncTeX file that belongs to the project of the given document.
+    pub fn load(workspace: &Workspace, uri: &Url) -> Result<Self, SyncTexError> {
+        let Some(child) = workspace.lookup(uri) else {
+            return Err(SyncTexError::TexNotFound(uri.clone()));
+        };
+
+        let parent = workspace.parents(child).into_iter().next().unwrap_or(child);
+
+        let Ok(working_dir) = workspace.current_dir(&parent.dir).to_file_path() else {
+            return Err(SyncTexError::NotLocal(parent.uri.clone()));
+        };
+
+        let Some(path) = find_synctex_file(workspace, parent) else {
+            return Err(SyncTexError::SyncTexNotFound(parent.uri.clone()));
+        };
+
+        let file = std::fs::File::open(&path)?;
+        let mut text = String::new();
+        if path.extension() == Some(OsStr::new("gz")) {
+            GzDecoder::new(file).read_to_string(&mut text)?;
+        } else {
+            std::io::BufReader::new(file).read_to_string(&mut text)?;
+        }
+
+        Self::parse(&text, &working_dir).ok_or(SyncTexError::Invalid(path))
+    }
+
+    /// Parses the text of a SyncTeX file.
+    /// Relative input paths are resolved against the directory in which the document was compiled.
+    pub fn parse(text: &str, working_dir: &Path) -> Option<Self> {
+        let mut inputs = FxHashMap::default();
+        let mut pages = Vec::new();
+        let mut unit = 1.0;
+        let mut magnification = 1.0;
+        let mut x_offset = 0.0;
+        let mut y_offset = 0.0;
+
+        let mut lines = text.lines();
+        if !lines.next()?.starts_with("SyncTeX Version:") {
+            return None;
+        }
+
+        for line in lines {
+            if let Some(input) = line.strip_prefix("Input:") {
+                let (tag, path) = input.split_once(':')?;
+                let path = normalize_path(&working_dir.join(path));
+                inputs.insert(tag.parse().ok()?, path);
+            } else if let Some(value) = line.strip_prefix("Unit:") {
+                unit = value.parse().ok()?;
+            } else if let Some(value) = line.strip_prefix("Magnification:") {
+                magnification = value.parse::<f32>().ok()? / 1000.0;
+            } else if let Some(value) = line.strip_prefix("X Offset:") {
+                x_offset = value.parse().ok()?;
+            } else if let Some(value) = line.strip_prefix("Y Offset:") {
+                y_offset = value.parse().ok()?;
+            } else if line.starts_with("Postamble:") {
+                break;
+            } else if let Some(number) = line.strip_prefix('{') {
+                let number = number.parse().ok()?;
+                pages.push(Page {
+                    number,
+                    records: Vec::new(),
+                });
+            } else if let Some(page) = pages.last_mut() {
+                let Some(mut record) = parse_record(line) else { continue };
+                let scale =
+                    |value: f32, offset: f32| (value * unit + offset) * magnification / SP_PER_BP;
+
+                record.h = scale(record.h, x_offset);
+                record.v = scale(record.v, y_offset);
+                record.width = scale(record.width, 0.0);
+                record.height = scale(record.height, 0.0);
+                record.depth = scale(record.depth, 0.0);
+                page.records.push(record);
+            }
+        }
+
+        Some(Self { inputs, pages })
+    }
+
+    /// Finds the area of the PDF that has been generated from the given line.
+    /// If the line itself does not produce any output, the next line that does is used instead.
+    pub fn forward(&self, path: &Path, line: u32) -> Option<SyncTexBox> {
+        let path = normalize_path(path);
+        let tags: Vec<_> = self
+            .inputs
+            .iter()
+            .filter(|(_, input)| **input == path)
+            .map(|(tag, _)| *tag)
+            .collect();
+
+        let line = line + 1;
+        let records = self
+            .pages
+            .iter()
+            .flat_map(|page| page.records.iter().map(move |record| (page.number, record)))
+            .filter(|(_, record)| tags.contains(&record.tag) && record.line >= line);
+
+        let target_line = records.clone().map(|(_, record)| record.line).min()?;
+        let records = records.filter(|(_, record)| record.line == target_line);
+
+        // Vertical boxes usually span whole paragraphs or pages, so we avoid them if possible.
+        let has_lines = records
+            .clone()
+            .any(|(_, record)| record.kind != RecordKind::VBox);
+
+        let mut records =
+            records.filter(|(_, record)| !has_lines || record.kind != RecordKind::VBox);
+
+        let (page, first) = records.next()?;
+
+        let mut left = first.h;
+        let mut top = first.v - first.height;
+        let mut right = first.h + first.width;
+        let mut bottom = first.v + first.depth;
+        for (_, record) in records.take_while(|(number, _)| *number == page) {
+            left = left.min(record.h);
+            top = top.min(record.v - record.height);
+            right = right.max(record.h + record.width);
+            bottom = bottom.max(record.v + record.depth);
+        }
+
+        Some(SyncTexBox {
+            page,
+            x: left,
+            y: top,
+            width: right - left,
+            height: bottom - top,
+        })
+    }
+
+    /// Finds the source location of the given point on a page of the PDF.
+    pub fn inverse(&self, page: u32, x: f32, y: f32) -> Option<SyncTexSource> {
+        let records = &self.pages.iter().find(|p| p.number == page)?.records;
+
+        let record = records
+            .iter()
+            .filter(|record| record.contains(x, y))
+            .min_by(|a, b| a.area().total_cmp(&b.area()))
+            .or_else(|| {
+                records
+                    .iter()
+                    .min_by(|a, b| a.distance(x, y).total_cmp(&b.distance(x, y)))
+            })?;
+
+        Some(SyncTexSource {
+            path: self.inputs.get(&record.tag)?.clone(),
+            line: record.line.saturating_sub(1),
+            column: record.column.map(|column| column.saturating_sub(1)),
+        })
+    }
+}
+
+fn parse_record(line: &str) -> Option<Record> {
+    let mut chars = line.chars();
+    let kind = match chars.next()? {
+        '[' => RecordKind::VBox,
+        '(' => RecordKind::HBox,
+        'v' => RecordKind::VoidVBox,
+        'h' => RecordKind::VoidHBox,
+        'k' => RecordKind::Kern,
+        'g' => RecordKind::Glue,
+        '$' => RecordKind::Math,
+        'x' => RecordKind::Current,
+        _ => return None,
+    };
+
+    let mut parts = chars.as_str().split(':');
+    let mut link = parts.next()?.split(',');
+    let tag = link.next()?.parse().ok()?;
+    let line = link.next()?.parse().ok()?;
+    let column = link
+        .next()
+        .and_then(|column| column.parse::<i32>().ok())
+        .and_then(|column| u32::try_from(column).ok());
+
+    let (h, v) = parts.next()?.split_once(',')?;
+    let h = h.parse().ok()?;
+    let v = v.parse().ok()?;
+
+    let mut size = parts
+        .next()
+        .into_iter()
+        .flat_map(|size| size.split(','))
+        .map(|value| value.parse::<f32>().ok());
+
+    let width = size.next().flatten().unwrap_or(0.0);
+    let height = size.next().flatten().unwrap_or(0.0);
+    let depth = size.next().flatten().unwrap_or(0.0);
+    Some(Record {
+        kind,
+        tag,
+        line,
+        column,
+        h,
+        v,
+        width,
+        height,
+        depth,
+    })
+}
+
+fn find_synctex_file(workspace: &Workspace, parent: &Document) -> Option<PathBuf> {
+    let base_dir = workspace.current_dir(&parent.dir);
+    let output_dir = workspace.output_dir(&base_dir).to_file_path().ok()?;
+    let aux_dir = workspace.aux_dir(&base_dir).to_file_path().ok()?;
+    let stem = workspace.jobname(parent)?;
+
+    // Depending on the version, `latexmk` moves the SyncTeX file out of the auxiliary directory.
+    [output_dir, aux_dir]
+        .into_iter()
+        .flat_map(|dir| ["synctex.gz", "synctex"].map(|ext| dir.join(format!("{stem}.{ext}"))))
+        .find(|path| path.exists())
+}
+
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                result.pop();
+            }
+            _ => result.push(component),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{SyncTex, SyncTexSource};
+
+    const SYNCTEX: &str = r#"SyncTeX Version:1
+Input:1:./main.tex
+Input:2:/texmf/article.cls
+Input:3:./chapter.tex
+Output:pdf
+Magnification:1000
+Unit:1
+X Offset:0
+Y Offset:0
+Content:
+!120
+{1
+[1,3:4736286,45040174:22609920,40303888,0
+(1,3:4736286,5395580:22609920,655360,0
+h1,3:4736286,5395580:1310720,655360,0
+x1,3:6047006,5395580
+)
+[3,2:4736286,7000000:22609920,1000000,0
+(3,2:4736286,6500000:10000000,655360,0
+k3,2:14736286,6500000:100000
+)
+(3,2:4736286,7000000:5000000,655360,0
+)
+]
+]
+}1
+{2
+[3,5:4736286,45040174:22609920,40303888,0
+(3,5:4736286,5395580:22609920,655360,0
+)
+]
+}2
+Postamble:
+Count:11
+Post scriptum:
+"#;
+
+    fn parse() -> SyncTex {
+        SyncTex::parse(SYNCTEX, Path::new("/texlab")).unwrap()
+    }
+
+    #[test]
+    fn test_forward() {
+        let result = parse()
+            .forward(Path::new("/texlab/chapter.tex"), 1)
+            .unwrap();
+
+        assert_eq!(result.page, 1);
+        assert!((result.x - 72.0).abs() < 0.01);
+        assert!((result.y - 88.85).abs() < 0.01);
+        assert!((result.width - 153.54).abs() < 0.01);
+        assert!((result.height - 17.56).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_forward_next_line() {
+        let result = parse()
+            .forward(Path::new("/texlab/chapter.tex"), 3)
+            .unwrap();
+
+        assert_eq!(result.page, 2);
+    }
+
+    #[test]
+    fn test_forward_unknown_file() {
+        assert_eq!(parse().forward(Path::new("/texlab/foo.tex"), 0), None);
+    }
+
+    #[test]
+    fn test_inverse() {
+        let result = parse().inverse(1, 80.0, 81.0).unwrap();
+        assert_eq!(
+            result,
+            SyncTexSource {
+                path: "/texlab/main.tex".into(),
+                line: 2,
+                column: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_inverse_nested() {
+        let result = parse().inverse(1, 80.0, 100.0).unwrap();
+        assert_eq!(result.path, Path::new("/texlab/chapter.tex"));
+        assert_eq!(result.line, 1);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(SyncTex::parse("foo", Path::new("/texlab")).is_none());
+    }
+}
@@ -0,0 +1,194 @@
+use std::borrow::Cow;
+
+use base_db::{
+    data::{BibtexDataModel, BibtexDialect},
+    semantics::tex::LinkKind,
+    BibDocumentData, Document, Workspace,
+};
+use rowan::ast::AstNode;
+use rustc_hash::{FxHashMap, FxHashSet};
+use syntax::bibtex::{self, HasDelims, HasEq, HasName, HasType, HasValue};
+
+use crate::{
+    types::{BibError, Diagnostic, DiagnosticData},
+    DiagnosticBuilder, DiagnosticSource,
+};
+
+/// Validates the entries of the bibliography files against the data model
+/// of the bibliography processor that is used by the project.
+#[derive(Default)]
+pub struct BibEntryErrors;
+
+impl DiagnosticSource for BibEntryErrors {
+    fn publish<'db>(
+        &'db mut self,
+        workspace: &'db Workspace,
+        builder: &mut DiagnosticBuilder<'db>,
+    ) {
+        for document in workspace.iter() {
+            let Some(data) = document.data.as_bib() else { continue };
+
+            let mut project: Vec<_> = workspace.project(document).documents.into_iter().collect();
+            if !project.contains(&document) {
+                project.push(document);
+            }
+
+            let dialect = detect_dialect(&project);
+            let parents = collect_fields(&project, dialect.data_model());
+            process_entries(document, data, dialect, &parents, builder);
+        }
+    }
+}
+
+/// Assumes biblatex unless the project is known to use plain BibTeX
+/// because biblatex also understands the fields of BibTeX.
+fn detect_dialect(project: &[&Document]) -> BibtexDialect {
+    let mut has_tex = false;
+    for data in project.iter().filter_map(|document| document.data.as_tex()) {
+        has_tex = true;
+        if data
+            .semantics
+            .links
+            .iter()
+            .any(|link| link.kind == LinkKind::Sty && link.path.text == "biblatex")
+        {
+            return BibtexDialect::Biblatex;
+        }
+    }
+
+    if has_tex {
+        BibtexDialect::Bibtex
+    } else {
+        BibtexDialect::Biblatex
+    }
+}
+
+/// Maps the keys of all entries in the project to the names of their fields.
+fn collect_fields(
+    project: &[&Document],
+    model: &BibtexDataModel,
+) -> FxHashMap<String, FxHashSet<String>> {
+    let mut entries = FxHashMap::default();
+    for data in project.iter().filter_map(|document| document.data.as_bib()) {
+        let root = bibtex::Root::cast(data.root_node()).unwrap();
+        for entry in root.entries() {
+            let Some(name) = entry.name_token() else { continue };
+            let fields = entry
+                .fields()
+                .filter_map(|field| field.name_token())
+                .map(|name| field_name(model, &name))
+                .collect();
+
+            entries.insert(String::from(name.text()), fields);
+        }
+    }
+
+    entries
+}
+
+fn process_entries<'db>(
+    document: &'db Document,
+    data: &BibDocumentData,
+    dialect: BibtexDialect,
+    parents: &FxHashMap<String, FxHashSet<String>>,
+    builder: &mut DiagnosticBuilder<'db>,
+) {
+    let model = dialect.data_model();
+    let mut push = |range, error| {
+        let diagnostic = Diagnostic {
+            range,
+            data: DiagnosticData::Bib(error),
+        };
+
+        builder.push(&document.uri, Cow::Owned(diagnostic));
+    };
+
+    let root = bibtex::Root::cast(data.root_node()).unwrap();
+    for entry in root.entries().filter(is_complete) {
+        let Some(type_token) = entry.type_token() else { continue };
+
+        let mut fields = FxHashSet::default();
+        let mut known_fields = Vec::new();
+        for field in entry.fields() {
+            let Some(name) = field.name_token() else { continue };
+            match model.find_field(name.text()) {
+                Some(known) => known_fields.push((name.text_range(), known)),
+                None => push(name.text_range(), BibError::UnknownField(dialect)),
+            }
+
+            let name = field_name(model, &name);
+            if name == "crossref" || name == "xdata" {
+                let value = field.value().map(|value| value.syntax().to_string());
+                for key in value.iter().flat_map(|value| parse_keys(value)) {
+                    fields.extend(inherited_fields(parents, key));
+                }
+            }
+
+            fields.insert(name);
+        }
+
+        let type_name = type_token.text().trim_start_matches('@');
+        let Some((entry_type, implied)) = model.find_entry_type(type_name) else {
+            push(type_token.text_range(), BibError::UnknownEntryType(dialect));
+            continue;
+        };
+
+        for (range, field) in known_fields {
+            if !model.is_field_allowed(entry_type, field) {
+                push(range, BibError::UnexpectedField(dialect));
+            }
+        }
+
+        fields.extend(implied.iter().copied().map(String::from));
+
+        let Some(name) = entry.name_token() else { continue };
+        for group in entry_type.required {
+            if !group.iter().any(|name| fields.contains(*name)) {
+                let names = group.iter().copied().map(String::from).collect();
+                push(name.text_range(), BibError::MissingField(dialect, names));
+            }
+        }
+    }
+}
+
+/// Entries with syntax errors are likely still being written.
+fn is_complete(entry: &bibtex::Entry) -> bool {
+    entry.name_token().is_some()
+        && entry.right_delim_token().is_some()
+        && entry
+            .fields()
+            .all(|field| field.eq_token().is_some() && field.value().is_some())
+}
+
+/// Returns the lowercase name of the field with aliases resolved.
+fn field_name(model: &BibtexDataModel, name: &bibtex::SyntaxToken) -> String {
+    model
+        .find_field(name.text())
+        .map_or_else(|| name.text().to_lowercase(), String::from)
+}
+
+/// Splits the value of a `crossref` or `xdata` field into the referenced keys.
+fn parse_keys(value: &str) -> impl Iterator<Item = &str> {
+    value
+        .trim()
+        .trim_start_matches(['{', '"'])
+        .trim_end_matches(['}', '"'])
+        .split(',')
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+}
+
+/// Returns the fields that an entry inherits from the given parent entry.
+/// The title of the parent becomes the `booktitle` of the child (e.g. `@inproceedings`).
+fn inherited_fields<'a>(
+    parents: &'a FxHashMap<String, FxHashSet<String>>,
+    key: &str,
+) -> impl Iterator<Item = String> + 'a {
+    let fields = parents.get(key).into_iter().flatten();
+    let titles = fields
+        .clone()
+        .filter(|field| *field == "title")
+        .map(|_| String::from("booktitle"));
+
+    fields.cloned().chain(titles)
+}
@@ -0,0 +1,143 @@
+use std::borrow::Cow;
+
+use base_db::{Document, Workspace};
+use rowan::{TextRange, TextSize};
+use rustc_hash::FxHashMap;
+use syntax::{BibLogError, BibLogTarget};
+use url::Url;
+
+use crate::{
+    types::{Diagnostic, DiagnosticData},
+    DiagnosticBuilder, DiagnosticSource,
+};
+
+/// Reports the messages of BibTeX and Biber at the bibliography entries
+/// or citations that they refer to.
+#[derive(Debug, Default)]
+pub struct BibLogErrors {
+    logs: FxHashMap<Url, FxHashMap<Url, Vec<Diagnostic>>>,
+}
+
+impl DiagnosticSource for BibLogErrors {
+    fn update(&mut self, workspace: &Workspace, log_document: &Document) {
+        let Some(data) = log_document.data.as_blg() else { return };
+        self.logs.remove(&log_document.uri);
+
+        let parents = workspace.parents(log_document);
+        let Some(root) = parents.first() else { return };
+
+        let project: Vec<_> = workspace.project(root).documents.into_iter().collect();
+        let mut errors: FxHashMap<Url, Vec<Diagnostic>> = FxHashMap::default();
+        for error in &data.errors {
+            let mut locations = match &error.target {
+                Some(BibLogTarget::Citation(key)) => find_citations(&project, key),
+                Some(BibLogTarget::Entry(key)) => find_entries(&project, error, key),
+                None => Vec::new(),
+            };
+
+            if locations.is_empty() {
+                let location = find_line(&project, error);
+                locations.push(location.unwrap_or((*root, TextRange::default())));
+            }
+
+            for (document, range) in locations {
+                let diagnostic = Diagnostic {
+                    range,
+                    data: DiagnosticData::BibLog(error.clone()),
+                };
+
+                errors
+                    .entry(document.uri.clone())
+                    .or_default()
+                    .push(diagnostic);
+            }
+        }
+
+        self.logs.insert(log_document.uri.clone(), errors);
+    }
+
+    fn publish<'db>(
+        &'db mut self,
+        workspace: &'db Workspace,
+        builder: &mut DiagnosticBuilder<'db>,
+    ) {
+        self.logs.retain(|uri, _| workspace.lookup(uri).is_some());
+
+        for errors in self.logs.values() {
+            for (uri, errors) in errors {
+                builder.push_many(uri, errors.iter().map(Cow::Borrowed));
+            }
+        }
+    }
+}
+
+fn find_citations<'db>(project: &[&'db Document], key: &str) -> Vec<(&'db Document, TextRange)> {
+    project
+        .iter()
+        .filter_map(|document| Some((*document, document.data.as_tex()?)))
+        .flat_map(|(document, data)| {
+            data.semantics
+                .citations
+                .iter()
+                .filter(|citation| citation.name.text == key)
+                .map(move |citation| (document, citation.name.range))
+        })
+        .collect()
+}
+
+fn find_entries<'db>(
+    project: &[&'db Document],
+    error: &BibLogError,
+    key: &str,
+) -> Vec<(&'db Document, TextRange)> {
+    let entries: Vec<_> = bib_documents(project, error)
+        .filter_map(|document| Some((document, document.data.as_bib()?)))
+        .flat_map(|(document, data)| {
+            data.semantics
+                .entries
+                .iter()
+                .filter(|entry| entry.name.text == key)
+                .map(move |entry| (document, entry.name.range))
+        })
+        .collect();
+
+    // A duplicate entry is reported with the line of the repeated definition.
+    let Some(line) = error.line else { return entries };
+    entries
+        .into_iter()
+        .min_by_key(|(document, range)| {
+            let entry_line = document.line_index.line_col(range.start()).line;
+            entry_line.abs_diff(line)
+        })
+        .into_iter()
+        .collect()
+}
+
+fn find_line<'db>(
+    project: &[&'db Document],
+    error: &BibLogError,
+) -> Option<(&'db Document, TextRange)> {
+    let line = error.line?;
+    let document = bib_documents(project, error).next()?;
+    let offset = document.line_index.newlines.get(line as usize).copied();
+    Some((
+        document,
+        TextRange::empty(offset.unwrap_or(TextSize::from(0))),
+    ))
+}
+
+/// Returns the bibliography files of the project, restricted to the file named in the message.
+fn bib_documents<'a, 'db>(
+    project: &'a [&'db Document],
+    error: &'a BibLogError,
+) -> impl Iterator<Item = &'db Document> + 'a {
+    project
+        .iter()
+        .copied()
+        .filter(|document| document.data.as_bib().is_some())
+        .filter(move |document| {
+            error.file.as_deref().map_or(true, |file| {
+                document.path.as_deref().and_then(|path| path.file_name()) == file.file_name()
+            })
+        })
+}
@@ -3,7 +3,7 @@ use std::borrow::Cow;
 use base_db::{Document, Workspace};
 use rowan::{TextLen, TextRange, TextSize};
 use rustc_hash::FxHashMap;
-use syntax::BuildError;
+use syntax::{BuildError, BuildLog as BuildLogData};
 use url::Url;
 
 use crate::{
@@ -11,31 +11,71 @@ use crate::{
     DiagnosticBuilder, DiagnosticSource,
 };
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 struct BuildLog {
+    root: Url,
     errors: FxHashMap<Url, Vec<Diagnostic>>,
 }
 
 #[derive(Debug, Default)]
 pub struct BuildErrors {
     logs: FxHashMap<Url, BuildLog>,
+    live_logs: FxHashMap<Url, BuildLog>,
 }
 
 impl DiagnosticSource for BuildErrors {
     fn update(&mut self, workspace: &Workspace, log_document: &Document) {
-        let mut errors: FxHashMap<Url, Vec<Diagnostic>> = FxHashMap::default();
-
         let Some(data) = log_document.data.as_log() else { return };
 
         let parents = workspace.parents(log_document);
-        let Some(root_document) = parents.iter().next() else { return };
+        let Some(root_document) = parents.first() else { return };
+
+        // The log file replaces the output of the build once it has been written.
+        self.live_logs.remove(&root_document.uri);
 
-        let Some(base_path) = root_document
-            .path
-            .as_deref()
-            .and_then(|path| path.parent()) else { return };
+        let Some(log) = BuildLog::new(workspace, root_document, &data.errors) else { return };
+        self.logs.insert(log_document.uri.clone(), log);
+    }
+
+    fn update_build_output(&mut self, workspace: &Workspace, root: &Document, log: &BuildLogData) {
+        let Some(log) = BuildLog::new(workspace, root, &log.errors) else { return };
+        self.live_logs.insert(root.uri.clone(), log);
+    }
 
-        for error in &data.errors {
+    fn publish<'db>(
+        &'db mut self,
+        workspace: &'db Workspace,
+        builder: &mut DiagnosticBuilder<'db>,
+    ) {
+        self.logs.retain(|uri, _| workspace.lookup(uri).is_some());
+        self.live_logs
+            .retain(|uri, _| workspace.lookup(uri).is_some());
+
+        for document in workspace.iter() {
+            let Some(log) = self.logs.get(&document.uri) else { continue };
+            if self.live_logs.contains_key(&log.root) {
+                continue;
+            }
+
+            for (uri, errors) in &log.errors {
+                builder.push_many(&uri, errors.iter().map(Cow::Borrowed));
+            }
+        }
+
+        for log in self.live_logs.values() {
+            for (uri, errors) in &log.errors {
+                builder.push_many(&uri, errors.iter().map(Cow::Borrowed));
+            }
+        }
+    }
+}
+
+impl BuildLog {
+    fn new(workspace: &Workspace, root_document: &Document, data: &[BuildError]) -> Option<Self> {
+        let mut errors: FxHashMap<Url, Vec<Diagnostic>> = FxHashMap::default();
+
+        let base_path = root_document.path.as_deref()?.parent()?;
+        for error in data {
             let full_path = base_path.join(&error.relative_path);
             let Ok(full_path_uri) = Url::from_file_path(&full_path) else { continue };
             let tex_document = workspace.lookup(&full_path_uri).unwrap_or(root_document);
@@ -62,26 +102,15 @@ impl DiagnosticSource for BuildErrors {
                 .push(diagnostic);
         }
 
-        self.logs
-            .insert(log_document.uri.clone(), BuildLog { errors });
-    }
-
-    fn publish<'db>(
-        &'db mut self,
-        workspace: &'db Workspace,
-        builder: &mut DiagnosticBuilder<'db>,
-    ) {
-        self.logs.retain(|uri, _| workspace.lookup(uri).is_some());
-
-        for document in workspace.iter() {
-            let Some(log) = self.logs.get(&document.uri) else { continue };
-            for (uri, errors) in &log.errors {
-                builder.push_many(&uri, errors.iter().map(Cow::Borrowed));
-            }
-        }
+        Some(Self {
+            root: root_document.uri.clone(),
+            errors,
+        })
     }
 }
 
+/// Finds the token that caused the error with the help of the context printed by TeX.
+/// The hint contains the source line up to the error and the remainder contains the rest.
 fn find_range_of_hint(document: &Document, error: &BuildError) -> Option<TextRange> {
     let line = error.line? as usize;
     let hint = error.hint.as_deref()?;
@@ -95,7 +124,42 @@ fn find_range_of_hint(document: &Document, error: &BuildError) -> Option<TextRan
         .unwrap_or((&document.text).text_len());
 
     let line_text = &document.text[line_start.into()..line_end.into()];
-    let hint_start = line_start + TextSize::try_from(line_text.find(hint)?).unwrap();
-    let hint_end = hint_start + hint.text_len();
-    Some(TextRange::new(hint_start, hint_end))
+
+    // Long lines are truncated by TeX with `...`.
+    let hint = hint.strip_prefix("...").unwrap_or(hint);
+    let remainder = error.remainder.as_deref().unwrap_or_default();
+    let remainder = remainder.trim_end_matches("...");
+
+    let mut positions = line_text
+        .match_indices(hint)
+        .map(|(start, _)| start + hint.len());
+
+    let end = positions
+        .clone()
+        .find(|end| line_text[*end..].trim_start().starts_with(remainder))
+        .or_else(|| positions.next())?;
+
+    let start = find_token_start(&line_text[..end])?;
+    let token_start = line_start + TextSize::try_from(start).ok()?;
+    let token_end = line_start + TextSize::try_from(end).ok()?;
+    Some(TextRange::new(token_start, token_end))
+}
+
+/// Returns the start of the last token in the text, which is either a control sequence
+/// or a single character.
+fn find_token_start(text: &str) -> Option<usize> {
+    let name_start = text
+        .trim_end_matches(|c: char| c.is_ascii_alphabetic() || c == '@')
+        .len();
+
+    if name_start < text.len() && text[..name_start].ends_with('\\') {
+        return Some(name_start - 1);
+    }
+
+    let (last, _) = text.char_indices().next_back()?;
+    if text[..last].ends_with('\\') {
+        Some(last - 1)
+    } else {
+        Some(last)
+    }
 }
@@ -1,11 +1,11 @@
 use std::borrow::Cow;
 
-use base_db::{graph::Graph, BibDocumentData, Document, DocumentData, TexDocumentData, Workspace};
-use itertools::Itertools;
+use base_db::{BibDocumentData, Document, DocumentData, TexDocumentData, Workspace};
 use rustc_hash::FxHashSet;
 
 use crate::{
     types::{BibError, Diagnostic, DiagnosticData, TexError},
+    util::find_duplicates,
     DiagnosticBuilder, DiagnosticSource,
 };
 
@@ -18,21 +18,14 @@ impl DiagnosticSource for CitationErrors {
         workspace: &'db Workspace,
         builder: &mut DiagnosticBuilder<'db>,
     ) {
-        let graphs: Vec<_> = workspace
-            .iter()
-            .map(|start| Graph::new(workspace, start))
-            .collect();
-
         for document in workspace.iter() {
-            let project = graphs
-                .iter()
-                .filter(|graph| graph.preorder().contains(&document))
-                .flat_map(|graph| graph.preorder());
+            let project: Vec<_> = workspace.project(document).documents.into_iter().collect();
 
             if let DocumentData::Tex(data) = &document.data {
-                self.process_tex(project, document, data, builder);
+                self.process_tex(project.iter().copied(), document, data, builder);
             } else if let DocumentData::Bib(data) = &document.data {
-                self.process_bib(project, document, data, builder);
+                self.process_bib(project.iter().copied(), document, data, builder);
+                self.process_duplicates(&project, document, builder);
             }
         }
     }
@@ -88,4 +81,49 @@ impl CitationErrors {
             }
         }
     }
+
+    fn process_duplicates<'db>(
+        &mut self,
+        project: &[&'db Document],
+        document: &'db Document,
+        builder: &mut DiagnosticBuilder<'db>,
+    ) {
+        let bib_data = || {
+            project
+                .iter()
+                .filter_map(|child| Some((*child, child.data.as_bib()?)))
+        };
+
+        let entries = bib_data().flat_map(|(child, data)| {
+            data.semantics
+                .entries
+                .iter()
+                .map(move |entry| (child, &entry.name))
+        });
+
+        for (range, others) in find_duplicates(document, entries) {
+            let diagnostic = Diagnostic {
+                range,
+                data: DiagnosticData::Bib(BibError::DuplicateEntry(others)),
+            };
+
+            builder.push(&document.uri, Cow::Owned(diagnostic));
+        }
+
+        let strings = bib_data().flat_map(|(child, data)| {
+            data.semantics
+                .strings
+                .iter()
+                .map(move |string| (child, &string.name))
+        });
+
+        for (range, others) in find_duplicates(document, strings) {
+            let diagnostic = Diagnostic {
+                range,
+                data: DiagnosticData::Bib(BibError::DuplicateString(others)),
+            };
+
+            builder.push(&document.uri, Cow::Owned(diagnostic));
+        }
+    }
 }
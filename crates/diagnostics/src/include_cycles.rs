@@ -0,0 +1,63 @@
+use std::borrow::Cow;
+
+use base_db::Workspace;
+use itertools::Itertools;
+use rowan::TextRange;
+use rustc_hash::FxHashSet;
+use url::Url;
+
+use crate::{
+    types::{Diagnostic, DiagnosticData, TexError},
+    DiagnosticBuilder, DiagnosticSource,
+};
+
+/// Reports the include commands of documents that include themselves, either directly
+/// or through other documents.
+#[derive(Default)]
+pub struct IncludeCycleErrors;
+
+impl DiagnosticSource for IncludeCycleErrors {
+    fn publish<'db>(
+        &'db mut self,
+        workspace: &'db Workspace,
+        builder: &mut DiagnosticBuilder<'db>,
+    ) {
+        let mut reported = FxHashSet::default();
+        for start in workspace.iter() {
+            for cycle in workspace.graph(start).cycles {
+                let mut includes: Vec<_> = cycle
+                    .iter()
+                    .filter_map(|edge| Some((edge.source, edge.weight.as_ref()?.link.path.range)))
+                    .collect();
+
+                // Every graph that reaches the cycle finds it, possibly starting at another link.
+                let first = includes
+                    .iter()
+                    .position_min_by_key(|(source, range)| (&source.uri, range.start()))
+                    .unwrap_or_default();
+
+                includes.rotate_left(first);
+                let path: Vec<(Url, TextRange)> = includes
+                    .iter()
+                    .map(|(source, range)| (source.uri.clone(), *range))
+                    .collect();
+
+                if !reported.insert(path.clone()) {
+                    continue;
+                }
+
+                for (i, (source, range)) in includes.into_iter().enumerate() {
+                    let mut path = path.clone();
+                    path.rotate_left(i);
+
+                    let diagnostic = Diagnostic {
+                        range,
+                        data: DiagnosticData::Tex(TexError::IncludeCycle(path)),
+                    };
+
+                    builder.push(&source.uri, Cow::Owned(diagnostic));
+                }
+            }
+        }
+    }
+}
@@ -1,11 +1,11 @@
 use std::borrow::Cow;
 
 use base_db::{semantics::tex::LabelKind, DocumentData, Workspace};
-use itertools::Itertools;
 use rustc_hash::FxHashSet;
 
 use crate::{
     types::{DiagnosticData, TexError},
+    util::find_duplicates,
     Diagnostic, DiagnosticBuilder, DiagnosticSource,
 };
 
@@ -18,22 +18,15 @@ impl DiagnosticSource for LabelErrors {
         workspace: &'db Workspace,
         builder: &mut DiagnosticBuilder<'db>,
     ) {
-        let graphs: Vec<_> = workspace
-            .iter()
-            .map(|start| base_db::graph::Graph::new(workspace, start))
-            .collect();
-
         for document in workspace.iter() {
             let DocumentData::Tex(data) = &document.data else { continue };
 
             let mut label_refs = FxHashSet::default();
             let mut label_defs = FxHashSet::default();
-            let project = graphs
-                .iter()
-                .filter(|graph| graph.preorder().contains(&document))
-                .flat_map(|graph| graph.preorder());
-
+            let project = workspace.project(document);
             for label in project
+                .documents
+                .iter()
                 .filter_map(|child| child.data.as_tex())
                 .flat_map(|data| data.semantics.labels.iter())
             {
@@ -61,6 +54,26 @@ impl DiagnosticSource for LabelErrors {
                     builder.push(&document.uri, Cow::Owned(diagnostic));
                 }
             }
+
+            let definitions = project
+                .documents
+                .iter()
+                .filter_map(|child| Some((*child, child.data.as_tex()?)))
+                .flat_map(|(child, data)| {
+                    data.semantics
+                        .labels
+                        .iter()
+                        .filter(|label| label.kind == LabelKind::Definition)
+                        .map(move |label| (child, &label.name))
+                });
+
+            for (range, others) in find_duplicates(document, definitions) {
+                let diagnostic = Diagnostic {
+                    range,
+                    data: DiagnosticData::Tex(TexError::DuplicateLabel(others)),
+                };
+                builder.push(&document.uri, Cow::Owned(diagnostic));
+            }
         }
     }
 }
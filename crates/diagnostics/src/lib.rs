@@ -1,21 +1,34 @@
+mod bib_entries;
+mod bib_log;
 mod build_log;
 mod citations;
 mod grammar;
+mod include_cycles;
 mod labels;
+mod missing_files;
 pub mod types;
+mod undefined_commands;
 pub(crate) mod util;
 
 use std::borrow::Cow;
 
 use base_db::{Document, Workspace};
+use bib_entries::BibEntryErrors;
+use bib_log::BibLogErrors;
 use build_log::BuildErrors;
 use citations::CitationErrors;
 use grammar::{BibSyntaxErrors, TexSyntaxErrors};
+use include_cycles::IncludeCycleErrors;
 use labels::LabelErrors;
+use missing_files::MissingFileErrors;
 use rustc_hash::FxHashMap;
+use syntax::BuildLog;
 use types::Diagnostic;
+use undefined_commands::UndefinedCommandErrors;
 use url::Url;
 
+pub use undefined_commands::{ComponentProvider, LinkedNames};
+
 #[derive(Debug, PartialEq, Eq, Clone, Default)]
 pub struct DiagnosticBuilder<'db> {
     inner: FxHashMap<&'db Url, Vec<Cow<'db, Diagnostic>>>,
@@ -45,6 +58,10 @@ pub trait DiagnosticSource {
     #[allow(unused_variables)]
     fn update(&mut self, workspace: &Workspace, document: &Document) {}
 
+    /// Updates the diagnostics with the output of a build that is still running.
+    #[allow(unused_variables)]
+    fn update_build_output(&mut self, workspace: &Workspace, root: &Document, log: &BuildLog) {}
+
     fn publish<'db>(&'db mut self, workspace: &'db Workspace, builder: &mut DiagnosticBuilder<'db>);
 }
 
@@ -52,14 +69,21 @@ pub struct DiagnosticManager {
     sources: Vec<Box<dyn DiagnosticSource>>,
 }
 
-impl Default for DiagnosticManager {
-    fn default() -> Self {
+impl DiagnosticManager {
+    /// Creates the manager with all sources.
+    /// The components are used to check if commands and environments are defined.
+    pub fn new(components: &'static dyn ComponentProvider) -> Self {
         let mut sources: Vec<Box<dyn DiagnosticSource>> = Vec::new();
         sources.push(Box::new(TexSyntaxErrors::default()));
         sources.push(Box::new(BibSyntaxErrors::default()));
         sources.push(Box::new(BuildErrors::default()));
+        sources.push(Box::new(BibLogErrors::default()));
         sources.push(Box::new(LabelErrors::default()));
         sources.push(Box::new(CitationErrors::default()));
+        sources.push(Box::new(BibEntryErrors::default()));
+        sources.push(Box::new(MissingFileErrors::default()));
+        sources.push(Box::new(IncludeCycleErrors::default()));
+        sources.push(Box::new(UndefinedCommandErrors::new(components)));
         Self { sources }
     }
 }
@@ -71,6 +95,12 @@ impl DiagnosticSource for DiagnosticManager {
         }
     }
 
+    fn update_build_output(&mut self, workspace: &Workspace, root: &Document, log: &BuildLog) {
+        for source in &mut self.sources {
+            source.update_build_output(workspace, root, log);
+        }
+    }
+
     fn publish<'db>(
         &'db mut self,
         workspace: &'db Workspace,
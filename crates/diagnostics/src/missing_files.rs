@@ -0,0 +1,139 @@
+use std::borrow::Cow;
+
+use base_db::{
+    graph::Graph,
+    semantics::{
+        tex::{Link, LinkKind},
+        Span,
+    },
+    Document, Workspace,
+};
+use itertools::Itertools;
+use url::Url;
+
+use crate::{
+    types::{Diagnostic, DiagnosticData, TexError},
+    DiagnosticBuilder, DiagnosticSource,
+};
+
+/// Reports the files referenced by commands like `\input` or `\includegraphics`
+/// that cannot be found.
+#[derive(Default)]
+pub struct MissingFileErrors;
+
+impl DiagnosticSource for MissingFileErrors {
+    fn publish<'db>(
+        &'db mut self,
+        workspace: &'db Workspace,
+        builder: &mut DiagnosticBuilder<'db>,
+    ) {
+        for document in workspace.iter() {
+            let Some(data) = document.data.as_tex() else {
+                continue;
+            };
+
+            let mut roots = workspace.parents(document);
+            if roots.is_empty() {
+                roots.push(document);
+            }
+
+            let graphs: Vec<_> = roots.iter().map(|root| workspace.graph(root)).collect();
+            for link in &data.semantics.links {
+                if let Some(candidates) = find_link_candidates(workspace, document, link, &graphs) {
+                    push(builder, document, &link.path, candidates);
+                }
+            }
+
+            for path in &data.semantics.graphics {
+                if let Some(candidates) = find_graphics_candidates(document, path, &graphs) {
+                    push(builder, document, path, candidates);
+                }
+            }
+        }
+    }
+}
+
+fn push<'db>(
+    builder: &mut DiagnosticBuilder<'db>,
+    document: &'db Document,
+    path: &Span,
+    candidates: Vec<Url>,
+) {
+    let diagnostic = Diagnostic {
+        range: path.range,
+        data: DiagnosticData::Tex(TexError::MissingFile(candidates)),
+    };
+
+    builder.push(&document.uri, Cow::Owned(diagnostic));
+}
+
+/// Returns the locations that have been tried if the link cannot be resolved
+/// by any of the projects that contain the document.
+fn find_link_candidates(
+    workspace: &Workspace,
+    document: &Document,
+    link: &Link,
+    graphs: &[Graph],
+) -> Option<Vec<Url>> {
+    // The class is usually provided by the distribution or a template outside of the workspace.
+    if link.kind == LinkKind::Cls || !is_literal(&link.path.text) {
+        return None;
+    }
+
+    let mut candidates = Vec::new();
+    for graph in graphs {
+        let unresolved = graph.unresolved.iter().find(|unresolved| {
+            unresolved.source == document && std::ptr::eq(unresolved.link, link)
+        })?;
+
+        candidates.extend(unresolved.candidates.iter().cloned());
+    }
+
+    // Without a distribution, we cannot tell if a package is installed.
+    let file_name_db = &workspace.distro().file_name_db;
+    if link.kind == LinkKind::Sty && file_name_db.is_empty() {
+        return None;
+    }
+
+    let stem = &link.path.text;
+    let mut file_names = std::iter::once(stem.clone()).chain(
+        link.kind
+            .extensions()
+            .iter()
+            .map(|ext| format!("{stem}.{ext}")),
+    );
+
+    if file_names.any(|name| file_name_db.get(&name).is_some()) {
+        return None;
+    }
+
+    Some(candidates.into_iter().unique().collect())
+}
+
+/// Returns the locations that have been tried if the image cannot be found
+/// by any of the projects that contain the document.
+fn find_graphics_candidates(
+    document: &Document,
+    path: &Span,
+    graphs: &[Graph],
+) -> Option<Vec<Url>> {
+    if !is_literal(&path.text) {
+        return None;
+    }
+
+    let mut candidates = Vec::new();
+    for graph in graphs {
+        let unresolved = graph.unresolved_graphics.iter().find(|unresolved| {
+            unresolved.source == document && std::ptr::eq(unresolved.path, path)
+        })?;
+
+        candidates.extend(unresolved.candidates.iter().cloned());
+    }
+
+    Some(candidates.into_iter().unique().collect())
+}
+
+/// Paths that contain macros are expanded by TeX and cannot be resolved statically.
+fn is_literal(path: &str) -> bool {
+    !path.is_empty() && !path.contains(['\\', '#'])
+}
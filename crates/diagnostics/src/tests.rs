@@ -1,15 +1,77 @@
 use std::borrow::Cow;
 
+use base_db::{
+    data::BibtexDialect, semantics::tex::LinkKind, util::LineCol, Config, Owner, Project, Workspace,
+};
+use distro::Language;
+use rowan::TextRange;
+use syntax::{BibLogError, BibLogTarget, BuildError, BuildErrorLevel, BuildLog};
 use test_utils::fixture::Fixture;
+use url::Url;
 
 use crate::{
     types::{BibError, Diagnostic, DiagnosticData, TexError},
-    DiagnosticBuilder, DiagnosticManager, DiagnosticSource,
+    ComponentProvider, DiagnosticBuilder, DiagnosticManager, DiagnosticSource, LinkedNames,
 };
 
+/// The components of the tests: the kernel (without file name), `article.cls` and two packages.
+const COMPONENTS: &[(&str, &[&str], &[&str])] = &[
+    (
+        "",
+        &[
+            "documentclass",
+            "usepackage",
+            "newcommand",
+            "newenvironment",
+            "newcounter",
+            "begin",
+            "end",
+        ],
+        &["document"],
+    ),
+    ("article.cls", &["section"], &[]),
+    ("foo.sty", &["foo", "DeclareMathOperator"], &[]),
+    ("algpseudocode.sty", &[], &["algorithmic"]),
+];
+
+struct TestComponents;
+
+impl ComponentProvider for TestComponents {
+    fn contains(&self, file_name: &str) -> bool {
+        COMPONENTS.iter().any(|(name, _, _)| *name == file_name)
+    }
+
+    fn linked_names<'a>(&'a self, project: &Project) -> LinkedNames<'a> {
+        let file_names: Vec<_> = project
+            .documents
+            .iter()
+            .filter_map(|document| document.data.as_tex())
+            .flat_map(|data| data.semantics.links.iter())
+            .filter_map(|link| match link.kind {
+                LinkKind::Sty => Some(format!("{}.sty", link.path.text)),
+                LinkKind::Cls => Some(format!("{}.cls", link.path.text)),
+                _ => None,
+            })
+            .collect();
+
+        let mut names = LinkedNames::default();
+        for (name, commands, environments) in COMPONENTS {
+            if name.is_empty() || file_names.iter().any(|file_name| file_name == name) {
+                names.commands.extend(commands.iter().copied());
+                names.environments.extend(environments.iter().copied());
+            }
+        }
+
+        names
+    }
+}
+
 fn check(input: &str, expected_data: &[DiagnosticData]) {
-    let fixture = Fixture::parse(input);
-    let mut manager = DiagnosticManager::default();
+    check_fixture(&Fixture::parse(input), expected_data);
+}
+
+fn check_fixture(fixture: &Fixture, expected_data: &[DiagnosticData]) {
+    let mut manager = DiagnosticManager::new(&TestComponents);
 
     let mut expected = DiagnosticBuilder::default();
     let mut expected_data = expected_data.iter();
@@ -183,9 +245,512 @@ fn test_citation_unused() {
     check(
         r#"
 %! main.bib
-@article{foo,}
-         ^^^
+@misc{foo, author = {Foo}, title = {Bar}, year = 2023}
+      ^^^
 "#,
         &[DiagnosticData::Bib(BibError::UnusedEntry)],
     )
 }
+
+#[test]
+fn test_build_log_undefined_command() {
+    check(
+        r#"
+%! main.tex
+\documentclass{article}
+\begin{document}
+Text \foo{} and \foo bar
+                ^^^^
+\end{document}
+
+%! main.log
+(./main.tex
+! Undefined control sequence.
+l.3 Text \foo{} and \foo
+                       bar
+)
+"#,
+        &[DiagnosticData::Build(BuildError {
+            relative_path: "./main.tex".into(),
+            level: BuildErrorLevel::Error,
+            message: String::from("Undefined control sequence."),
+            hint: Some(String::from("Text \\foo{} and \\foo")),
+            remainder: Some(String::from("bar")),
+            line: Some(2),
+            command: Some(String::from("foo")),
+        })],
+    )
+}
+
+/// Returns the location of the range with the given index in a document of the fixture.
+#[test]
+fn test_build_log_truncated_hint() {
+    let fixture = Fixture::parse(
+        r#"
+%! main.tex
+\documentclass{article}
+\RequirePackage{etoolbox}\RequirePackage{xpatch}\usepackage[style=foo,backend=biber]{biblatex}
+                                                                                             ^
+"#,
+    );
+
+    // TeX prints `...` in front of the context if the line is too long.
+    let error = BuildError {
+        relative_path: "./main.tex".into(),
+        level: BuildErrorLevel::Error,
+        message: String::from("LaTeX Error: File `foo.bbx' not found."),
+        hint: Some(String::from("...ckage[style=foo,backend=biber]{biblatex}")),
+        remainder: None,
+        line: Some(1),
+        command: None,
+    };
+
+    let document = &fixture.documents[0];
+    let root = fixture.workspace.lookup(&document.uri).unwrap();
+    let log = BuildLog {
+        errors: vec![error.clone()],
+    };
+
+    let mut manager = DiagnosticManager::new(&TestComponents);
+    manager.update_build_output(&fixture.workspace, root, &log);
+
+    let mut actual = DiagnosticBuilder::default();
+    manager.publish(&fixture.workspace, &mut actual);
+
+    let mut expected = DiagnosticBuilder::default();
+    let diagnostic = Diagnostic {
+        range: document.ranges[0],
+        data: DiagnosticData::Build(error),
+    };
+
+    expected.push(&document.uri, Cow::Owned(diagnostic));
+    assert_eq!(actual, expected);
+}
+
+fn location(fixture: &Fixture, document: usize, range: usize) -> (Url, TextRange) {
+    let document = &fixture.documents[document];
+    (document.uri.clone(), document.ranges[range])
+}
+
+#[test]
+fn test_label_duplicate() {
+    let fixture = Fixture::parse(
+        r#"
+%! main.tex
+\documentclass{article}
+\begin{document}
+\label{fig:result}
+       ^^^^^^^^^^
+\ref{fig:result}
+\include{chapter}
+\end{document}
+
+%! chapter.tex
+\label{fig:result}
+       ^^^^^^^^^^
+\label{fig:other}
+\ref{fig:other}
+"#,
+    );
+
+    check_fixture(
+        &fixture,
+        &[
+            DiagnosticData::Tex(TexError::DuplicateLabel(vec![location(&fixture, 1, 0)])),
+            DiagnosticData::Tex(TexError::DuplicateLabel(vec![location(&fixture, 0, 0)])),
+        ],
+    );
+}
+
+#[test]
+fn test_bib_entry_duplicate() {
+    let fixture = Fixture::parse(
+        r#"
+%! main.tex
+\documentclass{article}
+\begin{document}
+\cite{foo}
+\bibliography{a,b}
+\end{document}
+
+%! a.bib
+@string{jan = "January"}
+        ^^^
+@misc{foo, title = {Foo}}
+      ^^^
+
+%! b.bib
+@string{jan = "Jan."}
+        ^^^
+@misc{foo, title = {Bar}}
+      ^^^
+"#,
+    );
+
+    check_fixture(
+        &fixture,
+        &[
+            DiagnosticData::Bib(BibError::DuplicateString(vec![location(&fixture, 2, 0)])),
+            DiagnosticData::Bib(BibError::DuplicateEntry(vec![location(&fixture, 2, 1)])),
+            DiagnosticData::Bib(BibError::DuplicateString(vec![location(&fixture, 1, 0)])),
+            DiagnosticData::Bib(BibError::DuplicateEntry(vec![location(&fixture, 1, 1)])),
+        ],
+    );
+}
+
+fn bib_log_warning(message: &str, target: BibLogTarget) -> DiagnosticData {
+    DiagnosticData::BibLog(BibLogError {
+        level: BuildErrorLevel::Warning,
+        message: String::from(message),
+        target: Some(target),
+        file: None,
+        line: None,
+    })
+}
+
+#[test]
+fn test_bib_log_bibtex() {
+    check(
+        r#"
+%! main.tex
+\documentclass{article}
+\begin{document}
+\cite{foo}
+\cite{bar}
+      ^^^
+      ^^^
+\bibliography{main}
+\end{document}
+
+%! main.bib
+@misc{foo, title = {Foo}}
+      ^^^
+
+%! main.blg
+Warning--I didn't find a database entry for "bar"
+Warning--empty author in foo
+"#,
+        &[
+            bib_log_warning(
+                "I didn't find a database entry for \"bar\"",
+                BibLogTarget::Citation(String::from("bar")),
+            ),
+            DiagnosticData::Tex(TexError::UndefinedCitation),
+            bib_log_warning(
+                "empty author in foo",
+                BibLogTarget::Entry(String::from("foo")),
+            ),
+        ],
+    )
+}
+
+#[test]
+fn test_bib_log_biber() {
+    check(
+        r#"
+%! main.tex
+\documentclass{article}
+\usepackage{biblatex}
+\addbibresource{main.bib}
+\begin{document}
+\cite{foo}
+\end{document}
+
+%! main.bib
+@misc{foo, title = {Foo}, year = 2023}
+      ^^^
+      ^^^
+
+%! main.blg
+[1] Config.pm:307> INFO - This is Biber 2.19
+[133] Utils.pm:410> WARN - Datamodel: Entry 'foo' (main.bib): Missing mandatory field 'author'
+"#,
+        &[
+            DiagnosticData::BibLog(BibLogError {
+                level: BuildErrorLevel::Warning,
+                message: String::from(
+                    "Datamodel: Entry 'foo' (main.bib): Missing mandatory field 'author'",
+                ),
+                target: Some(BibLogTarget::Entry(String::from("foo"))),
+                file: Some("main.bib".into()),
+                line: None,
+            }),
+            missing_field(BibtexDialect::Biblatex, &["author", "editor"]),
+        ],
+    )
+}
+
+fn missing_field(dialect: BibtexDialect, fields: &[&str]) -> DiagnosticData {
+    let fields = fields.iter().copied().map(String::from).collect();
+    DiagnosticData::Bib(BibError::MissingField(dialect, fields))
+}
+
+#[test]
+fn test_bib_entry_fields_biblatex() {
+    check(
+        r#"
+%! main.tex
+\usepackage{biblatex}
+\addbibresource{main.bib}
+\cite{foo}
+\cite{bar}
+\cite{baz}
+
+%! main.bib
+@article{foo, author = {A}, journal = {B}, title = {C}, date = 2023, foo = {D}}
+                                                                     ^^^
+@phdthesis{bar, author = {A}, title = {B}, school = {C}}
+           ^^^
+@foo{baz, title = {A}}
+^^^^
+"#,
+        &[
+            DiagnosticData::Bib(BibError::UnknownField(BibtexDialect::Biblatex)),
+            missing_field(BibtexDialect::Biblatex, &["year", "date"]),
+            DiagnosticData::Bib(BibError::UnknownEntryType(BibtexDialect::Biblatex)),
+        ],
+    )
+}
+
+#[test]
+fn test_bib_entry_fields_bibtex() {
+    check(
+        r#"
+%! main.tex
+\cite{foo}
+\cite{bar}
+\cite{baz}
+\bibliography{main}
+
+%! main.bib
+@article{foo, author = {A}, title = {B}, journaltitle = {C}, year = 2023}
+         ^^^
+                                         ^^^^^^^^^^^^
+@inproceedings{bar, author = {A}, title = {B}, crossref = {baz}}
+@proceedings{baz, title = {C}, year = 2023}
+"#,
+        &[
+            missing_field(BibtexDialect::Bibtex, &["journal"]),
+            DiagnosticData::Bib(BibError::UnknownField(BibtexDialect::Bibtex)),
+        ],
+    )
+}
+
+#[test]
+fn test_bib_entry_fields_unexpected() {
+    check(
+        r#"
+%! main.tex
+\usepackage{biblatex}
+\addbibresource{main.bib}
+\cite{foo,bar,baz}
+
+%! main.bib
+@article{foo, author = {A}, journal = {B}, title = {C}, date = 2023, isbn = {D}, doi = {E}}
+                                                                     ^^^^
+@customa{bar, isbn = {A}}
+@set{baz, entryset = {foo, bar}, keywords = {A}, title = {B}}
+                                                 ^^^^^
+"#,
+        &[
+            DiagnosticData::Bib(BibError::UnexpectedField(BibtexDialect::Biblatex)),
+            DiagnosticData::Bib(BibError::UnexpectedField(BibtexDialect::Biblatex)),
+        ],
+    )
+}
+
+#[test]
+fn test_bib_entry_fields_unexpected_bibtex() {
+    check(
+        r#"
+%! main.tex
+\cite{foo}
+\bibliography{main}
+
+%! main.bib
+@misc{foo, title = {A}, publisher = {B}, doi = {C}, key = {D}}
+                        ^^^^^^^^^
+"#,
+        &[DiagnosticData::Bib(BibError::UnexpectedField(
+            BibtexDialect::Bibtex,
+        ))],
+    )
+}
+
+fn missing_file(paths: &[&str]) -> DiagnosticData {
+    let candidates = paths
+        .iter()
+        .map(|path| Url::parse(&format!("file:///texlab/{path}")).unwrap())
+        .collect();
+
+    DiagnosticData::Tex(TexError::MissingFile(candidates))
+}
+
+#[test]
+fn test_missing_file() {
+    check(
+        r#"
+%! main.tex
+\documentclass{article}
+\graphicspath{{figures/}}
+\begin{document}
+\include{chapter}
+\input{intro}
+       ^^^^^
+\bibliography{refs}
+              ^^^^
+\end{document}
+
+%! chapter.tex
+\includegraphics{plot.png}
+                 ^^^^^^^^
+\input{\jobname-extra}
+"#,
+        &[
+            missing_file(&["intro", "intro.tex"]),
+            missing_file(&["refs", "refs.bib"]),
+            missing_file(&[
+                "plot.png",
+                "plot.png.pdf",
+                "plot.png.png",
+                "plot.png.jpg",
+                "plot.png.jpeg",
+                "plot.png.mps",
+                "plot.png.eps",
+                "plot.png.ps",
+                "figures/plot.png",
+                "figures/plot.png.pdf",
+                "figures/plot.png.png",
+                "figures/plot.png.jpg",
+                "figures/plot.png.jpeg",
+                "figures/plot.png.mps",
+                "figures/plot.png.eps",
+                "figures/plot.png.ps",
+            ]),
+        ],
+    )
+}
+
+#[test]
+fn test_missing_file_graphics_created() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let uri = Url::from_file_path(dir.path().join("main.tex")).unwrap();
+    let text = String::from("\\includegraphics{plot}");
+
+    let mut workspace = Workspace::default();
+    let cursor = LineCol { line: 0, col: 0 };
+    workspace.open(uri, text, Language::Tex, Owner::Client, cursor);
+
+    let count_diagnostics = |workspace: &Workspace| {
+        let mut manager = DiagnosticManager::new(&TestComponents);
+        let mut builder = DiagnosticBuilder::default();
+        manager.publish(workspace, &mut builder);
+        builder
+            .iter()
+            .flat_map(|(_, diagnostics)| diagnostics)
+            .count()
+    };
+
+    let path = dir.path().join("plot.png");
+    assert_eq!(count_diagnostics(&workspace), 1);
+
+    std::fs::write(&path, [])?;
+    assert!(workspace.update_graphics(&path));
+    assert_eq!(count_diagnostics(&workspace), 0);
+
+    std::fs::remove_file(&path)?;
+    assert!(workspace.update_graphics(&path));
+    assert_eq!(count_diagnostics(&workspace), 1);
+    Ok(())
+}
+
+#[test]
+fn test_include_cycle() {
+    let fixture = Fixture::parse(
+        r#"
+%! main.tex
+\documentclass{article}
+\begin{document}
+\input{a}
+\end{document}
+
+%! a.tex
+\input{b}
+       ^
+
+%! b.tex
+\input{a}
+       ^
+"#,
+    );
+
+    let a = location(&fixture, 1, 0);
+    let b = location(&fixture, 2, 0);
+    check_fixture(
+        &fixture,
+        &[
+            DiagnosticData::Tex(TexError::IncludeCycle(vec![a.clone(), b.clone()])),
+            DiagnosticData::Tex(TexError::IncludeCycle(vec![b, a])),
+        ],
+    );
+}
+
+fn check_undefined_commands(input: &str, allowed: &[&str], expected_data: &[DiagnosticData]) {
+    let mut fixture = Fixture::parse(input);
+    let mut config = Config::default();
+    config.diagnostics.undefined_commands.enabled = true;
+    let allowed = allowed.iter().copied().map(String::from);
+    config
+        .diagnostics
+        .undefined_commands
+        .allowed
+        .extend(allowed);
+    fixture.workspace.set_config(config);
+    check_fixture(&fixture, expected_data);
+}
+
+#[test]
+fn test_undefined_commands() {
+    check_undefined_commands(
+        r#"
+%! main.tex
+\documentclass{article}
+\usepackage{foo}
+\newcommand{\bar}{\foo}
+\newenvironment{baz}{}{}
+\newcounter{qux}
+\DeclareMathOperator*{\argmax}{arg\,max}
+\begin{document}
+\bgein{baz}
+^^^^^^
+\quux \thequx $\argmax$
+\begin{algorithmic}
+       ^^^^^^^^^^^
+\end{algorithmic}
+\end{document}
+"#,
+        &["quux"],
+        &[
+            DiagnosticData::Tex(TexError::UndefinedCommand(String::from("bgein"))),
+            DiagnosticData::Tex(TexError::UndefinedEnvironment(String::from("algorithmic"))),
+        ],
+    )
+}
+
+#[test]
+fn test_undefined_commands_unknown_package() {
+    check_undefined_commands(
+        r#"
+%! main.tex
+\documentclass{article}
+\usepackage{mystery}
+\begin{document}
+\mysterycommand
+\begin{mysteryenvironment}
+\end{mysteryenvironment}
+\end{document}
+"#,
+        &[],
+        &[],
+    )
+}
@@ -1,5 +1,7 @@
+use base_db::data::BibtexDialect;
 use rowan::TextRange;
-use syntax::BuildError;
+use syntax::{BibLogError, BuildError};
+use url::Url;
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Diagnostic {
@@ -12,6 +14,7 @@ pub enum DiagnosticData {
     Tex(TexError),
     Bib(BibError),
     Build(BuildError),
+    BibLog(BibLogError),
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -22,6 +25,15 @@ pub enum TexError {
     UnusedLabel,
     UndefinedLabel,
     UndefinedCitation,
+    /// The label is defined multiple times in the project; contains the other definitions.
+    DuplicateLabel(Vec<(Url, TextRange)>),
+    /// The command is neither defined in the project nor provided by a known package.
+    UndefinedCommand(String),
+    UndefinedEnvironment(String),
+    /// The referenced file cannot be found; contains the locations that have been tried.
+    MissingFile(Vec<Url>),
+    /// The document includes itself; contains the include commands of the cycle starting here.
+    IncludeCycle(Vec<(Url, TextRange)>),
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -32,4 +44,14 @@ pub enum BibError {
     ExpectingEq,
     ExpectingFieldValue,
     UnusedEntry,
+    /// The key is used by other entries of the project.
+    DuplicateEntry(Vec<(Url, TextRange)>),
+    /// The string is defined multiple times in the project.
+    DuplicateString(Vec<(Url, TextRange)>),
+    UnknownEntryType(BibtexDialect),
+    UnknownField(BibtexDialect),
+    /// The field is known but not used by the type of the entry.
+    UnexpectedField(BibtexDialect),
+    /// The entry lacks a required field; contains the alternatives (like `year` or `date`).
+    MissingField(BibtexDialect, Vec<String>),
 }
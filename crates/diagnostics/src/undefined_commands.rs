@@ -0,0 +1,156 @@
+use std::borrow::Cow;
+
+use base_db::{semantics::tex::LinkKind, Document, Project, Workspace};
+use rowan::{TextLen, TextRange};
+use rustc_hash::FxHashSet;
+
+use crate::{
+    types::{Diagnostic, DiagnosticData, TexError},
+    DiagnosticBuilder, DiagnosticSource,
+};
+
+/// Provides the commands and environments of the packages and classes of the distribution.
+pub trait ComponentProvider {
+    /// Checks if the package or class with the given file name (like `amsmath.sty`) is known.
+    fn contains(&self, file_name: &str) -> bool;
+
+    /// Returns the names that are provided by the kernel
+    /// and by the packages and classes that the project loads.
+    fn linked_names<'a>(&'a self, project: &Project) -> LinkedNames<'a>;
+}
+
+#[derive(Debug, Default)]
+pub struct LinkedNames<'a> {
+    pub commands: FxHashSet<&'a str>,
+    pub environments: FxHashSet<&'a str>,
+}
+
+/// Reports the commands and environments that are neither defined in the project
+/// nor provided by the packages that it loads.
+pub struct UndefinedCommandErrors {
+    components: &'static dyn ComponentProvider,
+}
+
+impl UndefinedCommandErrors {
+    pub fn new(components: &'static dyn ComponentProvider) -> Self {
+        Self { components }
+    }
+}
+
+impl DiagnosticSource for UndefinedCommandErrors {
+    fn publish<'db>(
+        &'db mut self,
+        workspace: &'db Workspace,
+        builder: &mut DiagnosticBuilder<'db>,
+    ) {
+        let config = &workspace.config().diagnostics.undefined_commands;
+        if !config.enabled {
+            return;
+        }
+
+        for document in workspace.iter().filter(|document| !is_package(document)) {
+            let Some(data) = document.data.as_tex() else { continue };
+
+            let project = workspace.project(document);
+
+            // The names provided by packages that are unknown to the database cannot be checked.
+            if has_unknown_package(self.components, &project) {
+                continue;
+            }
+
+            let LinkedNames {
+                mut commands,
+                mut environments,
+            } = self.components.linked_names(&project);
+
+            for data in project
+                .documents
+                .iter()
+                .filter_map(|document| document.data.as_tex())
+            {
+                let semantics = &data.semantics;
+                let command_defs = semantics.command_definitions.iter();
+                commands.extend(command_defs.map(|def| def.name.text.as_str()));
+
+                let environment_defs = semantics.environment_definitions.iter();
+                let theorem_defs = semantics.theorem_definitions.iter();
+                environments.extend(environment_defs.map(|def| def.text.as_str()));
+                environments.extend(theorem_defs.map(|def| def.name.text.as_str()));
+            }
+
+            let is_known = |known: &FxHashSet<&str>, name: &str| {
+                known.contains(name)
+                    || known.contains(name.trim_end_matches('*'))
+                    || config.allowed.contains(name)
+            };
+
+            for command in &data.semantics.commands {
+                // Control symbols like `\\` and internal commands like `\@foo` are not tracked.
+                if !command.text.starts_with(|c: char| c.is_ascii_alphabetic())
+                    || command.text.contains('@')
+                    || is_known(&commands, &command.text)
+                {
+                    continue;
+                }
+
+                let start = command.range.start() - "\\".text_len();
+                let range = TextRange::new(start, command.range.end());
+                let diagnostic = Diagnostic {
+                    range,
+                    data: DiagnosticData::Tex(TexError::UndefinedCommand(command.text.clone())),
+                };
+
+                builder.push(&document.uri, Cow::Owned(diagnostic));
+            }
+
+            for environment in &data.semantics.environments {
+                if is_known(&environments, &environment.text) {
+                    continue;
+                }
+
+                let diagnostic = Diagnostic {
+                    range: environment.range,
+                    data: DiagnosticData::Tex(TexError::UndefinedEnvironment(
+                        environment.text.clone(),
+                    )),
+                };
+
+                builder.push(&document.uri, Cow::Owned(diagnostic));
+            }
+        }
+    }
+}
+
+/// Checks if the project loads a package or class that is neither in the database
+/// nor part of the project itself.
+fn has_unknown_package(components: &dyn ComponentProvider, project: &Project) -> bool {
+    let file_names: FxHashSet<&str> = project
+        .documents
+        .iter()
+        .filter_map(|document| document.path.as_deref()?.file_name()?.to_str())
+        .collect();
+
+    project
+        .documents
+        .iter()
+        .filter_map(|document| document.data.as_tex())
+        .flat_map(|data| data.semantics.links.iter())
+        .filter_map(|link| match link.kind {
+            LinkKind::Sty => Some(format!("{}.sty", link.path.text)),
+            LinkKind::Cls => Some(format!("{}.cls", link.path.text)),
+            _ => None,
+        })
+        .any(|name| !components.contains(&name) && !file_names.contains(name.as_str()))
+}
+
+/// Packages and classes contain internal definitions that cannot be tracked.
+fn is_package(document: &Document) -> bool {
+    document
+        .path
+        .as_deref()
+        .and_then(|path| path.extension())
+        .and_then(|extension| extension.to_str())
+        .map_or(false, |extension| {
+            matches!(extension.to_lowercase().as_str(), "sty" | "cls" | "dtx")
+        })
+}
@@ -1,6 +1,7 @@
 use std::borrow::Cow;
 
-use base_db::Workspace;
+use base_db::{semantics::Span, Document, Workspace};
+use rowan::TextRange;
 use rustc_hash::FxHashMap;
 use url::Url;
 
@@ -26,3 +27,34 @@ impl DiagnosticSource for SimpleDiagnosticSource {
         }
     }
 }
+
+/// Finds the names that are defined more than once.
+/// Returns the definitions of `document` together with the other definitions of the same name.
+pub fn find_duplicates<'db>(
+    document: &Document,
+    definitions: impl Iterator<Item = (&'db Document, &'db Span)>,
+) -> Vec<(TextRange, Vec<(Url, TextRange)>)> {
+    let mut groups: FxHashMap<&str, Vec<(&Url, TextRange)>> = FxHashMap::default();
+    for (child, name) in definitions {
+        groups
+            .entry(&name.text)
+            .or_default()
+            .push((&child.uri, name.range));
+    }
+
+    let mut duplicates = Vec::new();
+    for group in groups.values().filter(|group| group.len() > 1) {
+        for (uri, range) in group.iter().filter(|(uri, _)| *uri == &document.uri) {
+            let mut others: Vec<_> = group
+                .iter()
+                .filter(|other| other.0 != *uri || other.1 != *range)
+                .map(|(uri, range)| (Url::clone(uri), *range))
+                .collect();
+
+            others.sort_by(|a, b| (&a.0, a.1.start()).cmp(&(&b.0, b.1.start())));
+            duplicates.push((*range, others));
+        }
+    }
+
+    duplicates
+}
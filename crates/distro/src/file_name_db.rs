@@ -55,6 +55,10 @@ impl FileNameDB {
         self.files.iter().map(|file| (file.name(), file.path()))
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
     pub fn parse(
         root_dirs: &[PathBuf],
         reader: &mut dyn FnMut(&Path) -> Result<Vec<PathBuf>>,
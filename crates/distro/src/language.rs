@@ -6,8 +6,11 @@ pub enum Language {
     Bib,
     Aux,
     Log,
+    Blg,
+    Fls,
     Root,
     Tectonic,
+    Latexmkrc,
 }
 
 impl Language {
@@ -21,12 +24,18 @@ impl Language {
             return Some(Self::Tectonic);
         }
 
+        if name.eq_ignore_ascii_case(".latexmkrc") || name.eq_ignore_ascii_case("latexmkrc") {
+            return Some(Self::Latexmkrc);
+        }
+
         let extname = path.extension()?.to_str()?;
         match extname.to_lowercase().as_str() {
             "tex" | "sty" | "cls" | "def" | "lco" | "rnw" => Some(Self::Tex),
             "bib" | "bibtex" => Some(Self::Bib),
             "aux" => Some(Self::Aux),
             "log" => Some(Self::Log),
+            "blg" => Some(Self::Blg),
+            "fls" => Some(Self::Fls),
             _ => None,
         }
     }
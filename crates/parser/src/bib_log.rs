@@ -0,0 +1,170 @@
+use std::path::{Path, PathBuf};
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use syntax::{BibLog, BibLogError, BibLogTarget, BuildErrorLevel};
+
+static BIBER_MESSAGE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\[\d+\] [^>]*> (?P<level>WARN|ERROR) - (?P<msg>.*)$").unwrap());
+
+static BIBER_SYNTAX_ERROR_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"BibTeX subsystem: (?P<file>.+?), line (?P<line>\d+), (?P<msg>.*)$").unwrap()
+});
+
+static BIBER_FILE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?:\((?P<file1>[^()]+\.bib)\)|in file '(?P<file2>[^']+)')").unwrap());
+
+static BIBTEX_LOCATION_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(?P<msg>.*?)-{2,3}line (?P<line>\d+) of file (?P<file>.+)$").unwrap()
+});
+
+static CITATION_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"I didn't find a database entry for ["'](?P<key>[^"']+)["']"#).unwrap()
+});
+
+static ENTRY_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?:[Ee]ntry (?:key |type for )?["'](?P<key1>[^"']+)["']| in (?P<key2>[^\s"']+)$)"#,
+    )
+    .unwrap()
+});
+
+/// Parses the log of BibTeX or Biber (`.blg`).
+pub fn parse_bib_log(text: &str) -> BibLog {
+    let mut errors = Vec::new();
+    let mut lines = text.lines().peekable();
+    while let Some(line) = lines.next() {
+        if let Some(captures) = BIBER_MESSAGE_REGEX.captures(line) {
+            let level = match &captures["level"] {
+                "ERROR" => BuildErrorLevel::Error,
+                _ => BuildErrorLevel::Warning,
+            };
+
+            errors.push(parse_biber_message(level, &captures["msg"]));
+        } else if let Some(message) = line.strip_prefix("Warning--") {
+            let mut error = BibLogError {
+                level: BuildErrorLevel::Warning,
+                message: String::from(message),
+                target: find_target(message),
+                file: None,
+                line: None,
+            };
+
+            // Some warnings are followed by the location of the entry.
+            if let Some(captures) = lines
+                .peek()
+                .and_then(|line| BIBTEX_LOCATION_REGEX.captures(line))
+            {
+                error.file = Some(PathBuf::from(&captures["file"]));
+                error.line = parse_line(&captures["line"]);
+                lines.next();
+            }
+
+            errors.push(error);
+        } else if let Some(captures) = BIBTEX_LOCATION_REGEX.captures(line) {
+            let message = &captures["msg"];
+
+            // The context of the error is printed in lines starting with ` : `.
+            let mut target = None;
+            while let Some(context) = lines.peek().and_then(|line| line.strip_prefix(" : ")) {
+                if message == "Repeated entry" && target.is_none() {
+                    target = context
+                        .split_once('{')
+                        .map(|(_, key)| key.trim().trim_end_matches(','))
+                        .filter(|key| !key.is_empty())
+                        .map(|key| BibLogTarget::Entry(String::from(key)));
+                }
+
+                lines.next();
+            }
+
+            errors.push(BibLogError {
+                level: BuildErrorLevel::Error,
+                message: String::from(message),
+                target,
+                file: Some(PathBuf::from(&captures["file"])),
+                line: parse_line(&captures["line"]),
+            });
+        } else if let Some((message, _)) = line.split_once("---while reading file") {
+            errors.push(general_error(message));
+        } else if line.starts_with("I couldn't open") {
+            errors.push(general_error(line));
+        }
+    }
+
+    BibLog { errors }
+}
+
+fn parse_biber_message(level: BuildErrorLevel, message: &str) -> BibLogError {
+    if let Some(captures) = BIBER_SYNTAX_ERROR_REGEX.captures(message) {
+        return BibLogError {
+            level,
+            message: String::from(&captures["msg"]),
+            target: None,
+            file: biber_file_name(&captures["file"]),
+            line: parse_line(&captures["line"]),
+        };
+    }
+
+    let file = BIBER_FILE_REGEX
+        .captures(message)
+        .and_then(|captures| captures.name("file1").or_else(|| captures.name("file2")))
+        .and_then(|file| biber_file_name(file.as_str()));
+
+    BibLogError {
+        level,
+        message: String::from(message),
+        target: find_target(message),
+        file,
+        line: None,
+    }
+}
+
+/// Biber reports syntax errors in a temporary copy of the file (`refs.bib_1234.utf8`).
+fn biber_file_name(path: &str) -> Option<PathBuf> {
+    let name = Path::new(path).file_name()?.to_str()?;
+    let name = name
+        .strip_suffix(".utf8")
+        .and_then(|name| name.rsplit_once('_'))
+        .map_or(name, |(name, _)| name);
+
+    Some(PathBuf::from(name))
+}
+
+fn find_target(message: &str) -> Option<BibLogTarget> {
+    if let Some(captures) = CITATION_REGEX.captures(message) {
+        return Some(BibLogTarget::Citation(String::from(&captures["key"])));
+    }
+
+    let captures = ENTRY_REGEX.captures(message)?;
+    let key = captures.name("key1").or_else(|| captures.name("key2"))?;
+    Some(BibLogTarget::Entry(String::from(key.as_str())))
+}
+
+/// Converts a one-based line number into a zero-based line.
+fn parse_line(text: &str) -> Option<u32> {
+    text.parse::<u32>().ok().map(|line| line.saturating_sub(1))
+}
+
+fn general_error(message: &str) -> BibLogError {
+    BibLogError {
+        level: BuildErrorLevel::Error,
+        message: String::from(message),
+        target: None,
+        file: None,
+        line: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_bib_log;
+
+    #[test]
+    fn test_parse() {
+        insta::glob!("test_data/bib_log/*.txt", |path| {
+            let text = std::fs::read_to_string(path).unwrap().replace("\r\n", "\n");
+            insta::assert_debug_snapshot!(parse_bib_log(&text));
+        });
+    }
+}
@@ -1,181 +1,317 @@
-use std::{cmp::Ordering, path::PathBuf};
+use std::path::{Path, PathBuf};
 
 use once_cell::sync::Lazy;
-use regex::{Match, Regex};
+use regex::Regex;
 use syntax::{BuildError, BuildErrorLevel, BuildLog};
 
+/// TeX breaks the lines of the log after this number of characters.
 const MAX_LINE_LENGTH: usize = 79;
 
 static PACKAGE_MESSAGE_REGEX: Lazy<Regex> =
     Lazy::new(|| Regex::new("^\\([a-zA-Z_\\-]+\\)\\s*(?P<msg>.*)$").unwrap());
 
-static FILE_REGEX: Lazy<Regex> =
-    Lazy::new(|| Regex::new("\\((?P<file>[^\r\n()]+\\.(tex|sty|cls))").unwrap());
-
-static TEX_ERROR_REGEX: Lazy<Regex> = Lazy::new(|| {
-    Regex::new("(?m)^! ((?P<msg1>(.|\r|\n)*?)\r?\nl\\.(?P<line>\\d+)( (\\.\\.\\.)?(?P<hint>[^\r\n]+))?|(?P<msg2>[^\r\n]*))").unwrap()
+static FILE_LINE_ERROR_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new("^(?P<file>([a-zA-Z]:)?[^:\\s()]+):(?P<line>\\d+): (?P<msg>.*)$").unwrap()
 });
 
-static WARNING_REGEX: Lazy<Regex> = Lazy::new(|| {
-    Regex::new("(?P<msg>(LaTeX|Package [a-zA-Z_\\-]+) Warning: [^\r\n]*?(on input line (?P<line>\\d+))?\\.)[\r\n]").unwrap()
-});
+static ERROR_LOCATION_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new("^l\\.(?P<line>\\d+)( (\\.\\.\\.)?(?P<hint>.+))?$").unwrap());
 
-static BAD_BOX_REGEX: Lazy<Regex> = Lazy::new(|| {
-    Regex::new("(?P<msg>(Ov|Und)erfull \\\\[hv]box[^\r\n]*lines? (?P<line>\\d+)[^\r\n]*)").unwrap()
-});
+static WARNING_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new("(LaTeX|Package [a-zA-Z_\\-]+) Warning: ").unwrap());
+
+static INPUT_LINE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new("on input line (?P<line>\\d+)\\.$").unwrap());
+
+static BAD_BOX_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new("(Ov|Und)erfull \\\\[hv]box.*lines? (?P<line>\\d+)").unwrap());
 
 pub fn parse_build_log(log: &str) -> BuildLog {
-    let log = prepare_log(log);
-    let mut ranges: Vec<FileRange> = FILE_REGEX
-        .find_iter(&log)
-        .map(|result| FileRange::create(&log, result))
-        .collect();
-    ranges.sort();
-
-    let tex_errors = extract_matches(&log, &ranges, &TEX_ERROR_REGEX, BuildErrorLevel::Error);
-    let warnings = extract_matches(&log, &ranges, &WARNING_REGEX, BuildErrorLevel::Warning);
-    let bad_boxes = extract_matches(&log, &ranges, &BAD_BOX_REGEX, BuildErrorLevel::Warning);
-
-    let errors = vec![tex_errors, warnings, bad_boxes].concat();
-    BuildLog { errors }
+    let mut stream = BuildLogStream::default();
+    for line in log.lines() {
+        stream.push(line);
+    }
+
+    stream.finish();
+    stream.log
 }
 
-fn extract_matches(
-    log: &str,
-    ranges: &[FileRange],
-    regex: &Regex,
-    level: BuildErrorLevel,
-) -> Vec<BuildError> {
-    let mut errors = Vec::new();
-    for result in regex.find_iter(log) {
-        let captures = regex.captures(&log[result.start()..result.end()]).unwrap();
-        let message = captures
-            .name("msg")
-            .or_else(|| captures.name("msg1"))
-            .or_else(|| captures.name("msg2"))
-            .unwrap()
-            .as_str()
-            .lines()
-            .next()
-            .unwrap_or_default()
-            .to_owned();
-
-        if let Some(range) = ranges.iter().find(|range| range.contains(result.start())) {
-            let line = captures
-                .name("line")
-                .map(|result| result.as_str().parse::<u32>().unwrap() - 1);
-
-            let hint: Option<String> = if line.is_some() {
-                captures
-                    .name("hint")
-                    .map(|r| String::from(r.as_str().trim()))
-            } else {
-                None
-            };
-
-            errors.push(BuildError {
-                relative_path: range.path.clone(),
-                level,
-                message,
-                line,
-                hint,
-            });
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+enum State {
+    #[default]
+    Normal,
+    /// The rest of the source line that follows `l.<line>` for the given errors.
+    SourceLine(Vec<usize>),
+    /// The contents of an overfull or underfull box, terminated by an empty line.
+    BoxContent,
+}
+
+/// Parses the output of a TeX engine line by line.
+///
+/// The engine prints `(file` when it opens a file and `)` when it closes it again,
+/// so we keep a stack of the open files to find the file that a message belongs to.
+#[derive(Debug, Default)]
+pub struct BuildLogStream {
+    files: Vec<Option<PathBuf>>,
+    /// A line that has been broken by the engine because it was too long.
+    buffer: String,
+    state: State,
+    /// The message that can be continued by lines like `(babel) ...`.
+    last_message: Option<usize>,
+    /// The errors that are still waiting for their location (`l.<line>`).
+    pending_errors: Vec<usize>,
+    changed: bool,
+    log: BuildLog,
+}
+
+impl BuildLogStream {
+    /// Adds a line of output and returns `true` if the errors of the build have changed.
+    pub fn push(&mut self, line: &str) -> bool {
+        self.changed = false;
+
+        // A wrapped line never continues with an empty line or a new error.
+        if !self.buffer.is_empty() && (line.is_empty() || line.starts_with("! ")) {
+            self.flush();
+        }
+
+        let wrapped = line.len() == MAX_LINE_LENGTH || line.chars().count() == MAX_LINE_LENGTH;
+        let context = self.buffer.is_empty() && self.is_context_line(line);
+        self.buffer.push_str(line);
+        if !wrapped || context {
+            self.flush();
         }
+
+        self.changed
     }
-    errors
-}
 
-fn prepare_log(log: &str) -> String {
-    let mut old_lines = log.lines();
-    let mut new_lines: Vec<String> = Vec::new();
-    while let Some(line) = old_lines.next() {
-        if PACKAGE_MESSAGE_REGEX.is_match(line) {
-            let captures = PACKAGE_MESSAGE_REGEX.captures(line).unwrap();
-            if let Some(last_line) = new_lines.last_mut() {
-                last_line.push(' ');
-                last_line.push_str(captures.name("msg").unwrap().as_str());
-            }
-        } else if line.ends_with("...") {
-            let mut new_line = line[line.len() - 3..].to_owned();
-            if let Some(old_line) = old_lines.next() {
-                new_line.push_str(old_line);
-            }
-            new_lines.push(new_line);
-        } else if line.chars().count() == MAX_LINE_LENGTH {
-            let mut new_line = String::new();
-            new_line.push_str(line);
-            for old_line in old_lines.by_ref() {
-                new_line.push_str(old_line);
-                if old_line.chars().count() != MAX_LINE_LENGTH {
-                    break;
+    /// Parses the remaining output after the build has finished.
+    /// Returns `true` if the errors of the build have changed.
+    pub fn finish(&mut self) -> bool {
+        self.changed = false;
+        if !self.buffer.is_empty() {
+            self.flush();
+        }
+
+        self.pending_errors.clear();
+        self.changed
+    }
+
+    pub fn log(&self) -> &BuildLog {
+        &self.log
+    }
+
+    /// The innermost file that is currently being processed.
+    pub fn current_file(&self) -> Option<&Path> {
+        self.files.iter().rev().find_map(|file| file.as_deref())
+    }
+
+    /// The engine shows the context of an error before its location.
+    /// These lines contain source code, so their parentheses do not refer to files.
+    fn is_context_line(&self, line: &str) -> bool {
+        matches!(self.state, State::SourceLine(_))
+            || (!self.pending_errors.is_empty()
+                && (line.starts_with('<')
+                    || line.starts_with(' ')
+                    || line.starts_with('\\')
+                    || line.starts_with("l.")))
+    }
+
+    fn flush(&mut self) {
+        let line = std::mem::take(&mut self.buffer);
+        match std::mem::take(&mut self.state) {
+            State::Normal => self.process_line(&line),
+            State::SourceLine(indices) => {
+                let remainder = Some(line.trim()).filter(|text| !text.is_empty());
+                for index in indices {
+                    self.log.errors[index].remainder = remainder.map(String::from);
                 }
+
+                self.changed = true;
             }
-            new_lines.push(new_line);
+            State::BoxContent if line.is_empty() => {}
+            State::BoxContent => self.state = State::BoxContent,
+        };
+    }
+
+    fn process_line(&mut self, line: &str) {
+        let last_message = self.last_message.take();
+        if let Some(captures) = PACKAGE_MESSAGE_REGEX.captures(line) {
+            if let Some(index) = last_message {
+                self.continue_message(index, &captures["msg"]);
+            }
+        } else if let Some(message) = line.strip_prefix("! ") {
+            self.last_message = self.push_error(BuildErrorLevel::Error, message, None);
+            self.pending_errors.extend(self.last_message);
+        } else if !self.pending_errors.is_empty() && ERROR_LOCATION_REGEX.is_match(line) {
+            self.process_error_location(line);
+        } else if self.is_context_line(line) {
+            // The context of an error does not contain any files.
+        } else if let Some(captures) = FILE_LINE_ERROR_REGEX
+            .captures(line)
+            .filter(|captures| is_file_name(&captures["file"]))
+        {
+            self.log.errors.push(BuildError {
+                relative_path: PathBuf::from(&captures["file"]),
+                level: BuildErrorLevel::Error,
+                message: String::from(&captures["msg"]),
+                hint: None,
+                remainder: None,
+                line: parse_line(&captures["line"]),
+                command: None,
+            });
+
+            self.changed = true;
+            self.last_message = Some(self.log.errors.len() - 1);
+            self.pending_errors.extend(self.last_message);
+        } else if let Some(result) = WARNING_REGEX.find(line) {
+            self.scan_files(&line[..result.start()]);
+            let message = &line[result.start()..];
+            let line = INPUT_LINE_REGEX
+                .captures(message)
+                .and_then(|captures| parse_line(&captures["line"]));
+
+            self.last_message = self.push_error(BuildErrorLevel::Warning, message, line);
+        } else if let Some(captures) = BAD_BOX_REGEX.captures(line) {
+            let start = captures.get(0).unwrap().start();
+            self.scan_files(&line[..start]);
+            let message = &line[start..];
+            let line = parse_line(&captures["line"]);
+            self.push_error(BuildErrorLevel::Warning, message, line);
+            self.state = State::BoxContent;
         } else {
-            new_lines.push(line.to_owned());
+            self.scan_files(line);
         }
     }
-    new_lines.join("\n")
-}
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-struct FileRange {
-    pub path: PathBuf,
-    pub start: usize,
-    pub end: usize,
-}
+    fn process_error_location(&mut self, line: &str) {
+        let captures = ERROR_LOCATION_REGEX.captures(line).unwrap();
+        let hint = captures
+            .name("hint")
+            .map(|hint| String::from(hint.as_str().trim()))
+            .filter(|hint| !hint.is_empty());
 
-impl FileRange {
-    fn create(log: &str, result: Match) -> Self {
-        let mut balance = 1;
-        let mut end = result.start() + 1;
-        let chars = log[result.start() + 1..].chars();
-        for c in chars {
-            if balance <= 0 {
-                break;
+        let line = parse_line(&captures["line"]);
+        let indices = std::mem::take(&mut self.pending_errors);
+        for index in &indices {
+            let error = &mut self.log.errors[*index];
+            error.line = line;
+            error.hint = hint.clone();
+            if error.message == "Undefined control sequence." {
+                error.command = hint.as_deref().and_then(find_command).map(String::from);
             }
+        }
+
+        self.changed = true;
+        self.state = State::SourceLine(indices);
+    }
+
+    /// Adds a message to the file that is currently being processed.
+    fn push_error(
+        &mut self,
+        level: BuildErrorLevel,
+        message: &str,
+        line: Option<u32>,
+    ) -> Option<usize> {
+        let relative_path = self.current_file()?.to_path_buf();
+        self.log.errors.push(BuildError {
+            relative_path,
+            level,
+            message: String::from(message),
+            hint: None,
+            remainder: None,
+            line,
+            command: None,
+        });
+
+        self.changed = true;
+        Some(self.log.errors.len() - 1)
+    }
+
+    fn continue_message(&mut self, index: usize, text: &str) {
+        let error = &mut self.log.errors[index];
+        error.message.push(' ');
+        error.message.push_str(text);
+        if error.level == BuildErrorLevel::Warning {
+            error.line = INPUT_LINE_REGEX
+                .captures(text)
+                .and_then(|captures| parse_line(&captures["line"]))
+                .or(error.line);
+        }
 
+        self.last_message = Some(index);
+        self.changed = true;
+    }
+
+    fn scan_files(&mut self, text: &str) {
+        let mut chars = text.char_indices();
+        while let Some((index, c)) = chars.next() {
             if c == '(' {
-                balance += 1;
+                let name = file_name_at(&text[index + 1..]);
+                let file = Some(name)
+                    .filter(|name| is_file_name(name))
+                    .map(|name| PathBuf::from(name.trim_matches('"')));
+
+                // The location of an error always refers to the file that contains it.
+                if file.is_some() {
+                    self.pending_errors.clear();
+                }
+
+                self.files.push(file);
+                chars.nth(name.chars().count().saturating_sub(1));
             } else if c == ')' {
-                balance -= 1;
+                if let Some(Some(_)) = self.files.pop() {
+                    self.pending_errors.clear();
+                }
             }
-            end += c.len_utf8();
-        }
-
-        let captures = FILE_REGEX.captures(result.as_str()).unwrap();
-        let path = PathBuf::from(captures.name("file").unwrap().as_str());
-        Self {
-            path,
-            start: result.start(),
-            end,
         }
     }
+}
 
-    fn len(&self) -> usize {
-        self.end - self.start + 1
+/// Extracts the name of the file that follows an opening parenthesis.
+/// File names that contain spaces are quoted by the engine.
+fn file_name_at(text: &str) -> &str {
+    if let Some(quoted) = text.strip_prefix('"') {
+        let end = quoted.find('"').map_or(text.len(), |end| end + 2);
+        return &text[..end];
     }
 
-    fn contains(&self, index: usize) -> bool {
-        index >= self.start && index <= self.end
-    }
+    let end = text
+        .find(|c: char| c.is_whitespace() || matches!(c, '(' | ')' | '[' | '{' | '<'))
+        .unwrap_or(text.len());
+
+    &text[..end]
 }
 
-impl Ord for FileRange {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.len().cmp(&other.len())
-    }
+/// Checks if the text after an opening parenthesis refers to a file and not just to a remark.
+fn is_file_name(name: &str) -> bool {
+    let name = name.trim_matches('"');
+    let has_extension = Path::new(name)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map_or(false, |extension| {
+            extension.starts_with(|c: char| c.is_ascii_alphabetic())
+        });
+
+    !name.is_empty() && (name.contains('/') || name.contains('\\') || has_extension)
 }
 
-impl PartialOrd for FileRange {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
+/// Extracts the name of the control sequence at the end of the hint.
+fn find_command(hint: &str) -> Option<&str> {
+    let (_, name) = hint.rsplit_once('\\')?;
+    let is_word = name.chars().all(|c| c.is_ascii_alphabetic() || c == '@');
+    Some(name).filter(|name| !name.is_empty() && (is_word || name.chars().count() == 1))
+}
+
+/// Converts a one-based line number into a zero-based line.
+fn parse_line(text: &str) -> Option<u32> {
+    text.parse::<u32>().ok().map(|line| line.saturating_sub(1))
 }
 
 #[cfg(test)]
 mod tests {
-    use super::parse_build_log;
+    use std::path::Path;
+
+    use super::{parse_build_log, BuildLogStream};
 
     #[test]
     fn test_parse() {
@@ -184,4 +320,139 @@ mod tests {
             insta::assert_debug_snapshot!(parse_build_log(&text));
         });
     }
+
+    #[test]
+    fn test_stream() {
+        let mut stream = BuildLogStream::default();
+
+        // The name of the chapter is broken after the maximum line length.
+        let dir = format!("./{}", "a".repeat(64));
+        let line = format!("(./main.tex ({dir}");
+        assert_eq!(line.len(), super::MAX_LINE_LENGTH);
+        assert!(!stream.push(&line));
+        assert_eq!(stream.current_file(), None);
+
+        assert!(!stream.push("/chapter.tex"));
+        let chapter = format!("{dir}/chapter.tex");
+        assert_eq!(stream.current_file(), Some(Path::new(&chapter)));
+
+        let warning = "LaTeX Warning: Reference `foo' on page 1 undefined on input line 3.";
+        assert!(stream.push(warning));
+        assert_eq!(stream.log().errors.len(), 1);
+        assert_eq!(stream.log().errors[0].relative_path, Path::new(&chapter));
+        assert_eq!(stream.log().errors[0].line, Some(2));
+
+        assert!(stream.push("! Undefined control sequence."));
+        assert_eq!(stream.log().errors.len(), 2);
+        assert_eq!(stream.log().errors[1].line, None);
+
+        assert!(stream.push("l.5 \\foo"));
+        assert_eq!(stream.log().errors[1].line, Some(4));
+        assert_eq!(stream.log().errors[1].command.as_deref(), Some("foo"));
+
+        assert!(stream.push("     bar"));
+        assert_eq!(stream.log().errors[1].remainder.as_deref(), Some("bar"));
+
+        assert!(!stream.push(")"));
+        assert_eq!(stream.current_file(), Some(Path::new("./main.tex")));
+
+        assert!(stream.push("Overfull \\hbox (1.0pt too wide) in paragraph at lines 7--8"));
+        assert_eq!(stream.log().errors.len(), 3);
+        assert_eq!(
+            stream.log().errors[2].relative_path,
+            Path::new("./main.tex")
+        );
+        assert_eq!(stream.log().errors[2].line, Some(6));
+
+        // The contents of the box are neither messages nor files.
+        assert!(!stream.push("[]\\OT1/cmr/m/n/10 (foo.tex) bar"));
+        assert!(!stream.push(""));
+        assert_eq!(stream.current_file(), Some(Path::new("./main.tex")));
+
+        assert!(!stream.push(")"));
+        assert_eq!(stream.current_file(), None);
+        assert!(!stream.finish());
+        assert_eq!(stream.log().errors.len(), 3);
+    }
+
+    #[test]
+    fn test_stream_current_file() {
+        let mut stream = BuildLogStream::default();
+        stream.push("(./main.tex (/texmf/article.cls (/texmf/size10.clo)");
+        assert_eq!(
+            stream.current_file().unwrap().to_str(),
+            Some("/texmf/article.cls")
+        );
+
+        stream.push(") (./chapter.tex [1] (foo)");
+        assert_eq!(
+            stream.current_file().unwrap().to_str(),
+            Some("./chapter.tex")
+        );
+
+        assert!(stream.push("! Undefined control sequence."));
+        stream.push("l.3 \\foo");
+        stream.push("");
+        assert_eq!(stream.log().errors.len(), 1);
+
+        stream.push("))");
+        assert_eq!(stream.current_file(), None);
+    }
+
+    #[test]
+    fn test_stream_wrapped_file() {
+        let mut stream = BuildLogStream::default();
+        let dir = "/usr/local/texlive/2023/texmf-dist/tex/latex/l3backend";
+        stream.push(&format!("(./main.tex ({dir}/l3backend-p"));
+        stream.push("dftex.def");
+        assert_eq!(
+            stream.current_file(),
+            Some(Path::new(&format!("{dir}/l3backend-pdftex.def")))
+        );
+
+        stream.push("! Undefined control sequence.");
+        stream.push("l.5 \\foo (bar");
+        stream.push("");
+        assert_eq!(stream.log().errors[0].line, Some(4));
+
+        stream.push(") (./chapter.Rnw");
+        assert_eq!(
+            stream.current_file().unwrap().to_str(),
+            Some("./chapter.Rnw")
+        );
+    }
+
+    #[test]
+    fn test_stream_nested_files() {
+        let mut stream = BuildLogStream::default();
+        let dir = "/texmf/tex/latex/koma-script";
+        stream.push(&format!(
+            "(./main.tex ({dir}/scrlttr2.cls ({dir}/scrkbase.sty"
+        ));
+        stream.push(&format!(")) ({dir}/DIN.lco"));
+        stream.push("! Undefined control sequence.");
+        stream.push("l.84   \\setplength{firstheadvpos}{\\foo");
+        stream.push("");
+        assert_eq!(
+            stream.log().errors[0].relative_path,
+            Path::new(&format!("{dir}/DIN.lco"))
+        );
+
+        stream.push(") (./main.bbl");
+        stream.push("! Undefined control sequence.");
+        stream.push("l.12 \\newblock \\textsl");
+        stream.push("");
+        assert_eq!(
+            stream.log().errors[1].relative_path,
+            Path::new("./main.bbl")
+        );
+        assert_eq!(stream.log().errors[1].line, Some(11));
+
+        stream.push(")");
+        assert!(stream.push("LaTeX Warning: There were undefined references."));
+        assert_eq!(
+            stream.log().errors[2].relative_path,
+            Path::new("./main.tex")
+        );
+    }
 }
@@ -0,0 +1,93 @@
+use std::path::PathBuf;
+
+use rustc_hash::FxHashSet;
+use syntax::FileList;
+
+/// Parses a `.fls` file generated by `latex -recorder`.
+/// Relative paths are resolved against the `PWD` line of the file.
+pub fn parse_file_list(text: &str) -> FileList {
+    let mut file_list = FileList::default();
+    let mut inputs = FxHashSet::default();
+    let mut outputs = FxHashSet::default();
+    for line in text.lines() {
+        let Some((kind, path)) = line.trim_end().split_once(' ') else { continue };
+        match kind {
+            "PWD" => {
+                file_list.working_dir = Some(PathBuf::from(path));
+            }
+            "INPUT" => {
+                let path = resolve(&file_list, path);
+                if inputs.insert(path.clone()) {
+                    file_list.inputs.push(path);
+                }
+            }
+            "OUTPUT" => {
+                let path = resolve(&file_list, path);
+                if outputs.insert(path.clone()) {
+                    file_list.outputs.push(path);
+                }
+            }
+            _ => {}
+        };
+    }
+
+    file_list
+}
+
+fn resolve(file_list: &FileList, path: &str) -> PathBuf {
+    let path = match &file_list.working_dir {
+        Some(dir) => dir.join(path),
+        None => PathBuf::from(path),
+    };
+
+    // Removes the `./` prefix that TeX adds to files found in the current directory.
+    path.components().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::parse_file_list;
+
+    #[test]
+    fn test_parse() {
+        let file_list = parse_file_list(
+            r#"PWD /home/user/thesis
+INPUT /usr/share/texlive/texmf-dist/web2c/texmf.cnf
+INPUT main.tex
+OUTPUT main.log
+INPUT /usr/share/texlive/texmf-dist/tex/latex/base/article.cls
+INPUT ./chapters/intro.tex
+INPUT ./chapters/intro.tex
+INPUT main.aux
+OUTPUT main.aux
+OUTPUT main.pdf"#,
+        );
+
+        assert_eq!(
+            file_list.working_dir,
+            Some(PathBuf::from("/home/user/thesis"))
+        );
+
+        assert_eq!(
+            file_list.inputs,
+            vec![
+                PathBuf::from("/usr/share/texlive/texmf-dist/web2c/texmf.cnf"),
+                PathBuf::from("/home/user/thesis/main.tex"),
+                PathBuf::from("/usr/share/texlive/texmf-dist/tex/latex/base/article.cls"),
+                PathBuf::from("/home/user/thesis/chapters/intro.tex"),
+                PathBuf::from("/home/user/thesis/main.aux"),
+            ]
+        );
+
+        assert_eq!(
+            file_list.outputs,
+            vec![
+                PathBuf::from("/home/user/thesis/main.log"),
+                PathBuf::from("/home/user/thesis/main.aux"),
+                PathBuf::from("/home/user/thesis/main.pdf"),
+            ]
+        );
+    }
+}
@@ -1,4 +1,5 @@
 mod lexer;
+mod reparse;
 
 use rowan::{GreenNode, GreenNodeBuilder};
 use syntax::latex::SyntaxKind::{self, *};
@@ -10,6 +11,8 @@ use self::lexer::{
     Lexer,
 };
 
+pub use self::reparse::reparse_latex;
+
 #[derive(Debug, Clone, Copy)]
 struct ParserContext {
     allow_environment: bool,
@@ -48,7 +48,7 @@ impl<'a> Lexer<'a> {
     }
 }
 
-fn tokenize<'a>(input: &'a str, config: &SyntaxConfig) -> Vec<(Token, &'a str)> {
+pub(super) fn tokenize<'a>(input: &'a str, config: &SyntaxConfig) -> Vec<(Token, &'a str)> {
     let mut lexer = Token::lexer(input);
     std::iter::from_fn(move || {
         let kind = lexer.next()?.unwrap();
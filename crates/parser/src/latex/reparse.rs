@@ -0,0 +1,296 @@
+use rowan::{GreenNode, GreenToken, NodeOrToken, TextLen, TextRange};
+use syntax::latex::{self, SyntaxKind::*};
+
+use crate::SyntaxConfig;
+
+use super::{
+    lexer::{
+        tokenize,
+        types::{CommandName, Token},
+    },
+    Parser,
+};
+
+/// Updates the syntax tree of a LaTeX document after replacing the text in `delete` with `insert`
+/// without parsing the whole document again.
+///
+/// Returns the new tree along with the range of the new text that has been rebuilt
+/// or `None` if the edit crosses structural boundaries and the document needs to be parsed again.
+pub fn reparse_latex(
+    green: &GreenNode,
+    delete: TextRange,
+    insert: &str,
+    config: &SyntaxConfig,
+) -> Option<(GreenNode, TextRange)> {
+    let root = latex::SyntaxNode::new_root(green.clone());
+    if !root.text_range().contains_range(delete) {
+        return None;
+    }
+
+    reparse_tokens(&root, delete, insert, config)
+        .or_else(|| reparse_block(&root, delete, insert, config))
+}
+
+/// The parser decides on the structure of the tree by looking at the token kinds only.
+/// If the edited tokens are lexed into the same kinds as before,
+/// we only need to replace the text of the affected tokens.
+fn reparse_tokens(
+    root: &latex::SyntaxNode,
+    delete: TextRange,
+    insert: &str,
+    config: &SyntaxConfig,
+) -> Option<(GreenNode, TextRange)> {
+    let first = root.token_at_offset(delete.start()).left_biased()?;
+    let last = root.token_at_offset(delete.end()).right_biased()?;
+
+    let prev = prev_token(root, &first);
+    let next = next_token(root, &last);
+    let start = prev.clone().unwrap_or(first);
+    let end = next.clone().unwrap_or(last);
+    let window: Vec<_> = std::iter::successors(Some(start), |token| {
+        (token != &end).then(|| next_token(root, token)).flatten()
+    })
+    .collect();
+
+    let range = TextRange::new(
+        window.first()?.text_range().start(),
+        window.last()?.text_range().end(),
+    );
+
+    let old_text: String = window.iter().map(|token| token.text()).collect();
+    let mut new_text = old_text.clone();
+    new_text.replace_range(
+        std::ops::Range::<usize>::from(delete - range.start()),
+        insert,
+    );
+
+    let old_tokens = tokenize(&old_text, config);
+    let new_tokens = tokenize(&new_text, config);
+    if old_tokens.len() != window.len()
+        || new_tokens.len() != window.len()
+        || old_tokens
+            .iter()
+            .zip(&new_tokens)
+            .any(|((old_kind, _), (new_kind, _))| old_kind != new_kind)
+        || prev.map_or(false, |prev| new_tokens[0].1 != prev.text())
+        || next.map_or(false, |next| new_tokens.last().unwrap().1 != next.text())
+    {
+        return None;
+    }
+
+    let mut green = root.green().into_owned();
+    let mut changed: Option<TextRange> = None;
+    let mut new_end = range.start() + new_text.text_len();
+    for (token, (_, text)) in window.iter().zip(&new_tokens).rev() {
+        let new_range = TextRange::new(new_end - text.text_len(), new_end);
+        new_end = new_range.start();
+        if token.text() == *text {
+            continue;
+        }
+
+        let offset = token.text_range().start();
+        let token = latex::SyntaxNode::new_root(green)
+            .token_at_offset(offset)
+            .right_biased()?;
+
+        green = token.replace_with(GreenToken::new(token.kind().into(), text));
+        changed = Some(changed.map_or(new_range, |range| range.cover(new_range)));
+    }
+
+    let changed = changed.unwrap_or_else(|| TextRange::empty(delete.start()));
+    Some((green, changed))
+}
+
+/// Parses the smallest group or environment containing the edit again
+/// and replaces the old node if the new one spans exactly the same tokens.
+fn reparse_block(
+    root: &latex::SyntaxNode,
+    delete: TextRange,
+    insert: &str,
+    config: &SyntaxConfig,
+) -> Option<(GreenNode, TextRange)> {
+    let node = match root.covering_element(delete) {
+        NodeOrToken::Node(node) => node,
+        NodeOrToken::Token(token) => token.parent()?,
+    };
+
+    let mut candidates = Vec::new();
+    for node in node.ancestors() {
+        // Groups inside of these commands are parsed with different rules.
+        if matches!(
+            node.kind(),
+            COMMAND_DEFINITION | MATH_OPERATOR | ENVIRONMENT_DEFINITION | GRAPHICS_PATH
+        ) {
+            candidates.clear();
+        } else if matches!(node.kind(), CURLY_GROUP | ENVIRONMENT)
+            && node.text_range().start() < delete.start()
+            && delete.end() < node.text_range().end()
+        {
+            candidates.push(node);
+        }
+    }
+
+    candidates.into_iter().find_map(|node| {
+        let range = node.text_range();
+        let mut text = node.text().to_string();
+        text.replace_range(
+            std::ops::Range::<usize>::from(delete - range.start()),
+            insert,
+        );
+
+        // The parser needs to see the following token to decide where the node ends.
+        let next_token = root
+            .token_at_offset(range.end())
+            .right_biased()
+            .filter(|token| token.text_range().start() == range.end());
+
+        let next_text = next_token.as_ref().map_or("", |token| token.text());
+        let input = format!("{text}{next_text}");
+        if next_token.is_some()
+            && tokenize(&input, config)
+                .last()
+                .map_or(true, |(_, text)| *text != next_text)
+        {
+            return None;
+        }
+
+        let green = reparse_node(node.kind(), &input, config)?;
+        if green.text_len() != text.text_len() {
+            return None;
+        }
+
+        let new_range = TextRange::at(range.start(), text.text_len());
+        Some((node.replace_with(green), new_range))
+    })
+}
+
+// `SyntaxToken::next_token` and `SyntaxToken::prev_token` stop at empty nodes.
+fn next_token(root: &latex::SyntaxNode, token: &latex::SyntaxToken) -> Option<latex::SyntaxToken> {
+    root.token_at_offset(token.text_range().end())
+        .right_biased()
+        .filter(|next| next != token)
+}
+
+fn prev_token(root: &latex::SyntaxNode, token: &latex::SyntaxToken) -> Option<latex::SyntaxToken> {
+    root.token_at_offset(token.text_range().start())
+        .left_biased()
+        .filter(|prev| prev != token)
+}
+
+fn reparse_node(kind: latex::SyntaxKind, text: &str, config: &SyntaxConfig) -> Option<GreenNode> {
+    let mut parser = Parser::new(text, config);
+    match (kind, parser.peek()?) {
+        (CURLY_GROUP, Token::LCurly) => parser.curly_group(),
+        (ENVIRONMENT, Token::CommandName(CommandName::BeginEnvironment)) => parser.environment(),
+        _ => return None,
+    };
+
+    let green = parser.builder.finish();
+    let is_closed = latex::SyntaxNode::new_root(green.clone())
+        .children_with_tokens()
+        .any(|element| matches!(element.kind(), R_CURLY | END));
+
+    is_closed.then_some(green)
+}
+
+#[cfg(test)]
+mod tests {
+    use rowan::{TextRange, TextSize};
+    use syntax::latex;
+
+    use crate::{parse_latex, SyntaxConfig};
+
+    use super::reparse_latex;
+
+    fn check(text: &str, delete: std::ops::Range<u32>, insert: &str, incremental: bool) {
+        let config = SyntaxConfig::default();
+        let delete = TextRange::new(TextSize::from(delete.start), TextSize::from(delete.end));
+
+        let mut new_text = String::from(text);
+        new_text.replace_range(std::ops::Range::<usize>::from(delete), insert);
+
+        let expected = latex::SyntaxNode::new_root(parse_latex(&new_text, &config));
+        let actual = reparse_latex(&parse_latex(text, &config), delete, insert, &config)
+            .map(|(green, _)| latex::SyntaxNode::new_root(green));
+
+        assert_eq!(actual.is_some(), incremental);
+        if let Some(actual) = actual {
+            assert_eq!(format!("{actual:#?}"), format!("{expected:#?}"));
+        }
+    }
+
+    #[test]
+    fn test_word() {
+        check("\\section{Foo}\nBar baz.", 19..19, "x", true);
+    }
+
+    #[test]
+    fn test_word_split() {
+        check("\\section{Foo}\nBar baz.", 15..15, " ", false);
+    }
+
+    #[test]
+    fn test_label_name() {
+        check("\\label{foo}\n\\ref{foo}", 8..9, "aa", true);
+    }
+
+    #[test]
+    fn test_command_name_merge() {
+        check("\\foo-bar", 4..5, "x", false);
+    }
+
+    #[test]
+    fn test_command_name_classification() {
+        check("\\bfseries{foo}", 1..9, "section", false);
+    }
+
+    #[test]
+    fn test_comment_swallows_text() {
+        check("\\foo{a} b", 7..8, "%", false);
+    }
+
+    #[test]
+    fn test_group() {
+        check("\\foo{a b}\n\\bar{c}", 6..6, "\\textbf{x}", true);
+    }
+
+    #[test]
+    fn test_environment() {
+        check(
+            "\\begin{a}\n  foo\n\\end{a}\n\\begin{b}\n\\end{b}",
+            12..15,
+            "{\\begin{c}x\\end{c}}",
+            true,
+        );
+    }
+
+    #[test]
+    fn test_unbalanced_group() {
+        check("\\foo{a b}\n\\bar{c}", 6..6, "}", false);
+    }
+
+    #[test]
+    fn test_graphics_path() {
+        check("\\graphicspath{{a}}", 14..14, "\\section{b}", false);
+    }
+
+    #[test]
+    fn test_following_argument() {
+        check("\\begin{a}\n\\end{d{b}", 15..15, "\\foo", false);
+    }
+
+    #[test]
+    fn test_empty_node() {
+        check(
+            "\\documentclass[\\begin{document}]{article}",
+            15..17,
+            "x",
+            false,
+        );
+    }
+
+    #[test]
+    fn test_command_definition() {
+        check("\\newcommand{\\foo}{\\begin{a}}", 27..27, "{x}", false);
+    }
+}
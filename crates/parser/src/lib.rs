@@ -1,6 +1,15 @@
+mod bib_log;
 mod bibtex;
 mod build_log;
 mod config;
+mod file_list;
 mod latex;
 
-pub use self::{bibtex::parse_bibtex, build_log::parse_build_log, config::*, latex::parse_latex};
+pub use self::{
+    bib_log::parse_bib_log,
+    bibtex::parse_bibtex,
+    build_log::{parse_build_log, BuildLogStream},
+    config::*,
+    file_list::parse_file_list,
+    latex::{parse_latex, reparse_latex},
+};
@@ -21,8 +21,11 @@ pub fn document_symbols(workspace: &Workspace, document: &Document) -> Vec<Symbo
         }
         DocumentData::Aux(_)
         | DocumentData::Log(_)
+        | DocumentData::Blg(_)
+        | DocumentData::Fls(_)
         | DocumentData::Root
-        | DocumentData::Tectonic => Vec::new(),
+        | DocumentData::Tectonic(_)
+        | DocumentData::Latexmkrc(_) => Vec::new(),
     };
 
     filter_symbols(&mut symbols, &workspace.config().symbols);
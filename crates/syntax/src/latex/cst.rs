@@ -590,6 +590,18 @@ impl CommandDefinition {
     }
 }
 
+cst_node!(EnvironmentDefinition, ENVIRONMENT_DEFINITION);
+
+impl EnvironmentDefinition {
+    pub fn command(&self) -> Option<SyntaxToken> {
+        self.syntax().first_token()
+    }
+
+    pub fn name(&self) -> Option<CurlyGroupWord> {
+        self.syntax().children().find_map(CurlyGroupWord::cast)
+    }
+}
+
 cst_node!(AcronymReference, ACRONYM_REFERENCE);
 
 impl AcronymReference {
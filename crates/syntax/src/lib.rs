@@ -12,15 +12,53 @@ pub struct BuildError {
     pub relative_path: std::path::PathBuf,
     pub level: BuildErrorLevel,
     pub message: String,
+    /// The text of the source line up to the position of the error.
     pub hint: Option<String>,
+    /// The text of the source line after the position of the error.
+    pub remainder: Option<String>,
     pub line: Option<u32>,
+    /// The name of the control sequence (without `\`) of an `Undefined control sequence` error.
+    pub command: Option<String>,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Default)]
 pub struct BuildLog {
     pub errors: Vec<BuildError>,
 }
 
+/// A message of BibTeX or Biber read from a `.blg` file.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct BibLogError {
+    pub level: BuildErrorLevel,
+    pub message: String,
+    pub target: Option<BibLogTarget>,
+    /// The name of the bibliography file that the message refers to.
+    pub file: Option<std::path::PathBuf>,
+    /// The zero-based line in `file`.
+    pub line: Option<u32>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub enum BibLogTarget {
+    /// The entry with the given key in a bibliography file.
+    Entry(String),
+    /// The citations of the given key in the LaTeX documents.
+    Citation(String),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Default)]
+pub struct BibLog {
+    pub errors: Vec<BibLogError>,
+}
+
+/// The files read and written by a TeX run as recorded by the `-recorder` flag.
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Default)]
+pub struct FileList {
+    pub working_dir: Option<std::path::PathBuf>,
+    pub inputs: Vec<std::path::PathBuf>,
+    pub outputs: Vec<std::path::PathBuf>,
+}
+
 #[macro_export]
 macro_rules! match_ast {
     (match $node:ident { $($tt:tt)* }) => { $crate::match_ast!(match ($node) { $($tt)* }) };
@@ -1,3 +1,4 @@
+pub mod code_action;
 pub mod completion;
 pub mod definition;
 pub mod folding;
@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+
+use base_db::{semantics::tex::LinkKind, Document, Workspace};
+use lsp_types::{
+    CodeAction, CodeActionKind, CodeActionParams, Position, Range, TextEdit, WorkspaceEdit,
+};
+
+use crate::util::components::ComponentDatabase;
+
+/// Offers to load a package that provides an undefined command or environment.
+/// The name is taken from the `data` of diagnostics that are reported
+/// by the undefined command diagnostics or by the build log.
+pub fn find_all(
+    workspace: &Workspace,
+    params: &CodeActionParams,
+    database: &ComponentDatabase,
+) -> Option<Vec<CodeAction>> {
+    let document = workspace.lookup(&params.text_document.uri)?;
+    let parents = workspace.parents(document);
+    let root = parents.first().copied().unwrap_or(document);
+
+    let project = workspace.project(document);
+    let linked = database.linked_components(&project);
+
+    let mut actions = Vec::new();
+    for diagnostic in &params.context.diagnostics {
+        let Some(data) = &diagnostic.data else { continue };
+        let command = data.get("command").and_then(|name| name.as_str());
+        let environment = data.get("environment").and_then(|name| name.as_str());
+
+        let mut packages: Vec<&str> = database
+            .components
+            .iter()
+            .filter(|component| !linked.contains(component))
+            .filter(|component| {
+                command.map_or(false, |name| {
+                    component
+                        .commands
+                        .iter()
+                        .any(|command| command.name == name)
+                }) || environment.map_or(false, |name| {
+                    component
+                        .environments
+                        .iter()
+                        .any(|environment| environment == name)
+                })
+            })
+            .flat_map(|component| component.file_names.iter())
+            .filter_map(|file_name| file_name.strip_suffix(".sty"))
+            .collect();
+
+        packages.sort_unstable();
+        packages.dedup();
+
+        for package in packages {
+            let edit = TextEdit::new(
+                Range::new(package_position(root), package_position(root)),
+                format!("\\usepackage{{{package}}}\n"),
+            );
+
+            actions.push(CodeAction {
+                title: format!("Add \\usepackage{{{package}}}"),
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: Some(vec![diagnostic.clone()]),
+                edit: Some(WorkspaceEdit::new(HashMap::from([(
+                    root.uri.clone(),
+                    vec![edit],
+                )]))),
+                ..CodeAction::default()
+            });
+        }
+    }
+
+    Some(actions)
+}
+
+/// Returns the start of the line after the last package or the document class of the preamble.
+fn package_position(root: &Document) -> Position {
+    let links = root
+        .data
+        .as_tex()
+        .map_or(&[][..], |data| &data.semantics.links);
+
+    let last_link = |kind: LinkKind| links.iter().rev().find(|link| link.kind == kind);
+    let Some(link) = last_link(LinkKind::Sty).or_else(|| last_link(LinkKind::Cls)) else {
+        return Position::new(0, 0);
+    };
+
+    let line = root.line_index.line_col(link.path.range.end()).line;
+    Position::new(line + 1, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use lsp_types::{
+        CodeActionContext, CodeActionParams, Diagnostic, Position, Range, TextDocumentIdentifier,
+        TextEdit,
+    };
+    use test_utils::fixture::Fixture;
+
+    use crate::util::components::ComponentDatabase;
+
+    #[test]
+    fn test_add_package() {
+        let fixture = Fixture::parse(
+            r#"
+%! main.tex
+\documentclass{article}
+\usepackage{foo}
+\begin{document}
+\foo
+\begin{algorithmic}
+\end{algorithmic}
+\end{document}
+"#,
+        );
+
+        let database: ComponentDatabase = serde_json::from_str(
+            r#"{
+                "components": [
+                    {
+                        "fileNames": [],
+                        "references": [],
+                        "commands": [],
+                        "environments": ["document"]
+                    },
+                    {
+                        "fileNames": ["foo.sty"],
+                        "references": [],
+                        "commands": [{ "name": "foo", "parameters": [] }],
+                        "environments": []
+                    },
+                    {
+                        "fileNames": ["algpseudocode.sty"],
+                        "references": [],
+                        "commands": [],
+                        "environments": ["algorithmic"]
+                    }
+                ],
+                "metadata": []
+            }"#,
+        )
+        .unwrap();
+
+        let uri = fixture.documents[0].uri.clone();
+        let diagnostic = |data| Diagnostic {
+            data: Some(data),
+            ..Diagnostic::new_simple(Range::default(), String::new())
+        };
+
+        let params = CodeActionParams {
+            text_document: TextDocumentIdentifier::new(uri.clone()),
+            range: Range::default(),
+            context: CodeActionContext {
+                diagnostics: vec![
+                    diagnostic(serde_json::json!({ "command": "foo" })),
+                    diagnostic(serde_json::json!({ "environment": "algorithmic" })),
+                ],
+                only: None,
+                trigger_kind: None,
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+
+        let actions = super::find_all(&fixture.workspace, &params, &database).unwrap();
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].title, "Add \\usepackage{algpseudocode}");
+
+        let edit = TextEdit::new(
+            Range::new(Position::new(2, 0), Position::new(2, 0)),
+            String::from("\\usepackage{algpseudocode}\n"),
+        );
+
+        let changes = actions[0].edit.as_ref().unwrap().changes.as_ref().unwrap();
+        assert_eq!(changes, &HashMap::from([(uri, vec![edit])]));
+    }
+}
@@ -55,7 +55,7 @@ pub fn complete<'db>(
         for document in &context.project.documents {
             let DocumentData::Tex(data) = &document.data else { continue };
             for graphics_path in &data.semantics.graphics_paths {
-                dirs.push(current_dir(context, &path_text, Some(graphics_path)));
+                dirs.push(current_dir(context, &path_text, Some(&graphics_path.text)));
             }
         }
     }
@@ -94,9 +94,9 @@ fn current_dir(
     let parent = context
         .workspace
         .parents(context.document)
-        .iter()
+        .into_iter()
         .next()
-        .map_or(context.document, Clone::clone);
+        .unwrap_or(context.document);
 
     let path = context
         .workspace
@@ -13,7 +13,7 @@ pub(super) fn goto_definition<'a>(
         .iter()
         .copied()
         .chain(std::iter::once(context.document))
-        .flat_map(|parent| base_db::graph::Graph::new(context.workspace, parent).edges)
+        .flat_map(|parent| context.workspace.graph(parent).edges)
         .filter(|edge| edge.source == context.document)
         .find_map(|edge| {
             let range = edge.weight?.link.path.range;
@@ -41,8 +41,11 @@ pub fn find_all(workspace: &Workspace, uri: &Url) -> Option<Vec<FoldingRange>> {
         }
         DocumentData::Aux(_)
         | DocumentData::Log(_)
+        | DocumentData::Blg(_)
+        | DocumentData::Fls(_)
         | DocumentData::Root
-        | DocumentData::Tectonic => {
+        | DocumentData::Tectonic(_)
+        | DocumentData::Latexmkrc(_) => {
             return None;
         }
     };
@@ -25,9 +25,9 @@ struct LinkBuilder<'a> {
 }
 
 impl<'a> LinkBuilder<'a> {
-    pub fn push(&mut self, range: TextRange, target: &Document) {
+    pub fn push(&mut self, range: TextRange, target: &Url) {
         let range = self.document.line_index.line_col_lsp_range(range);
-        let target = Some(target.uri.clone());
+        let target = Some(target.clone());
         self.links.push(DocumentLink {
             range,
             target,
@@ -1,22 +1,75 @@
+use base_db::graph::{EdgeKind, Graph};
+use lsp_types::Url;
+use rowan::{ast::AstNode, TextRange};
+use rustc_hash::FxHashMap;
+use syntax::latex::{self, HasCurly};
+
 use super::LinkBuilder;
 
 pub(super) fn find_links(builder: &mut LinkBuilder) -> Option<()> {
-    let parent = *builder
+    let parent = builder
         .workspace
         .parents(builder.document)
-        .iter()
+        .into_iter()
         .next()
-        .unwrap_or(&builder.document);
+        .unwrap_or(builder.document);
 
-    let graph = base_db::graph::Graph::new(builder.workspace, parent);
+    let graph = builder.workspace.graph(parent);
 
     for edge in &graph.edges {
         if edge.source == builder.document {
             if let Some(weight) = &edge.weight {
-                builder.push(weight.link.path.range, edge.target);
+                builder.push(weight.link.path.range, &edge.target.uri);
             }
         }
     }
 
+    find_recorded_links(builder, &graph)
+}
+
+/// Links the arguments that name files which are only known from the `.fls` file
+/// (like `\lstinputlisting{code.py}` or `\includestandalone{figure}`).
+fn find_recorded_links(builder: &mut LinkBuilder, graph: &Graph) -> Option<()> {
+    let recorded: FxHashMap<&str, &Url> = graph
+        .edges
+        .iter()
+        .filter(|edge| edge.kind == EdgeKind::Recorder)
+        .map(|edge| &edge.target.uri)
+        .chain(&graph.assets)
+        .flat_map(|uri| {
+            // The extension can usually be omitted.
+            let stem = uri
+                .as_str()
+                .rsplit_once('.')
+                .filter(|(_, extension)| !extension.contains('/'))
+                .map(|(stem, _)| (stem, uri));
+
+            std::iter::once((uri.as_str(), uri)).chain(stem)
+        })
+        .collect();
+
+    if recorded.is_empty() {
+        return Some(());
+    }
+
+    let data = builder.document.data.as_tex()?;
+    let base_dir = builder.workspace.current_dir(&graph.start.dir);
+    for group in data
+        .root_node()
+        .descendants()
+        .filter_map(latex::CurlyGroup::cast)
+    {
+        let Some(text) = group.content_text() else { continue };
+        let Ok(uri) = base_dir.join(text.trim()) else { continue };
+        let Some(target) = recorded.get(uri.as_str()) else { continue };
+        if *target == &builder.document.uri {
+            continue;
+        }
+
+        let start = group.left_curly()?.text_range().end();
+        let end = group.right_curly()?.text_range().start();
+        builder.push(TextRange::new(start, end), target);
+    }
+
     Some(())
 }
@@ -1,3 +1,4 @@
+mod builds;
 mod dispatch;
 mod extensions;
 pub mod options;
@@ -10,8 +11,14 @@ use std::{
 };
 
 use anyhow::Result;
-use base_db::{util::LineCol, Config, Owner, Workspace};
-use commands::{BuildCommand, CleanCommand, CleanTarget, ForwardSearch};
+use base_db::{
+    util::{LineCol, LineColUtf16},
+    Config, Owner, Workspace,
+};
+use commands::{
+    BuildCommand, BuildState, CleanCommand, CleanTarget, ForwardSearch, ForwardSearchViewer,
+    SyncTex,
+};
 use crossbeam_channel::{Receiver, Sender};
 use diagnostics::{DiagnosticManager, DiagnosticSource};
 use distro::{Distro, Language};
@@ -27,6 +34,7 @@ use threadpool::ThreadPool;
 use crate::{
     client::LspClient,
     features::{
+        code_action,
         completion::{self, builder::CompletionItemData},
         definition, folding, formatting, highlight, hover, inlay_hint, link, reference, rename,
         symbols,
@@ -38,9 +46,13 @@ use crate::{
 };
 
 use self::{
+    builds::{BuildMonitor, BuildRegistry},
     extensions::{
-        BuildParams, BuildRequest, BuildResult, BuildStatus, EnvironmentLocation,
-        ForwardSearchRequest, ForwardSearchResult, ForwardSearchStatus, TextWithRange,
+        BuildEngine, BuildParams, BuildPhase, BuildRequest, BuildResult, BuildStateParams,
+        BuildStateRequest, BuildStateResult, BuildStatus, CleanParams, EnvironmentLocation,
+        ForwardSearchRequest, ForwardSearchResult, ForwardSearchStatus, ProjectStatusParams,
+        ProjectStatusRequest, ProjectStatusResult, SynctexForwardRequest, SynctexForwardResult,
+        SynctexInverseParams, SynctexInverseRequest, TextWithRange,
     },
     options::{Options, StartupOptions},
     progress::ProgressReporter,
@@ -49,11 +61,13 @@ use self::{
 #[derive(Debug)]
 enum InternalMessage {
     SetDistro(Distro),
-    SetOptions(Options),
+    SetOptions(Box<Options>),
     FileEvent(notify::Event),
     Diagnostics,
     ChktexResult(Url, Vec<lsp_types::Diagnostic>),
     ForwardSearch(Url, Option<Position>),
+    BuildCycle(Url, Url),
+    BuildOutput(Url, syntax::BuildLog),
 }
 
 pub struct Server {
@@ -68,7 +82,7 @@ pub struct Server {
     chktex_diagnostics: FxHashMap<Url, Vec<Diagnostic>>,
     watcher: FileWatcher,
     pool: ThreadPool,
-    pending_builds: Arc<Mutex<FxHashSet<u32>>>,
+    builds: Arc<BuildRegistry>,
 }
 
 impl Server {
@@ -86,10 +100,10 @@ impl Server {
             client_capabilities: Default::default(),
             client_info: Default::default(),
             chktex_diagnostics: Default::default(),
-            diagnostic_manager: DiagnosticManager::default(),
+            diagnostic_manager: DiagnosticManager::new(&*COMPONENT_DATABASE),
             watcher,
             pool: threadpool::Builder::new().build(),
-            pending_builds: Default::default(),
+            builds: Default::default(),
         }
     }
 
@@ -178,6 +192,7 @@ impl Server {
                 ..Default::default()
             }),
             inlay_hint_provider: Some(OneOf::Left(true)),
+            code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
             ..ServerCapabilities::default()
         }
     }
@@ -333,7 +348,9 @@ impl Server {
                         .parse_options(json.pop().expect("invalid configuration request"))
                         .unwrap();
 
-                    sender.send(InternalMessage::SetOptions(options)).unwrap();
+                    sender
+                        .send(InternalMessage::SetOptions(Box::new(options)))
+                        .unwrap();
                 }
                 Err(why) => {
                     log::error!("Retrieving configuration failed: {}", why);
@@ -343,11 +360,44 @@ impl Server {
     }
 
     fn update_options(&mut self, options: Options) {
+        let viewer = options.forward_search.viewer.clone();
+        let config = Config::from(options);
+        self.validate_build(&config);
+        self.validate_forward_search(viewer.as_deref(), &config);
+
         let mut workspace = self.workspace.write();
-        workspace.set_config(Config::from(options));
+        workspace.set_config(config);
         self.watcher.watch(&mut workspace);
     }
 
+    fn validate_build(&self, config: &Config) {
+        if let Err(why) = BuildCommand::validate(&config.build) {
+            log::warn!("Invalid build configuration: {why}");
+            let _ = self
+                .client
+                .send_notification::<ShowMessage>(ShowMessageParams {
+                    typ: MessageType::WARNING,
+                    message: format!("Invalid build configuration: {why}"),
+                });
+        }
+    }
+
+    fn validate_forward_search(&self, viewer: Option<&str>, config: &Config) {
+        let result = viewer
+            .map_or(Ok(()), |name| name.parse::<ForwardSearchViewer>().map(drop))
+            .and_then(|_| config.synctex.as_ref().map_or(Ok(()), ForwardSearch::validate));
+
+        if let Err(why) = result {
+            log::warn!("Invalid forward search configuration: {why}");
+            let _ = self
+                .client
+                .send_notification::<ShowMessage>(ShowMessageParams {
+                    typ: MessageType::WARNING,
+                    message: format!("Invalid forward search configuration: {why}"),
+                });
+        }
+    }
+
     fn cancel(&self, _params: CancelParams) -> Result<()> {
         Ok(())
     }
@@ -447,6 +497,7 @@ impl Server {
             let params = BuildParams {
                 text_document,
                 position: None,
+                recipe: None,
             };
 
             self.build(None, params)?;
@@ -461,6 +512,61 @@ impl Server {
         Ok(())
     }
 
+    /// Returns `false` if nothing the last build of the project depends on has changed.
+    fn needs_build(workspace: &Workspace, uri: &Url) -> bool {
+        let state = commands::build_state(workspace, uri);
+        if matches!(state, Ok(BuildState::UpToDate)) {
+            log::info!("Skipping build of {uri} because the output is up to date");
+            return false;
+        }
+
+        true
+    }
+
+    fn build_state(&self, id: RequestId, params: BuildStateParams) -> Result<()> {
+        let mut uri = params.text_document.uri;
+        normalize_uri(&mut uri);
+
+        let workspace = Arc::clone(&self.workspace);
+        self.run_fallible(id, move || {
+            let state = commands::build_state(&workspace.read(), &uri)?;
+            Ok(BuildStateResult::from(state))
+        });
+
+        Ok(())
+    }
+
+    fn project_status(&self, id: RequestId, params: ProjectStatusParams) -> Result<()> {
+        let mut uri = params.text_document.uri;
+        normalize_uri(&mut uri);
+
+        let workspace = Arc::clone(&self.workspace);
+        self.run_fallible(id, move || {
+            let workspace = workspace.read();
+            let Some(child) = workspace.lookup(&uri) else { return Ok(None) };
+            let root = workspace.parents(child).into_iter().next().unwrap_or(child);
+
+            let base_dir = workspace.current_dir(&root.dir);
+            let rc = workspace.latexmkrc(&base_dir);
+            let semantics = rc.and_then(|document| document.data.as_latexmkrc());
+            Ok(Some(ProjectStatusResult {
+                root: root.uri.clone(),
+                jobname: workspace.jobname(root),
+                output_dir: workspace.output_dir(&base_dir),
+                aux_dir: workspace.aux_dir(&base_dir),
+                latexmkrc: rc.map(|document| document.uri.clone()),
+                engine: semantics
+                    .and_then(|data| data.semantics.engine())
+                    .map(BuildEngine::from),
+                default_files: semantics
+                    .map(|data| data.semantics.default_files.clone())
+                    .unwrap_or_default(),
+            }))
+        });
+
+        Ok(())
+    }
+
     fn did_close(&mut self, params: DidCloseTextDocumentParams) -> Result<()> {
         let mut uri = params.text_document.uri;
         normalize_uri(&mut uri);
@@ -672,11 +778,11 @@ impl Server {
         match params.command.as_str() {
             "texlab.cleanAuxiliary" => {
                 let command = self.prepare_clean_command(params, CleanTarget::Auxiliary);
-                self.run_fallible(id, || command?.run());
+                self.run_fallible(id, || command.and_then(Self::clean));
             }
             "texlab.cleanArtifacts" => {
                 let command = self.prepare_clean_command(params, CleanTarget::Artifacts);
-                self.run_fallible(id, || command?.run());
+                self.run_fallible(id, || command.and_then(Self::clean));
             }
             "texlab.changeEnvironment" => {
                 let client = self.client.clone();
@@ -696,12 +802,10 @@ impl Server {
                     .send_response(lsp_server::Response::new_ok(id, dot))?;
             }
             "texlab.cancelBuild" => {
-                let pending_builds = Arc::clone(&self.pending_builds);
+                let root = self.prepare_cancel_build(params);
+                let builds = Arc::clone(&self.builds);
                 self.run_fallible(id, move || {
-                    for pid in pending_builds.lock().drain() {
-                        let _ = BuildCommand::cancel(pid);
-                    }
-
+                    builds.cancel(root?.as_ref());
                     Ok(())
                 });
             }
@@ -743,28 +847,50 @@ impl Server {
     }
 
     fn build(&self, id: Option<RequestId>, params: BuildParams) -> Result<()> {
-        static LOCK: Mutex<()> = Mutex::new(());
         static NEXT_TOKEN: AtomicI32 = AtomicI32::new(1);
 
         let mut uri = params.text_document.uri;
         normalize_uri(&mut uri);
 
         let workspace = self.workspace.read();
+        if workspace.config().build.continuous {
+            drop(workspace);
+            return self.build_continuous(id, uri);
+        }
 
         let client = self.client.clone();
 
         let fwd_search_after = workspace.config().build.forward_search_after;
 
-        let (sender, receiver) = crossbeam_channel::unbounded();
-        self.redirect_build_log(receiver);
+        let command = BuildCommand::with_recipe(&workspace, &uri, params.recipe.as_deref());
+        let engine = command
+            .as_ref()
+            .ok()
+            .and_then(BuildCommand::engine)
+            .map(Into::into);
+
+        let root = command.as_ref().map_or(&uri, BuildCommand::root);
+        let project_builds = self.builds.project(root);
+
+        // Builds on save do not need to queue up if there is already one waiting for the project.
+        if id.is_none() && !project_builds.queue_on_save() {
+            return Ok(());
+        }
 
-        let command = BuildCommand::new(&workspace, &uri);
         let internal = self.internal_tx.clone();
         let progress = self.client_capabilities.has_work_done_progress_support();
-        let pending_builds = Arc::clone(&self.pending_builds);
+        let monitor = BuildMonitor::new(client.clone(), internal.clone(), root.clone());
+        let monitor = Arc::new(Mutex::new(monitor));
+        let shared_workspace = Arc::clone(&self.workspace);
 
         self.pool.execute(move || {
-            let guard = LOCK.lock();
+            let guard = project_builds.lock();
+            project_builds.start();
+
+            // Reading the latexmk database can take a while, so builds on save check it here.
+            if id.is_none() && !Self::needs_build(&shared_workspace.read(), &uri) {
+                return;
+            }
 
             let progress_reporter = if progress {
                 let token = NEXT_TOKEN.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
@@ -773,35 +899,52 @@ impl Server {
                 None
             };
 
+            let mut failed_step = None;
             let status = command
                 .and_then(|command| {
-                    let mut process = command.spawn(sender)?;
-                    let pid = process.id();
-                    pending_builds.lock().insert(pid);
-                    let result = process.wait();
-
-                    let status = if pending_builds.lock().remove(&pid) {
-                        if result?.success() {
-                            BuildStatus::SUCCESS
-                        } else {
-                            BuildStatus::ERROR
+                    let steps = command.steps();
+                    for (index, step) in steps.iter().enumerate() {
+                        if let Some(progress_reporter) = &progress_reporter {
+                            let percentage = (index * 100 / steps.len()) as u32;
+                            progress_reporter.report(step.name(), percentage);
+                        }
+
+                        let (sender, receiver) = crossbeam_channel::unbounded();
+                        let reader = BuildMonitor::track(&monitor, step.name(), receiver);
+                        let result = project_builds.run(step, sender);
+                        let _ = reader.join();
+
+                        let status = result.map_err(|why| {
+                            failed_step = Some(String::from(step.name()));
+                            why
+                        })?;
+
+                        if status == BuildStatus::ERROR {
+                            failed_step = Some(String::from(step.name()));
                         }
-                    } else {
-                        BuildStatus::CANCELLED
-                    };
 
-                    Ok(status)
+                        if status != BuildStatus::SUCCESS {
+                            return Ok(status);
+                        }
+                    }
+
+                    Ok(BuildStatus::SUCCESS)
                 })
                 .unwrap_or_else(|why| {
                     log::error!("Failed to compile document \"{uri}\": {why}");
                     BuildStatus::FAILURE
                 });
 
+            monitor.lock().report(BuildPhase::Finished);
             drop(progress_reporter);
             drop(guard);
 
             if let Some(id) = id {
-                let result = BuildResult { status };
+                let result = BuildResult {
+                    status,
+                    engine,
+                    failed_step,
+                };
                 let _ = client.send_response(lsp_server::Response::new_ok(id, result));
             }
 
@@ -813,16 +956,138 @@ impl Server {
         Ok(())
     }
 
-    fn redirect_build_log(&self, receiver: Receiver<String>) {
+    /// Starts a build process that keeps running in the background
+    /// unless there is already one for the project.
+    fn build_continuous(&self, id: Option<RequestId>, uri: Url) -> Result<()> {
+        let workspace = self.workspace.read();
+        let command = BuildCommand::continuous(&workspace, &uri);
+        let engine = command
+            .as_ref()
+            .ok()
+            .and_then(BuildCommand::engine)
+            .map(Into::into);
+
+        let root = command.as_ref().map_or(&uri, BuildCommand::root);
+        let project_builds = self.builds.project(root);
+
         let client = self.client.clone();
+        let internal = self.internal_tx.clone();
+
         self.pool.execute(move || {
-            let typ = MessageType::LOG;
-            for message in receiver {
-                client
-                    .send_notification::<LogMessage>(LogMessageParams { message, typ })
-                    .unwrap();
+            let guard = project_builds.lock();
+            let status = if project_builds.is_continuous() {
+                BuildStatus::SUCCESS
+            } else {
+                command
+                    .and_then(|command| {
+                        let (sender, receiver) = crossbeam_channel::unbounded();
+
+                        // Continuous builds ignore recipes so there is exactly one step.
+                        let mut process = command.steps()[0].spawn(sender)?;
+                        let pid = process.id();
+                        project_builds.start_continuous(pid);
+
+                        let project_builds = Arc::clone(&project_builds);
+                        let root = command.root().clone();
+                        let mut monitor =
+                            BuildMonitor::new(client.clone(), internal.clone(), root.clone());
+
+                        monitor.start_step(command.steps()[0].name());
+                        std::thread::spawn(move || {
+                            for message in receiver {
+                                let is_cycle_end = BuildCommand::is_cycle_end(&message);
+                                monitor.push(message);
+
+                                if is_cycle_end {
+                                    monitor.finish_step();
+                                    monitor.report(BuildPhase::Finished);
+                                    let _ = internal.send(InternalMessage::BuildCycle(
+                                        root.clone(),
+                                        uri.clone(),
+                                    ));
+
+                                    monitor.restart_step();
+                                }
+                            }
+
+                            let _ = process.wait();
+                            project_builds.stop_continuous(pid);
+                        });
+
+                        Ok(BuildStatus::SUCCESS)
+                    })
+                    .unwrap_or_else(|why| {
+                        log::error!("Failed to start continuous build: {why}");
+                        BuildStatus::FAILURE
+                    })
+            };
+
+            drop(guard);
+
+            if let Some(id) = id {
+                let result = BuildResult {
+                    status,
+                    engine,
+                    failed_step: None,
+                };
+
+                let _ = client.send_response(lsp_server::Response::new_ok(id, result));
             }
         });
+
+        Ok(())
+    }
+
+    fn handle_build_output(&mut self, root: Url, log: syntax::BuildLog) {
+        let workspace = self.workspace.read();
+        let Some(document) = workspace.lookup(&root) else { return };
+        self.diagnostic_manager
+            .update_build_output(&workspace, document, &log);
+
+        drop(workspace);
+        self.publish_diagnostics_with_delay();
+    }
+
+    /// Reads the log files of a project again after a continuous build has finished a compilation.
+    fn handle_build_cycle(&mut self, root: Url, uri: Url) -> Result<()> {
+        let mut workspace = self.workspace.write();
+        let Some(document) = workspace.lookup(&root) else { return Ok(()) };
+
+        let graph = workspace.graph(document);
+        let paths: Vec<_> = graph
+            .edges
+            .iter()
+            .map(|edge| edge.target)
+            .filter(|document| matches!(document.language, Language::Log | Language::Blg))
+            .filter_map(|document| document.path.clone())
+            .chain(
+                graph
+                    .missing
+                    .iter()
+                    .filter(|uri| uri.path().ends_with(".log") || uri.path().ends_with(".blg"))
+                    .filter_map(|uri| uri.to_file_path().ok()),
+            )
+            .collect();
+
+        for path in paths {
+            let Some(language) = Language::from_path(&path) else { continue };
+            if workspace.load(&path, language, Owner::Server).is_ok() {
+                if let Some(document) = workspace.lookup_path(&path) {
+                    self.diagnostic_manager.update(&workspace, document);
+                }
+            }
+        }
+
+        let fwd_search_after = workspace.config().build.forward_search_after;
+        drop(workspace);
+        self.publish_diagnostics_with_delay();
+
+        // The cursor may have moved since the build has been started.
+        if fwd_search_after {
+            self.forward_search(None, uri, None)?;
+        }
+
+        Ok(())
     }
 
     fn forward_search(
@@ -834,23 +1099,34 @@ impl Server {
         normalize_uri(&mut uri);
 
         let client = self.client.clone();
-        let command = ForwardSearch::new(
-            &self.workspace.read(),
-            &uri,
-            position.map(|position| position.line),
-        );
+        let workspace = self.workspace.read();
+        let position = workspace
+            .lookup(&uri)
+            .zip(position)
+            .map(|(document, position)| {
+                let position = LineColUtf16 {
+                    line: position.line,
+                    col: position.character,
+                };
+
+                document.line_index.to_utf8(position)
+            });
+
+        let command = ForwardSearch::new(&workspace, &uri, position);
+        drop(workspace);
 
         self.pool.execute(move || {
-            let status = match command.and_then(ForwardSearch::run) {
-                Ok(()) => ForwardSearchStatus::SUCCESS,
+            let (status, message) = match command.and_then(ForwardSearch::run) {
+                Ok(()) => (ForwardSearchStatus::SUCCESS, None),
                 Err(why) => {
                     log::error!("Failed to execute forward search: {why}");
-                    ForwardSearchStatus::from(why)
+                    let message = why.to_string();
+                    (ForwardSearchStatus::from(why), Some(message))
                 }
             };
 
             if let Some(id) = id {
-                let result = ForwardSearchResult { status };
+                let result = ForwardSearchResult { status, message };
                 client
                     .send_response(lsp_server::Response::new_ok(id, result))
                     .unwrap();
@@ -860,9 +1136,59 @@ impl Server {
         Ok(())
     }
 
-    fn code_actions(&self, id: RequestId, _params: CodeActionParams) -> Result<()> {
-        self.client
-            .send_response(lsp_server::Response::new_ok(id, Vec::<CodeAction>::new()))?;
+    fn synctex_forward(&self, id: RequestId, params: TextDocumentPositionParams) -> Result<()> {
+        let mut uri = params.text_document.uri;
+        normalize_uri(&mut uri);
+
+        let workspace = Arc::clone(&self.workspace);
+        self.run_fallible(id, move || {
+            let workspace = workspace.read();
+            let synctex = SyncTex::load(&workspace, &uri)?;
+            let Ok(path) = uri.to_file_path() else { return Ok(None) };
+            let result = synctex
+                .forward(&path, params.position.line)
+                .map(|rect| SynctexForwardResult {
+                    page: rect.page,
+                    x: rect.x,
+                    y: rect.y,
+                    width: rect.width,
+                    height: rect.height,
+                });
+
+            Ok(result)
+        });
+
+        Ok(())
+    }
+
+    fn synctex_inverse(&self, id: RequestId, params: SynctexInverseParams) -> Result<()> {
+        let mut uri = params.text_document.uri;
+        normalize_uri(&mut uri);
+
+        let workspace = Arc::clone(&self.workspace);
+        self.run_fallible(id, move || {
+            let workspace = workspace.read();
+            let synctex = SyncTex::load(&workspace, &uri)?;
+            let result = synctex
+                .inverse(params.page, params.x, params.y)
+                .and_then(|source| {
+                    let mut uri = Url::from_file_path(&source.path).ok()?;
+                    normalize_uri(&mut uri);
+                    let position = Position::new(source.line, source.column.unwrap_or(0));
+                    Some(Location::new(uri, Range::new(position, position)))
+                });
+
+            Ok(result)
+        });
+
+        Ok(())
+    }
+
+    fn code_actions(&self, id: RequestId, mut params: CodeActionParams) -> Result<()> {
+        normalize_uri(&mut params.text_document.uri);
+        self.run_query(id, move |workspace| {
+            code_action::find_all(workspace, &params, &COMPONENT_DATABASE).unwrap_or_default()
+        });
         Ok(())
     }
 
@@ -879,6 +1205,10 @@ impl Server {
         match event.kind {
             notify::EventKind::Create(_) | notify::EventKind::Modify(_) => {
                 for path in event.paths {
+                    if matches!(event.kind, notify::EventKind::Create(_)) {
+                        changed |= workspace.update_graphics(&path);
+                    }
+
                     if workspace
                         .lookup_path(&path)
                         .map_or(true, |document| document.owner == Owner::Server)
@@ -895,6 +1225,7 @@ impl Server {
             }
             notify::EventKind::Remove(_) => {
                 for path in event.paths {
+                    changed |= workspace.update_graphics(&path);
                     if let Some(document) = workspace.lookup_path(&path) {
                         if document.owner == Owner::Server {
                             let uri = document.uri.clone();
@@ -917,7 +1248,33 @@ impl Server {
         &self,
         params: ExecuteCommandParams,
         target: CleanTarget,
-    ) -> Result<CleanCommand> {
+    ) -> Result<(CleanCommand, bool)> {
+        let workspace = self.workspace.read();
+        let mut params = self.parse_command_params::<CleanParams>(params.arguments)?;
+        normalize_uri(&mut params.uri);
+        let Some(document) = workspace.lookup(&params.uri) else {
+            anyhow::bail!("Document {} is not opened!", params.uri)
+        };
+
+        let command = CleanCommand::new(&workspace, document, target)?;
+        Ok((command, params.dry_run))
+    }
+
+    /// Returns the files that have been deleted or would be deleted in a dry run.
+    fn clean((command, dry_run): (CleanCommand, bool)) -> Result<Vec<PathBuf>> {
+        if dry_run {
+            Ok(command.dry_run())
+        } else {
+            command.run()
+        }
+    }
+
+    /// Returns the root document whose builds should be cancelled or `None` to cancel all builds.
+    fn prepare_cancel_build(&self, params: ExecuteCommandParams) -> Result<Option<Url>> {
+        if params.arguments.is_empty() {
+            return Ok(None);
+        }
+
         let workspace = self.workspace.read();
         let mut params = self.parse_command_params::<TextDocumentIdentifier>(params.arguments)?;
         normalize_uri(&mut params.uri);
@@ -925,7 +1282,13 @@ impl Server {
             anyhow::bail!("Document {} is not opened!", params.uri)
         };
 
-        CleanCommand::new(&workspace, document, target)
+        let root = workspace
+            .parents(document)
+            .into_iter()
+            .next()
+            .unwrap_or(document);
+
+        Ok(Some(root.uri.clone()))
     }
 
     fn change_environment(&self, params: ExecuteCommandParams) -> Result<ApplyWorkspaceEditParams> {
@@ -1047,9 +1410,19 @@ impl Server {
                                 })?
                                 .on::<Formatting, _>(|id, params| self.formatting(id, params))?
                                 .on::<BuildRequest, _>(|id, params| self.build(Some(id), params))?
+                                .on::<BuildStateRequest, _>(|id, params| self.build_state(id, params))?
+                                .on::<ProjectStatusRequest, _>(|id, params| {
+                                    self.project_status(id, params)
+                                })?
                                 .on::<ForwardSearchRequest, _>(|id, params| {
                                     self.forward_search(Some(id), params.text_document.uri, Some(params.position))
                                 })?
+                                .on::<SynctexForwardRequest, _>(|id, params| {
+                                    self.synctex_forward(id, params)
+                                })?
+                                .on::<SynctexInverseRequest, _>(|id, params| {
+                                    self.synctex_inverse(id, params)
+                                })?
                                 .on::<ExecuteCommand,_>(|id, params| self.execute_command(id, params))?
                                 .on::<SemanticTokensRangeRequest, _>(|id, params| {
                                     self.semantic_tokens_range(id, params)
@@ -1097,7 +1470,7 @@ impl Server {
                             self.workspace.write().set_distro(distro);
                         }
                         InternalMessage::SetOptions(options) => {
-                            self.update_options(options);
+                            self.update_options(*options);
                         }
                         InternalMessage::FileEvent(event) => {
                             self.handle_file_event(event);
@@ -1112,6 +1485,12 @@ impl Server {
                         InternalMessage::ForwardSearch(uri, position) => {
                             self.forward_search(None, uri, position)?;
                         }
+                        InternalMessage::BuildCycle(root, uri) => {
+                            self.handle_build_cycle(root, uri)?;
+                        }
+                        InternalMessage::BuildOutput(root, log) => {
+                            self.handle_build_output(root, log);
+                        }
                     };
                 }
             };
@@ -1120,9 +1499,10 @@ impl Server {
 
     pub fn run(mut self) -> Result<()> {
         self.initialize()?;
-        self.process_messages()?;
+        let result = self.process_messages();
+        self.builds.cancel(None);
         self.pool.join();
-        Ok(())
+        result
     }
 }
 
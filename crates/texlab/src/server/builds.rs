@@ -0,0 +1,229 @@
+use std::{
+    path::PathBuf,
+    sync::Arc,
+    thread::{self, JoinHandle},
+};
+
+use commands::{BuildCommand, BuildError, BuildStep};
+use crossbeam_channel::{Receiver, Sender};
+use lsp_types::{notification::LogMessage, LogMessageParams, MessageType, Url};
+use parking_lot::{Mutex, MutexGuard};
+use parser::BuildLogStream;
+use rustc_hash::{FxHashMap, FxHashSet};
+use syntax::BuildErrorLevel;
+
+use crate::client::LspClient;
+
+use super::{
+    extensions::{BuildPhase, BuildStatus, BuildStatusNotification, BuildStatusParams},
+    InternalMessage,
+};
+
+/// Keeps track of the running builds of every project.
+/// Builds of the same root document run one after another while different projects
+/// can be built in parallel.
+#[derive(Debug, Default)]
+pub struct BuildRegistry {
+    projects: Mutex<FxHashMap<Url, Arc<ProjectBuilds>>>,
+}
+
+#[derive(Debug, Default)]
+pub struct ProjectBuilds {
+    lock: Mutex<()>,
+    pids: Mutex<FxHashSet<u32>>,
+    queued_on_save: Mutex<bool>,
+    continuous: Mutex<Option<u32>>,
+}
+
+impl BuildRegistry {
+    pub fn project(&self, root: &Url) -> Arc<ProjectBuilds> {
+        let mut projects = self.projects.lock();
+        Arc::clone(projects.entry(root.clone()).or_default())
+    }
+
+    /// Cancels the builds of the given root document or all builds if no root is given.
+    pub fn cancel(&self, root: Option<&Url>) {
+        let projects = self.projects.lock();
+        let projects = projects
+            .iter()
+            .filter(|(uri, _)| root.map_or(true, |root| root == *uri));
+
+        for (_, project) in projects {
+            for pid in project.pids.lock().drain() {
+                let _ = BuildCommand::cancel(pid);
+            }
+        }
+    }
+}
+
+impl ProjectBuilds {
+    /// Blocks until the previous builds of the project have finished.
+    pub fn lock(&self) -> MutexGuard<'_, ()> {
+        self.lock.lock()
+    }
+
+    /// Returns `false` if a build triggered by saving a document is already waiting.
+    /// The waiting build will see the latest changes so there is no need to queue another one.
+    pub fn queue_on_save(&self) -> bool {
+        let mut queued = self.queued_on_save.lock();
+        !std::mem::replace(&mut *queued, true)
+    }
+
+    /// Marks the start of a build so that the next save queues a new build.
+    pub fn start(&self) {
+        *self.queued_on_save.lock() = false;
+    }
+
+    /// Runs a step of a build and waits for it to finish.
+    pub fn run(&self, step: &BuildStep, sender: Sender<String>) -> Result<BuildStatus, BuildError> {
+        let mut process = step.spawn(sender)?;
+        let pid = process.id();
+        self.insert(pid);
+        let result = process.wait();
+
+        let status = if !self.remove(pid) {
+            BuildStatus::CANCELLED
+        } else if result?.success() {
+            BuildStatus::SUCCESS
+        } else {
+            BuildStatus::ERROR
+        };
+
+        Ok(status)
+    }
+
+    pub fn insert(&self, pid: u32) {
+        self.pids.lock().insert(pid);
+    }
+
+    /// Returns `false` if the process has been cancelled in the meantime.
+    pub fn remove(&self, pid: u32) -> bool {
+        self.pids.lock().remove(&pid)
+    }
+
+    pub fn is_continuous(&self) -> bool {
+        self.continuous.lock().is_some()
+    }
+
+    /// Registers the process of a continuous build so that it can be cancelled.
+    pub fn start_continuous(&self, pid: u32) {
+        *self.continuous.lock() = Some(pid);
+        self.insert(pid);
+    }
+
+    pub fn stop_continuous(&self, pid: u32) {
+        let mut continuous = self.continuous.lock();
+        if *continuous == Some(pid) {
+            *continuous = None;
+        }
+
+        self.remove(pid);
+    }
+}
+
+/// Forwards the output of a build to the client and reports the errors while the build is running.
+pub struct BuildMonitor {
+    client: LspClient,
+    internal: Sender<InternalMessage>,
+    root: Url,
+    step: Option<String>,
+    stream: BuildLogStream,
+    current_file: Option<PathBuf>,
+}
+
+impl BuildMonitor {
+    pub fn new(client: LspClient, internal: Sender<InternalMessage>, root: Url) -> Self {
+        Self {
+            client,
+            internal,
+            root,
+            step: None,
+            stream: BuildLogStream::default(),
+            current_file: None,
+        }
+    }
+
+    /// Processes the output of the given step in a background thread.
+    pub fn track(
+        monitor: &Arc<Mutex<Self>>,
+        step: &str,
+        receiver: Receiver<String>,
+    ) -> JoinHandle<()> {
+        monitor.lock().start_step(step);
+        let monitor = Arc::clone(monitor);
+        thread::spawn(move || {
+            for message in receiver {
+                monitor.lock().push(message);
+            }
+
+            monitor.lock().finish_step();
+        })
+    }
+
+    pub fn start_step(&mut self, step: &str) {
+        self.step = Some(String::from(step));
+        self.restart_step();
+    }
+
+    /// Forgets the output of the current step, for example when a continuous build compiles again.
+    pub fn restart_step(&mut self) {
+        self.stream = BuildLogStream::default();
+        self.current_file = None;
+        self.report(BuildPhase::Running);
+    }
+
+    pub fn push(&mut self, message: String) {
+        let changed = self.stream.push(&message);
+
+        let typ = MessageType::LOG;
+        let _ = self
+            .client
+            .send_notification::<LogMessage>(LogMessageParams { message, typ });
+
+        if changed {
+            self.publish_errors();
+        }
+
+        let current_file = self.stream.current_file().map(PathBuf::from);
+        if changed || current_file != self.current_file {
+            self.current_file = current_file;
+            self.report(BuildPhase::Running);
+        }
+    }
+
+    pub fn finish_step(&mut self) {
+        if self.stream.finish() {
+            self.publish_errors();
+        }
+
+        self.current_file = None;
+    }
+
+    pub fn report(&self, phase: BuildPhase) {
+        let errors = &self.stream.log().errors;
+        let count = |level| errors.iter().filter(|error| error.level == level).count();
+
+        let params = BuildStatusParams {
+            uri: self.root.clone(),
+            phase,
+            step: self.step.clone(),
+            current_file: self
+                .current_file
+                .as_deref()
+                .map(|path| path.to_string_lossy().into_owned()),
+            errors: count(BuildErrorLevel::Error),
+            warnings: count(BuildErrorLevel::Warning),
+        };
+
+        let _ = self
+            .client
+            .send_notification::<BuildStatusNotification>(params);
+    }
+
+    fn publish_errors(&self) {
+        let log = self.stream.log().clone();
+        let _ = self
+            .internal
+            .send(InternalMessage::BuildOutput(self.root.clone(), log));
+    }
+}
@@ -1,5 +1,8 @@
-use commands::ForwardSearchError;
-use lsp_types::{Position, Range, TextDocumentIdentifier, TextDocumentPositionParams};
+use base_db::Engine;
+use commands::{BuildState, ForwardSearchError};
+use lsp_types::{
+    Location, Position, Range, TextDocumentIdentifier, TextDocumentPositionParams, Url,
+};
 use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
 
@@ -20,12 +23,21 @@ pub struct BuildParams {
 
     #[serde(default)]
     pub position: Option<Position>,
+
+    #[serde(default)]
+    pub recipe: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct BuildResult {
     pub status: BuildStatus,
+
+    #[serde(default)]
+    pub engine: Option<BuildEngine>,
+
+    #[serde(default)]
+    pub failed_step: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize_repr, Deserialize_repr)]
@@ -37,6 +49,139 @@ pub enum BuildStatus {
     CANCELLED = 3,
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BuildEngine {
+    Pdflatex,
+    Xelatex,
+    Lualatex,
+}
+
+impl From<Engine> for BuildEngine {
+    fn from(engine: Engine) -> Self {
+        match engine {
+            Engine::Pdflatex => Self::Pdflatex,
+            Engine::Xelatex => Self::Xelatex,
+            Engine::Lualatex => Self::Lualatex,
+        }
+    }
+}
+
+pub struct BuildStateRequest;
+
+impl lsp_types::request::Request for BuildStateRequest {
+    type Params = BuildStateParams;
+
+    type Result = BuildStateResult;
+
+    const METHOD: &'static str = "texlab/buildState";
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildStateParams {
+    pub text_document: TextDocumentIdentifier,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildStateResult {
+    pub state: BuildStateKind,
+    pub changed_files: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BuildStateKind {
+    Unknown,
+    Stale,
+    UpToDate,
+}
+
+impl From<BuildState> for BuildStateResult {
+    fn from(state: BuildState) -> Self {
+        match state {
+            BuildState::Unknown => Self {
+                state: BuildStateKind::Unknown,
+                changed_files: Vec::new(),
+            },
+            BuildState::Stale(files) => Self {
+                state: BuildStateKind::Stale,
+                changed_files: files
+                    .iter()
+                    .map(|path| path.to_string_lossy().into_owned())
+                    .collect(),
+            },
+            BuildState::UpToDate => Self {
+                state: BuildStateKind::UpToDate,
+                changed_files: Vec::new(),
+            },
+        }
+    }
+}
+
+pub struct ProjectStatusRequest;
+
+impl lsp_types::request::Request for ProjectStatusRequest {
+    type Params = ProjectStatusParams;
+
+    type Result = Option<ProjectStatusResult>;
+
+    const METHOD: &'static str = "texlab/projectStatus";
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectStatusParams {
+    pub text_document: TextDocumentIdentifier,
+}
+
+/// The build settings of the project that contains a document,
+/// including the values read from a `.latexmkrc` file.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectStatusResult {
+    pub root: Url,
+    pub jobname: Option<String>,
+    pub output_dir: Url,
+    pub aux_dir: Url,
+
+    #[serde(default)]
+    pub latexmkrc: Option<Url>,
+
+    #[serde(default)]
+    pub engine: Option<BuildEngine>,
+
+    #[serde(default)]
+    pub default_files: Vec<String>,
+}
+
+pub struct BuildStatusNotification;
+
+impl lsp_types::notification::Notification for BuildStatusNotification {
+    type Params = BuildStatusParams;
+
+    const METHOD: &'static str = "texlab/buildStatus";
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildStatusParams {
+    pub uri: Url,
+    pub phase: BuildPhase,
+    pub step: Option<String>,
+    pub current_file: Option<String>,
+    pub errors: usize,
+    pub warnings: usize,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BuildPhase {
+    Running,
+    Finished,
+}
+
 pub struct ForwardSearchRequest;
 
 impl lsp_types::request::Request for ForwardSearchRequest {
@@ -64,7 +209,11 @@ impl From<ForwardSearchError> for ForwardSearchStatus {
             ForwardSearchError::InvalidPath(_) => ForwardSearchStatus::ERROR,
             ForwardSearchError::TexNotFound(_) => ForwardSearchStatus::FAILURE,
             ForwardSearchError::PdfNotFound(_) => ForwardSearchStatus::ERROR,
+            ForwardSearchError::UnknownViewer(_) => ForwardSearchStatus::UNCONFIGURED,
+            ForwardSearchError::ViewerNotFound(_) => ForwardSearchStatus::ERROR,
             ForwardSearchError::LaunchViewer(_) => ForwardSearchStatus::ERROR,
+            ForwardSearchError::ViewerFailed { .. } => ForwardSearchStatus::ERROR,
+            ForwardSearchError::Placeholder(_) => ForwardSearchStatus::UNCONFIGURED,
         }
     }
 }
@@ -73,6 +222,58 @@ impl From<ForwardSearchError> for ForwardSearchStatus {
 #[serde(rename_all = "camelCase")]
 pub struct ForwardSearchResult {
     pub status: ForwardSearchStatus,
+
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+pub struct SynctexForwardRequest;
+
+impl lsp_types::request::Request for SynctexForwardRequest {
+    type Params = TextDocumentPositionParams;
+
+    type Result = Option<SynctexForwardResult>;
+
+    const METHOD: &'static str = "texlab/synctexForward";
+}
+
+/// A rectangle on a page of the PDF in PostScript points, starting at the top left corner.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SynctexForwardResult {
+    pub page: u32,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+pub struct SynctexInverseRequest;
+
+impl lsp_types::request::Request for SynctexInverseRequest {
+    type Params = SynctexInverseParams;
+
+    type Result = Option<Location>;
+
+    const METHOD: &'static str = "texlab/synctexInverse";
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SynctexInverseParams {
+    pub text_document: TextDocumentIdentifier,
+    pub page: u32,
+    pub x: f32,
+    pub y: f32,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanParams {
+    pub uri: Url,
+
+    #[serde(default)]
+    pub dry_run: bool,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
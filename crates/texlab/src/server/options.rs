@@ -1,7 +1,8 @@
 use std::path::PathBuf;
 use std::time::Duration;
 
-use base_db::{Config, Formatter, SynctexConfig};
+use base_db::{BuildRecipe, BuildStep, Config, Formatter, SynctexConfig};
+use commands::ForwardSearchViewer;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 
@@ -70,6 +71,39 @@ pub struct BuildOptions {
     pub on_save: bool,
     pub forward_search_after: bool,
     pub filename: Option<String>,
+    pub aux_directory: Option<String>,
+    pub pdf_directory: Option<String>,
+    pub engine_flags: EngineFlagsOptions,
+    pub recipes: Vec<BuildRecipeOptions>,
+    pub recipe: Option<String>,
+    pub continuous: bool,
+    pub continuous_args: Option<Vec<String>>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(default)]
+pub struct EngineFlagsOptions {
+    pub pdflatex: Option<String>,
+    pub xelatex: Option<String>,
+    pub lualatex: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildRecipeOptions {
+    pub name: String,
+    pub steps: Vec<BuildStepOptions>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildStepOptions {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub executable: String,
+    #[serde(default)]
+    pub args: Vec<String>,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
@@ -84,6 +118,7 @@ pub struct ChktexOptions {
 #[serde(rename_all = "camelCase")]
 #[serde(default)]
 pub struct ForwardSearchOptions {
+    pub viewer: Option<String>,
     pub executable: Option<String>,
     pub args: Option<Vec<String>>,
 }
@@ -94,6 +129,15 @@ pub struct ForwardSearchOptions {
 pub struct DiagnosticsOptions {
     pub allowed_patterns: Vec<RegexPattern>,
     pub ignored_patterns: Vec<RegexPattern>,
+    pub undefined_commands: UndefinedCommandsOptions,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(default)]
+pub struct UndefinedCommandsOptions {
+    pub enabled: bool,
+    pub allowed: Vec<String>,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -155,9 +199,47 @@ impl From<Options> for Config {
         config.build.args = value.build.args.unwrap_or(config.build.args);
         config.build.on_save = value.build.on_save;
         config.build.forward_search_after = value.build.forward_search_after;
-        config.build.output_dir = value.aux_directory.unwrap_or_else(|| String::from("."));
+        // `texlab.auxDirectory` predates the separate directories and applies to all output files.
+        config.build.output_dir = value
+            .build
+            .pdf_directory
+            .or_else(|| value.aux_directory.clone())
+            .unwrap_or_else(|| String::from("."));
+
+        config.build.aux_dir = value.build.aux_directory;
         config.build.output_filename = value.build.filename.map(PathBuf::from);
 
+        let engine_flags = &mut config.build.engine_flags;
+        let options = value.build.engine_flags;
+        engine_flags.pdflatex = options.pdflatex.unwrap_or(engine_flags.pdflatex.clone());
+        engine_flags.xelatex = options.xelatex.unwrap_or(engine_flags.xelatex.clone());
+        engine_flags.lualatex = options.lualatex.unwrap_or(engine_flags.lualatex.clone());
+
+        config.build.recipes = value
+            .build
+            .recipes
+            .into_iter()
+            .map(|recipe| BuildRecipe {
+                name: recipe.name,
+                steps: recipe
+                    .steps
+                    .into_iter()
+                    .map(|step| BuildStep {
+                        name: step.name.unwrap_or_else(|| step.executable.clone()),
+                        program: step.executable,
+                        args: step.args,
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        config.build.default_recipe = value.build.recipe;
+        config.build.continuous = value.build.continuous;
+        config.build.continuous_args = value
+            .build
+            .continuous_args
+            .unwrap_or(config.build.continuous_args);
+
         config.diagnostics.allowed_patterns = value
             .diagnostics
             .allowed_patterns
@@ -172,6 +254,14 @@ impl From<Options> for Config {
             .map(|pattern| pattern.0)
             .collect();
 
+        let undefined_commands = value.diagnostics.undefined_commands;
+        config.diagnostics.undefined_commands.enabled = undefined_commands.enabled;
+        config.diagnostics.undefined_commands.allowed = undefined_commands
+            .allowed
+            .into_iter()
+            .map(|name| String::from(name.trim_start_matches('\\')))
+            .collect();
+
         config.diagnostics.delay = value
             .diagnostics_delay
             .map_or(config.diagnostics.delay, Duration::from_millis);
@@ -200,11 +290,18 @@ impl From<Options> for Config {
         config.formatting.latex_indent.local = value.latexindent.local;
         config.formatting.latex_indent.modify_line_breaks = value.latexindent.modify_line_breaks;
 
-        config.synctex = value
-            .forward_search
-            .executable
-            .zip(value.forward_search.args)
-            .map(|(program, args)| SynctexConfig { program, args });
+        let forward_search = value.forward_search;
+        config.synctex = match forward_search
+            .viewer
+            .as_deref()
+            .and_then(|name| name.parse::<ForwardSearchViewer>().ok())
+        {
+            Some(viewer) => Some(viewer.config(forward_search.executable, forward_search.args)),
+            None => forward_search
+                .executable
+                .zip(forward_search.args)
+                .map(|(program, args)| SynctexConfig { program, args }),
+        };
 
         config.symbols.allowed_patterns = value
             .symbols
@@ -1,7 +1,7 @@
 use lsp_types::{
     notification::Progress, request::WorkDoneProgressCreate, NumberOrString, ProgressParams,
     ProgressParamsValue, Url, WorkDoneProgress, WorkDoneProgressBegin,
-    WorkDoneProgressCreateParams, WorkDoneProgressEnd,
+    WorkDoneProgressCreateParams, WorkDoneProgressEnd, WorkDoneProgressReport,
 };
 
 use crate::LspClient;
@@ -41,4 +41,17 @@ impl ProgressReporter {
 
         Self { client, token }
     }
+
+    pub fn report(&self, message: &str, percentage: u32) {
+        let _ = self.client.send_notification::<Progress>(ProgressParams {
+            token: NumberOrString::Number(self.token),
+            value: ProgressParamsValue::WorkDone(WorkDoneProgress::Report(
+                WorkDoneProgressReport {
+                    cancellable: Some(false),
+                    message: Some(String::from(message)),
+                    percentage: Some(percentage),
+                },
+            )),
+        });
+    }
 }
@@ -1,6 +1,7 @@
 use std::io::Read;
 
 use base_db::{semantics::tex::LinkKind, Project};
+use diagnostics::{ComponentProvider, LinkedNames};
 use flate2::read::GzDecoder;
 use itertools::Itertools;
 use lsp_types::{MarkupContent, MarkupKind};
@@ -69,6 +70,27 @@ impl ComponentDatabase {
     }
 }
 
+impl ComponentProvider for ComponentDatabase {
+    fn contains(&self, file_name: &str) -> bool {
+        self.find(file_name).is_some()
+    }
+
+    fn linked_names<'a>(&'a self, project: &Project) -> LinkedNames<'a> {
+        let mut names = LinkedNames::default();
+        for component in self.linked_components(project) {
+            for command in &component.commands {
+                names.commands.insert(command.name.as_str());
+            }
+
+            for environment in &component.environments {
+                names.environments.insert(environment.as_str());
+            }
+        }
+
+        names
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Component {
@@ -146,8 +146,11 @@ impl<'a, T> CursorContext<'a, T> {
             }
             DocumentData::Aux(_)
             | DocumentData::Log(_)
+            | DocumentData::Blg(_)
+            | DocumentData::Fls(_)
             | DocumentData::Root
-            | DocumentData::Tectonic => None,
+            | DocumentData::Tectonic(_)
+            | DocumentData::Latexmkrc(_) => None,
         };
 
         Some(Self {
@@ -1,8 +1,13 @@
-use base_db::{util::filter_regex_patterns, Document, Workspace};
+use std::borrow::Cow;
+
+use base_db::{data::BibtexDialect, util::filter_regex_patterns, Document, Workspace};
 use diagnostics::{
     types::{BibError, Diagnostic, DiagnosticData, TexError},
     DiagnosticBuilder, DiagnosticSource,
 };
+use itertools::Itertools;
+use lsp_types::Url;
+use rowan::TextRange;
 use rustc_hash::FxHashMap;
 use syntax::BuildErrorLevel;
 
@@ -21,7 +26,7 @@ pub fn collect<'db>(
         .map(|(document, diags)| {
             let diags = diags
                 .into_iter()
-                .map(|diag| create_diagnostic(document, &diag))
+                .map(|diag| create_diagnostic(workspace, document, &diag))
                 .collect::<Vec<_>>();
 
             (document, diags)
@@ -29,7 +34,11 @@ pub fn collect<'db>(
         .collect()
 }
 
-fn create_diagnostic(document: &Document, diagnostic: &Diagnostic) -> lsp_types::Diagnostic {
+fn create_diagnostic(
+    workspace: &Workspace,
+    document: &Document,
+    diagnostic: &Diagnostic,
+) -> lsp_types::Diagnostic {
     let range = document.line_index.line_col_lsp_range(diagnostic.range);
 
     let severity = match &diagnostic.data {
@@ -40,6 +49,11 @@ fn create_diagnostic(document: &Document, diagnostic: &Diagnostic) -> lsp_types:
             TexError::UnusedLabel => lsp_types::DiagnosticSeverity::HINT,
             TexError::UndefinedLabel => lsp_types::DiagnosticSeverity::ERROR,
             TexError::UndefinedCitation => lsp_types::DiagnosticSeverity::ERROR,
+            TexError::DuplicateLabel(_) => lsp_types::DiagnosticSeverity::ERROR,
+            TexError::UndefinedCommand(_) => lsp_types::DiagnosticSeverity::WARNING,
+            TexError::UndefinedEnvironment(_) => lsp_types::DiagnosticSeverity::WARNING,
+            TexError::MissingFile(_) => lsp_types::DiagnosticSeverity::ERROR,
+            TexError::IncludeCycle(_) => lsp_types::DiagnosticSeverity::ERROR,
         },
         DiagnosticData::Bib(error) => match error {
             BibError::ExpectingLCurly => lsp_types::DiagnosticSeverity::ERROR,
@@ -48,11 +62,24 @@ fn create_diagnostic(document: &Document, diagnostic: &Diagnostic) -> lsp_types:
             BibError::ExpectingEq => lsp_types::DiagnosticSeverity::ERROR,
             BibError::ExpectingFieldValue => lsp_types::DiagnosticSeverity::ERROR,
             BibError::UnusedEntry => lsp_types::DiagnosticSeverity::HINT,
+            BibError::DuplicateEntry(_) => lsp_types::DiagnosticSeverity::ERROR,
+            BibError::DuplicateString(_) => lsp_types::DiagnosticSeverity::ERROR,
+            BibError::UnknownEntryType(_) => lsp_types::DiagnosticSeverity::WARNING,
+            BibError::UnknownField(BibtexDialect::Bibtex) => lsp_types::DiagnosticSeverity::HINT,
+            BibError::UnknownField(BibtexDialect::Biblatex) => {
+                lsp_types::DiagnosticSeverity::INFORMATION
+            }
+            BibError::UnexpectedField(_) => lsp_types::DiagnosticSeverity::HINT,
+            BibError::MissingField(..) => lsp_types::DiagnosticSeverity::WARNING,
         },
         DiagnosticData::Build(error) => match error.level {
             BuildErrorLevel::Error => lsp_types::DiagnosticSeverity::ERROR,
             BuildErrorLevel::Warning => lsp_types::DiagnosticSeverity::WARNING,
         },
+        DiagnosticData::BibLog(error) => match error.level {
+            BuildErrorLevel::Error => lsp_types::DiagnosticSeverity::ERROR,
+            BuildErrorLevel::Warning => lsp_types::DiagnosticSeverity::WARNING,
+        },
     };
 
     let code = match &diagnostic.data {
@@ -63,6 +90,11 @@ fn create_diagnostic(document: &Document, diagnostic: &Diagnostic) -> lsp_types:
             TexError::UnusedLabel => Some(9),
             TexError::UndefinedLabel => Some(10),
             TexError::UndefinedCitation => Some(11),
+            TexError::DuplicateLabel(_) => Some(13),
+            TexError::UndefinedCommand(_) => Some(19),
+            TexError::UndefinedEnvironment(_) => Some(20),
+            TexError::MissingFile(_) => Some(21),
+            TexError::IncludeCycle(_) => Some(22),
         },
         DiagnosticData::Bib(error) => match error {
             BibError::ExpectingLCurly => Some(4),
@@ -71,34 +103,73 @@ fn create_diagnostic(document: &Document, diagnostic: &Diagnostic) -> lsp_types:
             BibError::ExpectingEq => Some(7),
             BibError::ExpectingFieldValue => Some(8),
             BibError::UnusedEntry => Some(12),
+            BibError::DuplicateEntry(_) => Some(14),
+            BibError::DuplicateString(_) => Some(15),
+            BibError::UnknownEntryType(_) => Some(16),
+            BibError::UnknownField(_) => Some(17),
+            BibError::UnexpectedField(_) => Some(23),
+            BibError::MissingField(..) => Some(18),
         },
-        DiagnosticData::Build(_) => None,
+        DiagnosticData::Build(_) | DiagnosticData::BibLog(_) => None,
     };
 
     let source = match &diagnostic.data {
         DiagnosticData::Tex(_) | DiagnosticData::Bib(_) => "texlab",
         DiagnosticData::Build(_) => "latex",
+        DiagnosticData::BibLog(_) => "bibtex",
     };
 
-    let message = String::from(match &diagnostic.data {
+    let message: Cow<str> = match &diagnostic.data {
         DiagnosticData::Tex(error) => match error {
-            TexError::UnexpectedRCurly => "Unexpected \"}\"",
-            TexError::ExpectingRCurly => "Expecting a curly bracket: \"}\"",
-            TexError::MismatchedEnvironment => "Mismatched environment",
-            TexError::UnusedLabel => "Unused label",
-            TexError::UndefinedLabel => "Undefined reference",
-            TexError::UndefinedCitation => "Undefined reference",
+            TexError::UnexpectedRCurly => "Unexpected \"}\"".into(),
+            TexError::ExpectingRCurly => "Expecting a curly bracket: \"}\"".into(),
+            TexError::MismatchedEnvironment => "Mismatched environment".into(),
+            TexError::UnusedLabel => "Unused label".into(),
+            TexError::UndefinedLabel => "Undefined reference".into(),
+            TexError::UndefinedCitation => "Undefined reference".into(),
+            TexError::DuplicateLabel(_) => "Duplicate label".into(),
+            TexError::UndefinedCommand(_) => "Undefined command".into(),
+            TexError::UndefinedEnvironment(_) => "Undefined environment".into(),
+            TexError::MissingFile(candidates) => {
+                let paths: Vec<_> = candidates.iter().map(display_path).collect();
+                format!("File not found; tried: {}", paths.join(", ")).into()
+            }
+            TexError::IncludeCycle(path) => {
+                let names = path
+                    .iter()
+                    .chain(path.first())
+                    .map(|(uri, _)| display_name(uri));
+
+                format!("Include cycle: {}", names.format(" → ")).into()
+            }
         },
         DiagnosticData::Bib(error) => match error {
-            BibError::ExpectingLCurly => "Expecting a curly bracket: \"{\"",
-            BibError::ExpectingKey => "Expecting a key",
-            BibError::ExpectingRCurly => "Expecting a curly bracket: \"}\"",
-            BibError::ExpectingEq => "Expecting an equality sign: \"=\"",
-            BibError::ExpectingFieldValue => "Expecting a field value",
-            BibError::UnusedEntry => "Unused entry",
+            BibError::ExpectingLCurly => "Expecting a curly bracket: \"{\"".into(),
+            BibError::ExpectingKey => "Expecting a key".into(),
+            BibError::ExpectingRCurly => "Expecting a curly bracket: \"}\"".into(),
+            BibError::ExpectingEq => "Expecting an equality sign: \"=\"".into(),
+            BibError::ExpectingFieldValue => "Expecting a field value".into(),
+            BibError::UnusedEntry => "Unused entry".into(),
+            BibError::DuplicateEntry(_) => "Duplicate entry key".into(),
+            BibError::DuplicateString(_) => "Duplicate string".into(),
+            BibError::UnknownEntryType(dialect) => {
+                format!("Unknown {} entry type", dialect_name(*dialect)).into()
+            }
+            BibError::UnknownField(dialect) => {
+                format!("Unknown {} field", dialect_name(*dialect)).into()
+            }
+            BibError::UnexpectedField(dialect) => {
+                let dialect = dialect_name(*dialect);
+                format!("Field not used by this {dialect} entry type").into()
+            }
+            BibError::MissingField(_, fields) => {
+                let fields: Vec<_> = fields.iter().map(|field| format!("`{field}`")).collect();
+                format!("Missing required field: {}", fields.join(" or ")).into()
+            }
         },
-        DiagnosticData::Build(error) => &error.message,
-    });
+        DiagnosticData::Build(error) => Cow::Borrowed(&error.message),
+        DiagnosticData::BibLog(error) => Cow::Borrowed(&error.message),
+    };
 
     let tags = match &diagnostic.data {
         DiagnosticData::Tex(error) => match error {
@@ -108,6 +179,11 @@ fn create_diagnostic(document: &Document, diagnostic: &Diagnostic) -> lsp_types:
             TexError::UnusedLabel => Some(vec![lsp_types::DiagnosticTag::UNNECESSARY]),
             TexError::UndefinedLabel => None,
             TexError::UndefinedCitation => None,
+            TexError::DuplicateLabel(_) => None,
+            TexError::UndefinedCommand(_) => None,
+            TexError::UndefinedEnvironment(_) => None,
+            TexError::MissingFile(_) => None,
+            TexError::IncludeCycle(_) => None,
         },
         DiagnosticData::Bib(error) => match error {
             BibError::ExpectingLCurly => None,
@@ -116,8 +192,44 @@ fn create_diagnostic(document: &Document, diagnostic: &Diagnostic) -> lsp_types:
             BibError::ExpectingEq => None,
             BibError::ExpectingFieldValue => None,
             BibError::UnusedEntry => Some(vec![lsp_types::DiagnosticTag::UNNECESSARY]),
+            BibError::DuplicateEntry(_) => None,
+            BibError::DuplicateString(_) => None,
+            BibError::UnknownEntryType(_) => None,
+            BibError::UnknownField(_) => None,
+            BibError::UnexpectedField(_) => None,
+            BibError::MissingField(..) => None,
         },
-        DiagnosticData::Build(_) => None,
+        DiagnosticData::Build(_) | DiagnosticData::BibLog(_) => None,
+    };
+
+    let data = match &diagnostic.data {
+        DiagnosticData::Build(error) => error
+            .command
+            .as_ref()
+            .map(|command| serde_json::json!({ "command": command })),
+        DiagnosticData::Tex(TexError::UndefinedCommand(command)) => {
+            Some(serde_json::json!({ "command": command }))
+        }
+        DiagnosticData::Tex(TexError::UndefinedEnvironment(environment)) => {
+            Some(serde_json::json!({ "environment": environment }))
+        }
+        DiagnosticData::Tex(_) | DiagnosticData::Bib(_) | DiagnosticData::BibLog(_) => None,
+    };
+
+    let related_information = match &diagnostic.data {
+        DiagnosticData::Tex(TexError::DuplicateLabel(others)) => {
+            Some(create_related_information(workspace, others, "Label"))
+        }
+        DiagnosticData::Bib(BibError::DuplicateEntry(others)) => {
+            Some(create_related_information(workspace, others, "Entry"))
+        }
+        DiagnosticData::Bib(BibError::DuplicateString(others)) => {
+            Some(create_related_information(workspace, others, "String"))
+        }
+        DiagnosticData::Tex(TexError::IncludeCycle(path)) => {
+            Some(create_cycle_information(workspace, path))
+        }
+        _ => None,
     };
 
     lsp_types::Diagnostic {
@@ -125,10 +237,70 @@ fn create_diagnostic(document: &Document, diagnostic: &Diagnostic) -> lsp_types:
         code: code.map(lsp_types::NumberOrString::Number),
         source: Some(String::from(source)),
         tags,
-        ..lsp_types::Diagnostic::new_simple(range, message)
+        data,
+        related_information,
+        ..lsp_types::Diagnostic::new_simple(range, message.into_owned())
     }
 }
 
+/// Shows the local path of a candidate if possible.
+fn display_path(uri: &Url) -> String {
+    uri.to_file_path()
+        .map_or_else(|_| uri.to_string(), |path| path.display().to_string())
+}
+
+/// Returns the file name of a document for short messages.
+fn display_name(uri: &Url) -> String {
+    uri.to_file_path()
+        .ok()
+        .and_then(|path| Some(path.file_name()?.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| uri.to_string())
+}
+
+fn dialect_name(dialect: BibtexDialect) -> &'static str {
+    match dialect {
+        BibtexDialect::Bibtex => "BibTeX",
+        BibtexDialect::Biblatex => "biblatex",
+    }
+}
+
+/// Points to the other definitions of a duplicate name.
+fn create_related_information(
+    workspace: &Workspace,
+    others: &[(Url, TextRange)],
+    kind: &str,
+) -> Vec<lsp_types::DiagnosticRelatedInformation> {
+    others
+        .iter()
+        .filter_map(|(uri, range)| {
+            let document = workspace.lookup(uri)?;
+            let range = document.line_index.line_col_lsp_range(*range);
+            Some(lsp_types::DiagnosticRelatedInformation {
+                location: lsp_types::Location::new(uri.clone(), range),
+                message: format!("{kind} is also defined here"),
+            })
+        })
+        .collect()
+}
+
+/// Points to every include command of a cycle in the order they are followed.
+fn create_cycle_information(
+    workspace: &Workspace,
+    path: &[(Url, TextRange)],
+) -> Vec<lsp_types::DiagnosticRelatedInformation> {
+    path.iter()
+        .filter_map(|(uri, range)| {
+            let document = workspace.lookup(uri)?;
+            let target = &document.text[*range];
+            let range = document.line_index.line_col_lsp_range(*range);
+            Some(lsp_types::DiagnosticRelatedInformation {
+                location: lsp_types::Location::new(uri.clone(), range),
+                message: format!("{} includes `{target}`", display_name(uri)),
+            })
+        })
+        .collect()
+}
+
 pub fn filter(
     all_diagnostics: &mut FxHashMap<&Document, Vec<lsp_types::Diagnostic>>,
     workspace: &Workspace,
@@ -53,7 +53,13 @@ impl Fixture {
             let language_id = String::from(match language {
                 Language::Tex => "latex",
                 Language::Bib => "bibtex",
-                Language::Aux | Language::Log | Language::Root | Language::Tectonic => continue,
+                Language::Aux
+                | Language::Log
+                | Language::Blg
+                | Language::Fls
+                | Language::Root
+                | Language::Tectonic
+                | Language::Latexmkrc => continue,
             });
 
             client.send_notification::<DidOpenTextDocument>(DidOpenTextDocumentParams {